@@ -97,6 +97,7 @@ export interface Claims {
 }
 
 export enum RoleEnum {
+  Worker = -1,
   Guest = 0,
   Reader = 1,
   Contributor = 2,
@@ -12,6 +12,23 @@ mod m20250131_212358_words;
 mod m20250131_212844_approvals;
 mod m20250315_175307_add_external_id_to_episodes;
 mod m20250315_193802_add_role_to_users;
+mod m20251019_120000_add_audio_url_to_episodes;
+mod m20251019_120100_downloads;
+mod m20251019_130000_feeds;
+mod m20251019_140000_podcasts;
+mod m20251019_140100_add_podcast_to_episodes_and_feeds;
+mod m20251019_150000_transcription_jobs;
+mod m20251019_150100_add_log_to_transcription_jobs;
+mod m20251019_160000_revisions;
+mod m20251019_160100_revision_parts;
+mod m20251019_170000_part_locks;
+mod m20251019_180000_speaker_aliases;
+mod m20251019_180100_speaker_mappings;
+mod m20251019_190000_assignments;
+mod m20251019_200000_comments;
+mod m20251019_210000_glossary_terms;
+mod m20251019_210100_glossary_misrecognitions;
+mod m20251019_220000_merged_episode_speakers;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -28,6 +45,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20250131_212844_approvals::Migration),
             Box::new(m20250315_175307_add_external_id_to_episodes::Migration),
             Box::new(m20250315_193802_add_role_to_users::Migration),
+            Box::new(m20251019_120000_add_audio_url_to_episodes::Migration),
+            Box::new(m20251019_120100_downloads::Migration),
+            Box::new(m20251019_130000_feeds::Migration),
+            Box::new(m20251019_140000_podcasts::Migration),
+            Box::new(m20251019_140100_add_podcast_to_episodes_and_feeds::Migration),
+            Box::new(m20251019_150000_transcription_jobs::Migration),
+            Box::new(m20251019_150100_add_log_to_transcription_jobs::Migration),
+            Box::new(m20251019_160000_revisions::Migration),
+            Box::new(m20251019_160100_revision_parts::Migration),
+            Box::new(m20251019_170000_part_locks::Migration),
+            Box::new(m20251019_180000_speaker_aliases::Migration),
+            Box::new(m20251019_180100_speaker_mappings::Migration),
+            Box::new(m20251019_190000_assignments::Migration),
+            Box::new(m20251019_200000_comments::Migration),
+            Box::new(m20251019_210000_glossary_terms::Migration),
+            Box::new(m20251019_210100_glossary_misrecognitions::Migration),
+            Box::new(m20251019_220000_merged_episode_speakers::Migration),
             // inject-above (do not remove this comment)
         ]
     }
@@ -0,0 +1,29 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "downloads",
+            &[
+                ("url", ColType::String),
+                ("status", ColType::String),
+                ("attempts", ColType::Integer),
+                ("bytes_downloaded", ColType::BigInteger),
+                ("bytes_total", ColType::BigIntegerNull),
+                ("error", ColType::TextNull),
+            ],
+            &[("episode", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "downloads").await
+    }
+}
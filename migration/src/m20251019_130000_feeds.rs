@@ -0,0 +1,29 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "feeds",
+            &[
+                ("url", ColType::String),
+                ("poll_interval", ColType::Integer),
+                ("crop_description_at", ColType::StringNull),
+                ("download_audio", ColType::Boolean),
+                ("last_polled_at", ColType::TimestampWithTimeZoneNull),
+                ("last_error", ColType::TextNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "feeds").await
+    }
+}
@@ -0,0 +1,27 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "podcasts",
+            &[
+                ("title", ColType::String),
+                ("description", ColType::TextNull),
+                ("artwork_url", ColType::StringNull),
+                ("language", ColType::StringNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "podcasts").await
+    }
+}
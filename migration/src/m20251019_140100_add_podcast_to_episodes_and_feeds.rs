@@ -0,0 +1,21 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // Nullable, episodes created before podcasts existed are not assigned yet
+        add_column(m, "episodes", "podcast_id", ColType::IntegerNull).await?;
+        add_column(m, "feeds", "podcast_id", ColType::IntegerNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "feeds", "podcast_id").await?;
+        remove_column(m, "episodes", "podcast_id").await?;
+        Ok(())
+    }
+}
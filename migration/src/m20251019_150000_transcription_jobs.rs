@@ -0,0 +1,29 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "transcription_jobs",
+            &[
+                ("status", ColType::String),
+                ("attempts", ColType::Integer),
+                ("progress", ColType::Double),
+                ("worker_id", ColType::IntegerNull),
+                ("lease_expires_at", ColType::TimestampWithTimeZoneNull),
+                ("error", ColType::TextNull),
+            ],
+            &[("episode", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "transcription_jobs").await
+    }
+}
@@ -0,0 +1,27 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "revision_parts",
+            &[
+                // No reference, the part might not exist anymore
+                ("part_id", ColType::Integer),
+                ("before", ColType::TextNull),
+                ("after", ColType::TextNull),
+            ],
+            &[("revision", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "revision_parts").await
+    }
+}
@@ -0,0 +1,33 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "part_locks",
+            &[("expires_at", ColType::TimestampWithTimeZone)],
+            &[("part", ""), ("user", "")],
+        )
+        .await?;
+
+        // Only one lock per part
+        m.create_index(
+            Index::create()
+                .name("idx-part_locks-part_id")
+                .table(Alias::new("part_locks"))
+                .col(Alias::new("part_id"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "part_locks").await
+    }
+}
@@ -0,0 +1,33 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "speaker_aliases",
+            &[("name", ColType::String)],
+            &[("speaker", "")],
+        )
+        .await?;
+
+        // An alias has to point to exactly one speaker
+        m.create_index(
+            Index::create()
+                .name("idx-speaker_aliases-name")
+                .table(Alias::new("speaker_aliases"))
+                .col(Alias::new("name"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "speaker_aliases").await
+    }
+}
@@ -0,0 +1,34 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "speaker_mappings",
+            &[("label", ColType::String)],
+            &[("podcast", ""), ("speaker", "")],
+        )
+        .await?;
+
+        // One speaker per diarization label and podcast
+        m.create_index(
+            Index::create()
+                .name("idx-speaker_mappings-podcast_id-label")
+                .table(Alias::new("speaker_mappings"))
+                .col(Alias::new("podcast_id"))
+                .col(Alias::new("label"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "speaker_mappings").await
+    }
+}
@@ -0,0 +1,27 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "assignments",
+            &[
+                ("starts_at", ColType::DoubleNull),
+                ("ends_at", ColType::DoubleNull),
+                ("due_at", ColType::TimestampWithTimeZoneNull),
+                ("status", ColType::String),
+            ],
+            &[("episode", ""), ("user", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "assignments").await
+    }
+}
@@ -0,0 +1,30 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "comments",
+            &[
+                ("text", ColType::Text),
+                // Optional range of words the comment is about
+                ("from_word_id", ColType::IntegerNull),
+                ("to_word_id", ColType::IntegerNull),
+                // Replies point to the first comment of their thread
+                ("parent_id", ColType::IntegerNull),
+                ("resolved", ColType::Boolean),
+            ],
+            &[("part", ""), ("user", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "comments").await
+    }
+}
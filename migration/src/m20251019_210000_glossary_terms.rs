@@ -0,0 +1,28 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "glossary_terms",
+            &[
+                // The canonical spelling
+                ("term", ColType::String),
+                ("note", ColType::TextNull),
+                // Terms without a podcast apply to all podcasts
+                ("podcast_id", ColType::IntegerNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "glossary_terms").await
+    }
+}
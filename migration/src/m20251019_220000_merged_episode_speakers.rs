@@ -0,0 +1,34 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "merged_episode_speakers",
+            // The removed episode speaker, which revisions may still refer to
+            &[("merged_id", ColType::Integer)],
+            // The episode speaker that took its parts
+            &[("episode_speaker", "")],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-merged_episode_speakers-merged_id")
+                .table(Alias::new("merged_episode_speakers"))
+                .col(Alias::new("merged_id"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "merged_episode_speakers").await
+    }
+}
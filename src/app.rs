@@ -16,7 +16,13 @@ use std::path::Path;
 
 #[allow(unused_imports)]
 use crate::{
-    controllers, initializers, models::_entities::users, tasks, workers::downloader::DownloadWorker,
+    controllers, initializers,
+    models::_entities::users,
+    tasks,
+    workers::{
+        downloader::DownloadWorker, feed_sync::FeedSyncWorker, transcriber::TranscriptionWorker,
+        waveform::WaveformWorker,
+    },
 };
 
 pub struct App;
@@ -45,9 +51,10 @@ impl Hooks for App {
     }
 
     async fn initializers(_ctx: &AppContext) -> Result<Vec<Box<dyn Initializer>>> {
-        Ok(vec![Box::new(
-            initializers::tantivy_search::TantivySearchInitializer,
-        )])
+        Ok(vec![
+            Box::new(initializers::tantivy_search::TantivySearchInitializer),
+            Box::new(initializers::spelling::SpellingInitializer),
+        ])
     }
 
     fn routes(_ctx: &AppContext) -> AppRoutes {
@@ -56,19 +63,37 @@ impl Hooks for App {
             .add_route(controllers::words::routes())
             .add_route(controllers::sentences::routes())
             .add_route(controllers::parts::routes())
+            .add_route(controllers::revisions::routes())
+            .add_route(controllers::comments::routes())
+            .add_route(controllers::review_queue::routes())
+            .add_route(controllers::find_replace::routes())
+            .add_route(controllers::glossary::routes())
+            .add_route(controllers::lint::routes())
+            .add_route(controllers::assignments::routes())
+            .add_route(controllers::downloads::routes())
             .add_route(controllers::episode_speakers::routes())
+            .add_route(controllers::speaker_aliases::routes())
+            .add_route(controllers::speaker_mappings::routes())
             .add_route(controllers::speakers::routes())
             .add_route(controllers::episodes::routes())
+            .add_route(controllers::feeds::routes())
+            .add_route(controllers::podcasts::routes())
+            .add_route(controllers::transcription_jobs::routes())
             .add_route(controllers::auth::routes())
     }
 
     async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
         queue.register(DownloadWorker::build(ctx)).await?;
+        queue.register(FeedSyncWorker::build(ctx)).await?;
+        queue.register(TranscriptionWorker::build(ctx)).await?;
+        queue.register(WaveformWorker::build(ctx)).await?;
         Ok(())
     }
 
-    #[allow(unused_variables)]
     fn register_tasks(tasks: &mut Tasks) {
+        tasks.register(tasks::create_worker::CreateWorker);
+        tasks.register(tasks::requeue_transcription_jobs::RequeueTranscriptionJobs);
+        tasks.register(tasks::sync_feeds::SyncFeeds);
         // tasks-inject (do not remove)
     }
 
@@ -0,0 +1,150 @@
+use std::io::Cursor;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+pub use symphonia::core::errors::Error as AudioError;
+
+/// Decodes an audio file (mp3, wav, ...) and hands the samples, mixed down to
+/// mono, to `on_samples` in the order they appear in the file.
+///
+/// The callback receives the sample rate and a chunk of samples. Returning
+/// `ControlFlow::Break` stops decoding early, e.g. once a clip is complete.
+///
+/// Returns the sample rate of the decoded track.
+pub fn decode_mono<F>(content: Vec<u8>, mut on_samples: F) -> Result<u32, AudioError>
+where
+    F: FnMut(u32, &[f32]) -> ControlFlow<()>,
+{
+    let source = MediaSourceStream::new(Box::new(Cursor::new(content)), Default::default());
+    let probed = symphonia::default::get_probe().format(
+        &Hint::new(),
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(AudioError::Unsupported("no audio track found"))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut mono: Vec<f32> = vec![];
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(e),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A broken frame should not ruin the whole file, just skip it
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(e),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+
+        mono.clear();
+        mono.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+
+        if on_samples(sample_rate, &mono).is_break() {
+            break;
+        }
+    }
+
+    Ok(sample_rate)
+}
+
+/// Location of an episode's uploaded audio file in the storage
+pub fn episode_audio_path(episode_id: i32) -> PathBuf {
+    PathBuf::from("episodes").join(format!("{}.mp3", episode_id))
+}
+
+/// Cuts the range between `start` and `end` (in seconds) out of an audio file
+/// and returns it as a mono 16 bit WAV file. `fade` seconds at the beginning
+/// and the end are faded in and out.
+pub fn cut_clip(content: Vec<u8>, start: f64, end: f64, fade: f64) -> Result<Vec<u8>, AudioError> {
+    let mut clip: Vec<f32> = vec![];
+    let mut position: u64 = 0;
+    let sample_rate = decode_mono(content, |sample_rate, samples| {
+        let first = (start * f64::from(sample_rate)) as u64;
+        let last = (end * f64::from(sample_rate)) as u64;
+        for sample in samples {
+            if position >= last {
+                return ControlFlow::Break(());
+            }
+            if position >= first {
+                clip.push(*sample);
+            }
+            position += 1;
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    let fade_samples = ((fade * f64::from(sample_rate)) as usize).min(clip.len() / 2);
+    let length = clip.len();
+    for i in 0..fade_samples {
+        let factor = i as f32 / fade_samples as f32;
+        clip[i] *= factor;
+        clip[length - 1 - i] *= factor;
+    }
+
+    Ok(encode_wav(sample_rate, &clip))
+}
+
+/// Encodes mono samples as 16 bit PCM WAV file
+pub fn encode_wav(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    // Format chunk: PCM, one channel, 16 bits per sample
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+
+    wav
+}
@@ -1,6 +1,22 @@
 use crate::models::users::Model;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use loco_rs::auth::jwt::JWT;
+use loco_rs::controller::middleware::auth;
+use loco_rs::model::Authenticable;
 use loco_rs::prelude::*;
 
+/// Role of the accounts transcription workers use. It only grants access to
+/// the worker endpoints of transcription jobs.
+pub const ROLE_WORKER: i32 = -1;
+
+pub fn check_worker(user: &Model) -> Result<()> {
+    if user.role == ROLE_WORKER {
+        return Ok(());
+    }
+    Err(Error::Unauthorized("Unauthorized".into()))
+}
+
 pub fn check_admin(user: &Model) -> Result<()> {
     if user.role >= 3 {
         return Ok(());
@@ -21,3 +37,38 @@ pub fn check_reader(user: &Model) -> Result<()> {
     }
     Err(Error::Unauthorized("Unauthorized".into()))
 }
+
+/// Name of the query parameter and the cookie an `EventStreamAuth` token may
+/// come from
+pub const TOKEN_PARAMETER: &str = "token";
+
+/// Like `JWTWithUser`, but the token may also be given in the `token` query
+/// parameter or cookie, as browsers can't send headers with an `EventSource`
+pub struct EventStreamAuth {
+    pub user: Model,
+}
+
+impl<S> FromRequestParts<S> for EventStreamAuth
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let ctx = AppContext::from_ref(state);
+        let token = auth::extract_token_from_header(&parts.headers)
+            .ok()
+            .or_else(|| auth::extract_token_from_query(TOKEN_PARAMETER, parts).ok())
+            .or_else(|| auth::extract_token_from_cookie(TOKEN_PARAMETER, parts).ok())
+            .ok_or_else(|| Error::Unauthorized("token is not found".to_string()))?;
+        let jwt = ctx.config.get_jwt_config()?;
+        let claims = JWT::new(&jwt.secret)
+            .validate(&token)
+            .map_err(|_| Error::Unauthorized("token is not valid".to_string()))?;
+        let user = Model::find_by_claims_key(&ctx.db, &claims.claims.pid)
+            .await
+            .map_err(|_| Error::Unauthorized("token is not valid".to_string()))?;
+        Ok(Self { user })
+    }
+}
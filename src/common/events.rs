@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+use crate::models::part_locks::LockDisplay;
+
+/// Events a slow client may fall behind before it misses some. It has to
+/// reload the episode then.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Shared by all requests and workers of the process. Clients connected to
+/// another process (e.g. behind a load balancer) do not get the events.
+static CHANNEL: OnceLock<broadcast::Sender<EpisodeEvent>> = OnceLock::new();
+
+/// Something changed in an episode that other viewers should know about
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EpisodeEvent {
+    pub episode_id: i32,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    PartCreated { part_id: i32 },
+    PartUpdated { part_id: i32 },
+    PartDeleted { part_id: i32 },
+    ApprovalChanged { part_id: i32, approvals: u32 },
+    LockAcquired { locks: Vec<LockDisplay> },
+    LockReleased { part_ids: Vec<i32> },
+    SpeakersChanged,
+    CommentsChanged { part_id: i32 },
+}
+
+impl EventKind {
+    /// Name of the server-sent event
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PartCreated { .. } => "part_created",
+            Self::PartUpdated { .. } => "part_updated",
+            Self::PartDeleted { .. } => "part_deleted",
+            Self::ApprovalChanged { .. } => "approval_changed",
+            Self::LockAcquired { .. } => "lock_acquired",
+            Self::LockReleased { .. } => "lock_released",
+            Self::SpeakersChanged => "speakers_changed",
+            Self::CommentsChanged { .. } => "comments_changed",
+        }
+    }
+}
+
+fn channel() -> &'static broadcast::Sender<EpisodeEvent> {
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Sends the event to everybody subscribed. Nobody listening is fine.
+pub fn publish(episode_id: i32, kind: EventKind) {
+    let _ = channel().send(EpisodeEvent { episode_id, kind });
+}
+
+/// Events of all episodes from now on
+pub fn subscribe() -> broadcast::Receiver<EpisodeEvent> {
+    channel().subscribe()
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, FixedOffset};
+use feed_rs::parser::{self, ParseFeedError};
+use std::path::Path;
+
+/// Line width for the description text. Wide enough to not wrap paragraphs.
+const DESCRIPTION_WIDTH: usize = 10_000;
+
+/// An episode as announced in a podcast feed (RSS or Atom)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedItem {
+    pub guid: String,
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    pub published_at: Option<DateTime<FixedOffset>>,
+    pub audio_url: Option<String>,
+    /// Name of the audio file without extension, e.g. `episode-32` for
+    /// `https://example.com/files/episode-32.mp3?source=rss`
+    pub filename: String,
+}
+
+/// Parses a feed. The description is converted to plain text and everything
+/// starting at `crop_description_at` is removed from it.
+pub fn parse(
+    content: &[u8],
+    crop_description_at: Option<&str>,
+) -> Result<Vec<FeedItem>, ParseFeedError> {
+    let feed = parser::parse(content)?;
+
+    let items = feed
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let audio_url = entry
+                .media
+                .iter()
+                .flat_map(|x| x.content.iter())
+                .find_map(|x| x.url.clone());
+
+            let filename = audio_url
+                .as_ref()
+                .and_then(|x| x.path_segments()?.next_back().map(String::from))
+                .and_then(|x| Path::new(&x).file_stem()?.to_str().map(String::from))
+                .unwrap_or_else(|| entry.id.clone());
+
+            let description_html = entry
+                .summary
+                .map(|x| x.content)
+                .or_else(|| entry.content.and_then(|x| x.body))
+                .unwrap_or_default();
+
+            FeedItem {
+                guid: entry.id,
+                title: entry.title.map(|x| x.content).unwrap_or_default(),
+                link: entry
+                    .links
+                    .first()
+                    .map(|x| x.href.clone())
+                    .unwrap_or_default(),
+                description: to_text(&description_html, crop_description_at),
+                published_at: entry.published.or(entry.updated).map(Into::into),
+                audio_url: audio_url.map(String::from),
+                filename,
+            }
+        })
+        .collect();
+
+    Ok(items)
+}
+
+fn to_text(html: &str, crop_at: Option<&str>) -> String {
+    let text = html2text::from_read(html.as_bytes(), DESCRIPTION_WIDTH);
+    let text = match crop_at.filter(|x| !x.is_empty()) {
+        Some(marker) => text.split(marker).next().unwrap_or_default(),
+        None => &text,
+    };
+
+    text.trim().to_string()
+}
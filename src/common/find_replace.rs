@@ -0,0 +1,262 @@
+use sea_orm::sea_query::{Expr, Func, LikeExpr};
+use sea_orm::{entity::prelude::*, JoinType, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::common::part_operations::{replaced_texts, WordReplacement};
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::words as WordsNS;
+
+/// Largest number of ids in one `IN (...)`, databases limit the number of
+/// bound parameters
+pub const CHUNK_SIZE: usize = 500;
+
+/// Where to search. Without an episode or podcast all episodes are searched.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Scope {
+    pub episode_id: Option<i32>,
+    pub podcast_id: Option<i32>,
+}
+
+/// Words to find and what to replace them with. Punctuation around the words
+/// is ignored when matching and kept when replacing.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    find: Vec<String>,
+    replace: Vec<String>,
+    case_sensitive: bool,
+}
+
+/// A sentence with all replacements in it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SentenceMatch {
+    pub episode_id: i32,
+    pub part_id: i32,
+    pub sentence_id: i32,
+    pub replacements: Vec<WordReplacement>,
+    pub before: String,
+    pub after: String,
+}
+
+fn leading_punctuation(text: &str) -> &str {
+    let rest = text.trim_start_matches(|c: char| !c.is_alphanumeric());
+    &text[..text.len() - rest.len()]
+}
+
+fn trailing_punctuation(text: &str) -> &str {
+    let rest = text.trim_end_matches(|c: char| !c.is_alphanumeric());
+    &text[rest.len()..]
+}
+
+impl Pattern {
+    pub fn new(
+        find: &str,
+        replace: &str,
+        case_sensitive: bool,
+    ) -> std::result::Result<Self, &'static str> {
+        let mut pattern = Self {
+            find: vec![],
+            replace: replace.split_whitespace().map(String::from).collect(),
+            case_sensitive,
+        };
+        pattern.find = find
+            .split_whitespace()
+            .map(|x| pattern.normalize(x))
+            .collect();
+        if pattern.find.is_empty() || pattern.find.iter().any(String::is_empty) {
+            return Err("Search for words, not only punctuation");
+        }
+        if pattern.replace.is_empty() {
+            return Err("Replacement is empty, hide the words instead");
+        }
+        Ok(pattern)
+    }
+
+    /// The longest run of ASCII characters in the words to find, escaped for
+    /// `LIKE`. SQLite only folds the case of ASCII letters, so other
+    /// characters can't narrow down the sentences.
+    fn like_fragment(&self) -> Option<String> {
+        let fragment = self
+            .find
+            .iter()
+            .flat_map(|x| x.split(|c: char| !c.is_ascii()))
+            .max_by_key(|x| x.len())
+            .filter(|x| !x.is_empty())?;
+        let mut output = String::new();
+        for c in fragment.chars() {
+            if matches!(c, '\\' | '%' | '_') {
+                output.push('\\');
+            }
+            output.push(c);
+        }
+        Some(output)
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        let text = text.trim_matches(|c: char| !c.is_alphanumeric());
+        if self.case_sensitive {
+            text.to_string()
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    /// Replacement texts for the matched words
+    fn texts(&self, matched: &[&WordsNS::Model]) -> Vec<String> {
+        let mut texts = self.replace.clone();
+        if let (Some(first_word), Some(first)) = (matched.first(), texts.first_mut()) {
+            let prefix = leading_punctuation(first_word.effective_text());
+            if !first.starts_with(prefix) {
+                first.insert_str(0, prefix);
+            }
+        }
+        if let (Some(last_word), Some(last)) = (matched.last(), texts.last_mut()) {
+            let suffix = trailing_punctuation(last_word.effective_text());
+            if !last.ends_with(suffix) {
+                last.push_str(suffix);
+            }
+        }
+        texts
+    }
+
+    /// Replacements within the words of one sentence. Matches do not overlap
+    /// and words that already read like the replacement are left alone.
+    pub fn replacements(&self, words: &[WordsNS::Model]) -> Vec<WordReplacement> {
+        let visible: Vec<&WordsNS::Model> = words.iter().filter(|x| !x.hidden).collect();
+        let length = self.find.len();
+        let mut output = vec![];
+        let mut index = 0;
+        while index + length <= visible.len() {
+            let matched = &visible[index..index + length];
+            if !matched
+                .iter()
+                .zip(&self.find)
+                .all(|(word, find)| self.normalize(word.effective_text()) == *find)
+            {
+                index += 1;
+                continue;
+            }
+            let texts = self.texts(matched);
+            let unchanged = texts.len() == length
+                && matched
+                    .iter()
+                    .zip(&texts)
+                    .all(|(word, text)| word.effective_text() == text);
+            if !unchanged {
+                output.push(WordReplacement {
+                    word_ids: matched.iter().map(|x| x.id).collect(),
+                    texts,
+                });
+            }
+            index += length;
+        }
+        output
+    }
+}
+
+/// Text of a sentence with the replacements applied, like it is stored
+fn sentence_text(words: &[WordsNS::Model], replacements: &[WordReplacement]) -> String {
+    let mut replaced = HashMap::<i32, Option<String>>::new();
+    for replacement in replacements {
+        let texts = replaced_texts(replacement.word_ids.len(), &replacement.texts);
+        replaced.extend(replacement.word_ids.iter().copied().zip(texts));
+    }
+    words
+        .iter()
+        .filter(|x| !x.hidden)
+        .filter_map(|x| match replaced.get(&x.id) {
+            Some(text) => text.clone(),
+            None => Some(x.effective_text().to_string()),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Sentences of the scope in which the pattern matches, in the order they
+/// are spoken. Phrases do not match across sentences.
+pub async fn find_matches<C: ConnectionTrait>(
+    db: &C,
+    scope: &Scope,
+    pattern: &Pattern,
+) -> Result<Vec<SentenceMatch>, DbErr> {
+    let mut query =
+        SentencesNS::Entity::find().join(JoinType::InnerJoin, SentencesNS::Relation::Parts.def());
+    // Only sentences containing the longest ASCII run can match
+    if let Some(fragment) = pattern.like_fragment() {
+        let text = Expr::col((SentencesNS::Entity, SentencesNS::Column::Text));
+        let like = LikeExpr::new(format!("%{fragment}%")).escape('\\');
+        query = query.filter(if pattern.case_sensitive {
+            text.like(like)
+        } else {
+            Expr::expr(Func::lower(text)).like(like)
+        });
+    }
+    if let Some(episode_id) = scope.episode_id {
+        query = query.filter(PartsNS::Column::EpisodeId.eq(episode_id));
+    }
+    if let Some(podcast_id) = scope.podcast_id {
+        query = query
+            .join(JoinType::InnerJoin, PartsNS::Relation::Episodes.def())
+            .filter(EpisodesNS::Column::PodcastId.eq(podcast_id));
+    }
+    let sentences = query
+        .order_by_asc(PartsNS::Column::EpisodeId)
+        .order_by_asc(PartsNS::Column::StartsAt)
+        .order_by_asc(SentencesNS::Column::StartsAt)
+        .order_by_asc(SentencesNS::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut output = vec![];
+    for sentences in sentences.chunks(CHUNK_SIZE) {
+        output.extend(match_sentences(db, sentences, pattern).await?);
+    }
+    Ok(output)
+}
+
+/// Matches within sentences of few enough parts to look them up at once
+async fn match_sentences<C: ConnectionTrait>(
+    db: &C,
+    sentences: &[SentencesNS::Model],
+    pattern: &Pattern,
+) -> Result<Vec<SentenceMatch>, DbErr> {
+    let episodes: HashMap<i32, i32> = PartsNS::Entity::find()
+        .filter(PartsNS::Column::Id.is_in(sentences.iter().map(|x| x.part_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.episode_id))
+        .collect();
+    let mut words = BTreeMap::<i32, Vec<WordsNS::Model>>::new();
+    for word in WordsNS::Entity::find()
+        .filter(WordsNS::Column::SentenceId.is_in(sentences.iter().map(|x| x.id)))
+        .order_by_asc(WordsNS::Column::StartsAt)
+        .order_by_asc(WordsNS::Column::EndsAt)
+        .order_by_asc(WordsNS::Column::Id)
+        .all(db)
+        .await?
+    {
+        words.entry(word.sentence_id).or_default().push(word);
+    }
+
+    Ok(sentences
+        .iter()
+        .filter_map(|sentence| {
+            let words = words.get(&sentence.id)?;
+            let replacements = pattern.replacements(words);
+            if replacements.is_empty() {
+                return None;
+            }
+            Some(SentenceMatch {
+                episode_id: *episodes.get(&sentence.part_id)?,
+                part_id: sentence.part_id,
+                sentence_id: sentence.id,
+                before: sentence_text(words, &[]),
+                after: sentence_text(words, &replacements),
+                replacements,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,120 @@
+use sea_orm::{entity::prelude::*, Condition, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::common::find_replace::Pattern;
+use crate::common::part_operations::WordReplacement;
+use crate::models::_entities::glossary_misrecognitions as GlossaryMisrecognitionsNS;
+use crate::models::_entities::glossary_terms as GlossaryTermsNS;
+use crate::models::_entities::words as WordsNS;
+
+/// A term with the ways it was misrecognized before
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TermDisplay {
+    pub term: GlossaryTermsNS::Model,
+    pub misrecognitions: Vec<String>,
+}
+
+/// Words that likely are a misrecognized glossary term
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub glossary_term_id: i32,
+    pub term: String,
+    pub sentence_id: i32,
+    pub replacement: WordReplacement,
+}
+
+/// Terms of the podcast and those of all podcasts, sorted by term. Without a
+/// podcast only the latter are loaded.
+pub async fn terms<C: ConnectionTrait>(
+    db: &C,
+    podcast_id: Option<i32>,
+) -> Result<Vec<TermDisplay>, DbErr> {
+    let mut condition = Condition::any().add(GlossaryTermsNS::Column::PodcastId.is_null());
+    if let Some(podcast_id) = podcast_id {
+        condition = condition.add(GlossaryTermsNS::Column::PodcastId.eq(podcast_id));
+    }
+    let terms = GlossaryTermsNS::Entity::find()
+        .filter(condition)
+        .order_by_asc(GlossaryTermsNS::Column::Term)
+        .order_by_asc(GlossaryTermsNS::Column::Id)
+        .all(db)
+        .await?;
+    load_misrecognitions(db, terms).await
+}
+
+pub async fn load_misrecognitions<C: ConnectionTrait>(
+    db: &C,
+    terms: Vec<GlossaryTermsNS::Model>,
+) -> Result<Vec<TermDisplay>, DbErr> {
+    let mut misrecognitions = BTreeMap::<i32, Vec<String>>::new();
+    for item in GlossaryMisrecognitionsNS::Entity::find()
+        .filter(GlossaryMisrecognitionsNS::Column::GlossaryTermId.is_in(terms.iter().map(|x| x.id)))
+        .order_by_asc(GlossaryMisrecognitionsNS::Column::Id)
+        .all(db)
+        .await?
+    {
+        misrecognitions
+            .entry(item.glossary_term_id)
+            .or_default()
+            .push(item.text);
+    }
+    Ok(terms
+        .into_iter()
+        .map(|term| TermDisplay {
+            misrecognitions: misrecognitions.remove(&term.id).unwrap_or_default(),
+            term,
+        })
+        .collect())
+}
+
+/// Suggestions for the words of a part. Known misrecognitions are matched as
+/// well as the term itself with another case, each word is suggested once.
+pub fn suggestions(terms: &[TermDisplay], words: &[WordsNS::Model]) -> Vec<Suggestion> {
+    let mut by_sentence = BTreeMap::<i32, Vec<WordsNS::Model>>::new();
+    for word in words {
+        by_sentence
+            .entry(word.sentence_id)
+            .or_default()
+            .push(word.clone());
+    }
+
+    let mut output = vec![];
+    let mut suggested = HashSet::<i32>::new();
+    for item in terms {
+        let patterns = item
+            .misrecognitions
+            .iter()
+            .chain(std::iter::once(&item.term.term))
+            .filter_map(|x| Pattern::new(x, &item.term.term, false).ok());
+        for pattern in patterns {
+            for (sentence_id, words) in &by_sentence {
+                for replacement in pattern.replacements(words) {
+                    if replacement.word_ids.iter().any(|x| suggested.contains(x)) {
+                        continue;
+                    }
+                    suggested.extend(replacement.word_ids.iter().copied());
+                    output.push(Suggestion {
+                        glossary_term_id: item.term.id,
+                        term: item.term.term.clone(),
+                        sentence_id: *sentence_id,
+                        replacement,
+                    });
+                }
+            }
+        }
+    }
+    output
+}
+
+/// The terms as an initial prompt for whisper, which then is more likely to
+/// spell them right
+pub fn prompt(terms: &[TermDisplay]) -> String {
+    let mut seen = HashSet::new();
+    terms
+        .iter()
+        .map(|x| x.term.term.trim())
+        .filter(|x| !x.is_empty() && seen.insert(*x))
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
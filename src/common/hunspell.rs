@@ -0,0 +1,57 @@
+//! Reads Hunspell dictionaries, which are checked with spellbook. The US
+//! English dictionary is built in and used unless another one is configured.
+
+use encoding_rs::{Encoding, UTF_8};
+use std::path::Path;
+
+pub use spellbook::Dictionary;
+
+const BUNDLED_AFF: &str = include_str!("../../dictionaries/en_US.aff");
+const BUNDLED_DIC: &str = include_str!("../../dictionaries/en_US.dic");
+
+/// The encoding given by the `SET` line of an `.aff` file, UTF-8 without one.
+/// Hunspell names some encodings differently, e.g. `microsoft-cp1251`.
+fn encoding(aff: &[u8]) -> std::io::Result<&'static Encoding> {
+    // The directive itself is ASCII in every encoding
+    let label = String::from_utf8_lossy(aff).lines().find_map(|line| {
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["SET", label, ..] => Some((*label).to_string()),
+            _ => None,
+        }
+    });
+    let Some(label) = label else {
+        return Ok(UTF_8);
+    };
+    let name = label.strip_prefix("microsoft-").unwrap_or(&label);
+    Encoding::for_label(name.as_bytes()).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unknown encoding {label}"),
+        )
+    })
+}
+
+fn parse(aff: &str, dic: &str) -> std::io::Result<Dictionary> {
+    Dictionary::new(aff, dic)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Reads the `.aff` and `.dic` files next to `path`, decoded as the affix
+/// file says
+pub fn read(path: &Path) -> std::io::Result<Dictionary> {
+    let aff = std::fs::read(path.with_extension("aff"))?;
+    let dic = std::fs::read(path.with_extension("dic"))?;
+    let encoding = encoding(&aff)?;
+    parse(&encoding.decode(&aff).0, &encoding.decode(&dic).0)
+}
+
+/// The built-in US English dictionary
+pub fn bundled() -> std::io::Result<Dictionary> {
+    parse(BUNDLED_AFF, BUNDLED_DIC)
+}
+
+/// The configured dictionary, or the built-in one without a configuration.
+/// Parsing takes a while, so this is done once when the app starts.
+pub fn load(path: Option<&Path>) -> std::io::Result<Dictionary> {
+    path.map_or_else(bundled, read)
+}
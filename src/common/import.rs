@@ -0,0 +1,259 @@
+use loco_rs::prelude::*;
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::initializers::tantivy_search::TantivyContainer;
+use crate::models::_entities::episode_speakers as EpisodeSpeakersNS;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::speaker_mappings as SpeakerMappingsNS;
+use crate::models::_entities::speakers as SpeakersNS;
+use crate::models::_entities::words as WordsNS;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportTranscription {
+    pub transcription: Vec<ImportPart>,
+    /// Speaker names or aliases for the diarization labels, e.g. `SPEAKER_00`
+    /// → `Host A`. Remembered for the podcast of the episode.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub speakers: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportPart {
+    pub start: f64,
+    pub end: f64,
+    pub speaker: String,
+    pub text: String,
+    pub sentences: Vec<ImportSentence>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportSentence {
+    pub text: String,
+    pub words: Vec<ImportWord>,
+    pub start: f64,
+    pub end: f64,
+    pub words_per_second: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportWord {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    pub probability: f64,
+}
+
+/// Creates speakers, parts, sentences and words of a blank episode from a
+/// transcription and adds the parts to the search index. Nothing is created
+/// if any of it fails.
+pub async fn import_transcription(
+    db: &DatabaseConnection,
+    tantivy: &TantivyContainer,
+    episode_id: i32,
+    transcription: ImportTranscription,
+) -> Result<()> {
+    let txn = db.begin().await?;
+    let parts = import_parts(&txn, episode_id, transcription).await?;
+    txn.commit().await?;
+
+    for part in &parts {
+        tantivy
+            .update_part(part)
+            .map_err(|e| Error::Message(e.to_string()))?;
+    }
+    // Reloads the reader as well, so the parts can be found right away
+    tantivy
+        .commit()
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+    Ok(())
+}
+
+async fn import_parts<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    transcription: ImportTranscription,
+) -> Result<Vec<PartsNS::Model>> {
+    let episode = EpisodesNS::Entity::find_by_id(episode_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+
+    // Bad request if anything already exists
+    let existing_parts = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .all(db)
+        .await?;
+    if !existing_parts.is_empty() {
+        return Err(Error::BadRequest(String::from(
+            "Import only works for blank episodes",
+        )));
+    }
+
+    // Bad request if anything already exists
+    let existing_speakers = EpisodeSpeakersNS::Entity::find()
+        .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(episode_id))
+        .all(db)
+        .await?;
+    if !existing_speakers.is_empty() {
+        return Err(Error::BadRequest(String::from(
+            "Import only works for blank episodes",
+        )));
+    }
+
+    // All new. Start with speakers.
+    let labels = transcription
+        .transcription
+        .iter()
+        .map(|x| x.speaker.clone())
+        .collect::<HashSet<_>>();
+    let speaker_map =
+        resolve_speakers(db, episode.podcast_id, labels, &transcription.speakers).await?;
+
+    // Now assign all speakers to episode speakers. Labels naming the same
+    // speaker share one.
+    let mut episode_speaker_ids = HashMap::<i32, i32>::new();
+    let mut episode_speaker_map = HashMap::<String, i32>::new();
+    for (label, speaker_id) in speaker_map {
+        let episode_speaker_id = match episode_speaker_ids.get(&speaker_id) {
+            Some(id) => *id,
+            None => {
+                let mut item = EpisodeSpeakersNS::ActiveModel {
+                    ..Default::default()
+                };
+                item.episode_id = Set(episode_id);
+                item.speaker_id = Set(speaker_id);
+                let item = item.insert(db).await?;
+                episode_speaker_ids.insert(speaker_id, item.id);
+                item.id
+            }
+        };
+        episode_speaker_map.insert(label, episode_speaker_id);
+    }
+
+    // Now got for the parts
+    let mut parts = Vec::new();
+    for import_part in transcription.transcription {
+        if import_part.text.is_empty() {
+            continue;
+        }
+
+        let mut item = PartsNS::ActiveModel {
+            ..Default::default()
+        };
+        item.episode_speaker_id = Set(*episode_speaker_map.get(&import_part.speaker).unwrap());
+        item.episode_id = Set(episode_id);
+        item.text = Set(import_part.text.clone());
+        item.part_type = Set(0);
+        item.starts_at = Set(import_part.start);
+        item.ends_at = Set(import_part.end);
+        let part = item.insert(db).await?;
+
+        for import_sentence in import_part.sentences {
+            if import_sentence.text.is_empty() {
+                continue;
+            }
+
+            let mut item = SentencesNS::ActiveModel {
+                ..Default::default()
+            };
+            item.part_id = Set(part.id);
+            item.text = Set(import_sentence.text.clone());
+            item.starts_at = Set(import_sentence.start);
+            item.ends_at = Set(import_sentence.end);
+            item.words_per_second = Set(import_sentence.words_per_second);
+            let sentence = item.insert(db).await?;
+
+            let words = import_sentence
+                .words
+                .iter()
+                .map(|x| {
+                    let mut item = WordsNS::ActiveModel {
+                        ..Default::default()
+                    };
+
+                    item.sentence_id = Set(sentence.id);
+                    item.hidden = Set(false);
+                    item.overwrite = Set("".into());
+                    item.text = Set(x.text.clone());
+                    item.probability = Set(x.probability);
+                    item.starts_at = Set(x.start);
+                    item.ends_at = Set(x.end);
+                    item
+                })
+                .collect::<Vec<WordsNS::ActiveModel>>();
+
+            WordsNS::Entity::insert_many(words)
+                .on_empty_do_nothing()
+                .exec(db)
+                .await?;
+        }
+        parts.push(part);
+    }
+
+    Ok(parts)
+}
+
+/// Finds the speaker of every diarization label: the name given with the
+/// import, else the speaker remembered for the podcast, else the label
+/// itself. Names are looked up among speakers and their aliases, unknown ones
+/// become new speakers. Names given with the import are remembered.
+async fn resolve_speakers<C: ConnectionTrait>(
+    db: &C,
+    podcast_id: Option<i32>,
+    labels: HashSet<String>,
+    names: &HashMap<String, String>,
+) -> Result<HashMap<String, i32>> {
+    let remembered = match podcast_id {
+        Some(podcast_id) => SpeakerMappingsNS::Entity::find()
+            .filter(SpeakerMappingsNS::Column::PodcastId.eq(podcast_id))
+            .filter(SpeakerMappingsNS::Column::Label.is_in(labels.iter().cloned()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| (x.label.clone(), x))
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let mut speaker_map = HashMap::new();
+    for label in labels {
+        let speaker_id = if let Some(name) = names.get(&label) {
+            let speaker_id = find_or_create_speaker(db, name).await?;
+            if let Some(podcast_id) = podcast_id {
+                let mut item = match remembered.get(&label) {
+                    Some(mapping) => mapping.clone().into_active_model(),
+                    None => SpeakerMappingsNS::ActiveModel {
+                        podcast_id: Set(podcast_id),
+                        label: Set(label.clone()),
+                        ..Default::default()
+                    },
+                };
+                item.speaker_id = Set(speaker_id);
+                item.save(db).await?;
+            }
+            speaker_id
+        } else if let Some(mapping) = remembered.get(&label) {
+            mapping.speaker_id
+        } else {
+            find_or_create_speaker(db, &label).await?
+        };
+        speaker_map.insert(label, speaker_id);
+    }
+    Ok(speaker_map)
+}
+
+async fn find_or_create_speaker<C: ConnectionTrait>(db: &C, name: &str) -> Result<i32> {
+    if let Some(speaker) = SpeakersNS::Entity::find_by_name_or_alias(db, name).await? {
+        return Ok(speaker.id);
+    }
+    let mut item = SpeakersNS::ActiveModel {
+        ..Default::default()
+    };
+    item.name = Set(name.to_string());
+    Ok(item.insert(db).await?.id)
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::common::glossary::TermDisplay;
+use crate::common::hunspell::Dictionary;
+use crate::models::_entities::words as WordsNS;
+
+/// Marks that end a sentence
+const FINAL_PUNCTUATION: [char; 4] = ['.', '!', '?', '…'];
+/// Marks that may follow the final punctuation, e.g. a closing quote
+const CLOSING_PUNCTUATION: [char; 6] = ['"', '\'', ')', ']', '”', '’'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Neither in the dictionary nor in the glossary
+    Misspelling,
+    /// The same word as the one before
+    DoubledWord,
+    /// A sentence starting with a lowercase letter
+    LowercaseStart,
+    /// A sentence without a period, question or exclamation mark at the end
+    MissingPunctuation,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+/// Checks words against a dictionary and the glossary
+pub struct Checker<'a> {
+    dictionary: &'a Dictionary,
+    /// Lowercased words of the glossary terms
+    glossary: HashSet<String>,
+}
+
+fn bare(text: &str) -> &str {
+    text.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(dictionary: &'a Dictionary, terms: &[TermDisplay]) -> Self {
+        Self {
+            dictionary,
+            glossary: terms
+                .iter()
+                .flat_map(|x| x.term.term.split_whitespace())
+                .map(|x| bare(x).to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Numbers are not checked, words joined by hyphens are checked one by one
+    fn is_known(&self, word: &str) -> bool {
+        let dictionary = self.dictionary;
+        if word.is_empty()
+            || word.chars().any(|c| c.is_ascii_digit())
+            || self.glossary.contains(&word.to_lowercase())
+            || dictionary.check(word)
+        {
+            return true;
+        }
+        word.contains('-')
+            && word.split('-').all(|x| {
+                x.is_empty() || dictionary.check(x) || self.glossary.contains(&x.to_lowercase())
+            })
+    }
+
+    /// Issues of the words of one sentence, keyed by word id. Hidden words are
+    /// skipped as they are not part of the text.
+    pub fn check_sentence(&self, words: &[WordsNS::Model]) -> BTreeMap<i32, Vec<Issue>> {
+        let mut output = BTreeMap::<i32, Vec<Issue>>::new();
+        let visible: Vec<&WordsNS::Model> = words.iter().filter(|x| !x.hidden).collect();
+
+        for (index, word) in visible.iter().enumerate() {
+            let text = bare(word.effective_text());
+            if !self.is_known(text) {
+                output.entry(word.id).or_default().push(Issue {
+                    kind: IssueKind::Misspelling,
+                    message: format!("\"{text}\" is not in the dictionary"),
+                });
+            }
+            let doubled = index > 0
+                && !text.is_empty()
+                && bare(visible[index - 1].effective_text()).to_lowercase() == text.to_lowercase();
+            if doubled {
+                output.entry(word.id).or_default().push(Issue {
+                    kind: IssueKind::DoubledWord,
+                    message: format!("\"{text}\" is repeated"),
+                });
+            }
+        }
+
+        let first = visible
+            .iter()
+            .find(|x| x.effective_text().chars().any(char::is_alphanumeric));
+        if let Some(first) = first {
+            let starts_lowercase = bare(first.effective_text())
+                .chars()
+                .next()
+                .is_some_and(char::is_lowercase);
+            if starts_lowercase {
+                output.entry(first.id).or_default().push(Issue {
+                    kind: IssueKind::LowercaseStart,
+                    message: "Sentence starts with a lowercase letter".to_string(),
+                });
+            }
+        }
+
+        if let Some(last) = visible.last() {
+            let ends_with_punctuation = last
+                .effective_text()
+                .trim_end_matches(CLOSING_PUNCTUATION)
+                .ends_with(FINAL_PUNCTUATION);
+            if !ends_with_punctuation {
+                output.entry(last.id).or_default().push(Issue {
+                    kind: IssueKind::MissingPunctuation,
+                    message: "Sentence does not end with punctuation".to_string(),
+                });
+            }
+        }
+        output
+    }
+
+    /// Issues of the words of a part, which may belong to several sentences
+    pub fn check(&self, words: &[WordsNS::Model]) -> BTreeMap<i32, Vec<Issue>> {
+        let mut by_sentence = BTreeMap::<i32, Vec<WordsNS::Model>>::new();
+        for word in words {
+            by_sentence
+                .entry(word.sentence_id)
+                .or_default()
+                .push(word.clone());
+        }
+        by_sentence
+            .values()
+            .flat_map(|x| self.check_sentence(x))
+            .collect()
+    }
+}
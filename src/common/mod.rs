@@ -1,3 +1,18 @@
+pub mod audio;
 pub mod check_auth;
 pub mod claims;
+pub mod events;
+pub mod feed;
+pub mod find_replace;
+pub mod glossary;
+pub mod hunspell;
+pub mod import;
+pub mod lint;
+pub mod part_operations;
+pub mod review;
+pub mod revisions;
 pub mod settings;
+pub mod speaker_merge;
+pub mod speaker_stats;
+pub mod transcription;
+pub mod waveform;
@@ -0,0 +1,828 @@
+use loco_rs::Error;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    entity::prelude::*, ActiveValue::Set, IntoActiveModel, JoinType, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::_entities::comments as CommentsNS;
+use crate::models::_entities::episode_speakers as EpisodeSpeakersNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::words as WordsNS;
+
+/// Why a split, merge or reassignment was refused
+#[derive(Debug)]
+pub enum PartOperationError {
+    WordNotFound,
+    /// Splitting at the first word would leave an empty part
+    NothingToSplit,
+    NotAdjacent,
+    EmptyRange,
+    /// Neither a time range nor a speaker was given
+    EmptySelection,
+    EpisodeSpeakerNotFound,
+    /// Timestamps can only be stretched by a positive factor
+    InvalidScale,
+    /// Two sync points at the same time do not give a stretch factor
+    InvalidSyncPoints,
+    NegativeTimestamp,
+    /// Retimed timestamps would end before they start or overlap the parts
+    /// that stay in place
+    OutOfOrder,
+    /// Replaced words have to follow each other in one sentence
+    NotConsecutive,
+    Db(DbErr),
+}
+
+impl std::fmt::Display for PartOperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WordNotFound => f.write_str("Word is not part of the part"),
+            Self::NothingToSplit => f.write_str("Cannot split a part at its first word"),
+            Self::NotAdjacent => f.write_str("Only adjacent parts of an episode can be merged"),
+            Self::EmptyRange => f.write_str("Time range is empty"),
+            Self::EmptySelection => f.write_str("Select a time range or a speaker"),
+            Self::EpisodeSpeakerNotFound => f.write_str("Speaker is not part of the episode"),
+            Self::InvalidScale => f.write_str("Scale has to be a positive number"),
+            Self::InvalidSyncPoints => {
+                f.write_str("Give one or two sync points at different times")
+            }
+            Self::NegativeTimestamp => {
+                f.write_str("Timestamps would be before the start of the audio")
+            }
+            Self::OutOfOrder => {
+                f.write_str("Retimed parts would end before they start or overlap earlier parts")
+            }
+            Self::NotConsecutive => {
+                f.write_str("Replace consecutive words of one sentence, each word once")
+            }
+            Self::Db(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PartOperationError {}
+
+impl From<DbErr> for PartOperationError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl From<PartOperationError> for Error {
+    fn from(e: PartOperationError) -> Self {
+        match e {
+            PartOperationError::Db(e) => Self::DB(e),
+            e => Self::BadRequest(e.to_string()),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, PartOperationError>;
+
+/// Parts whose ids changed in one operation
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub created: Vec<i32>,
+    pub updated: Vec<i32>,
+    pub deleted: Vec<i32>,
+}
+
+impl Changes {
+    /// Parts that exist after the operation
+    pub fn existing(&self) -> Vec<i32> {
+        self.created
+            .iter()
+            .chain(self.updated.iter())
+            .copied()
+            .collect()
+    }
+}
+
+/// Parts to give to another speaker: everything between two timestamps,
+/// everything currently spoken by one episode speaker, or both
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Selection {
+    pub starts_at: Option<f64>,
+    pub ends_at: Option<f64>,
+    pub from_episode_speaker_id: Option<i32>,
+}
+
+impl Selection {
+    /// Whether `selected` would return the part of its episode
+    pub fn includes(&self, part: &PartsNS::Model) -> bool {
+        self.ends_at.is_none_or(|x| part.starts_at < x)
+            && self.starts_at.is_none_or(|x| part.ends_at > x)
+            && self
+                .from_episode_speaker_id
+                .is_none_or(|x| part.episode_speaker_id == x)
+    }
+}
+
+/// Splits the part in front of `word_id`. The word and everything after it
+/// goes to a new part, spoken by `episode_speaker_id` or the same speaker.
+/// Returns both halves.
+pub async fn split<C: ConnectionTrait>(
+    db: &C,
+    part: PartsNS::Model,
+    word_id: i32,
+    episode_speaker_id: Option<i32>,
+) -> Result<(PartsNS::Model, PartsNS::Model)> {
+    if let Some(episode_speaker_id) = episode_speaker_id {
+        check_episode_speaker(db, part.episode_id, episode_speaker_id).await?;
+    }
+    let (sentences, words) = load_in_order(db, part.id).await?;
+    let position = words
+        .iter()
+        .position(|x| x.id == word_id)
+        .ok_or(PartOperationError::WordNotFound)?;
+    if position == 0 {
+        return Err(PartOperationError::NothingToSplit);
+    }
+    let word = &words[position];
+
+    let new_part = PartsNS::ActiveModel {
+        text: Set(String::new()),
+        part_type: Set(part.part_type),
+        starts_at: Set(word.starts_at),
+        ends_at: Set(part.ends_at),
+        episode_id: Set(part.episode_id),
+        episode_speaker_id: Set(episode_speaker_id.unwrap_or(part.episode_speaker_id)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let sentence_index = sentences
+        .iter()
+        .position(|x| x.id == word.sentence_id)
+        .ok_or(PartOperationError::WordNotFound)?;
+    let sentence = &sentences[sentence_index];
+    let sentence_words: Vec<&WordsNS::Model> = words
+        .iter()
+        .filter(|x| x.sentence_id == sentence.id)
+        .collect();
+    let split_at = sentence_words
+        .iter()
+        .position(|x| x.id == word_id)
+        .unwrap_or_default();
+
+    let mut moved_sentences: Vec<i32> = sentences[sentence_index + 1..]
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    if split_at == 0 {
+        moved_sentences.push(sentence.id);
+    } else {
+        // The sentence is cut in two as well
+        let (head, tail) = sentence_words.split_at(split_at);
+        refresh_sentence(db, sentence.clone(), head).await?;
+        let new_sentence = SentencesNS::ActiveModel {
+            text: Set(String::new()),
+            starts_at: Set(word.starts_at),
+            ends_at: Set(sentence.ends_at),
+            words_per_second: Set(0.0),
+            part_id: Set(new_part.id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        WordsNS::Entity::update_many()
+            .col_expr(WordsNS::Column::SentenceId, Expr::value(new_sentence.id))
+            .col_expr(WordsNS::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(WordsNS::Column::Id.is_in(tail.iter().map(|x| x.id)))
+            .exec(db)
+            .await?;
+        refresh_sentence(db, new_sentence, tail).await?;
+    }
+    move_sentences(db, moved_sentences, new_part.id).await?;
+
+    // Threads go along with the words they are about
+    let moved_threads: Vec<i32> = CommentsNS::Entity::find()
+        .filter(CommentsNS::Column::PartId.eq(part.id))
+        .filter(CommentsNS::Column::FromWordId.is_in(words[position..].iter().map(|x| x.id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    CommentsNS::Entity::update_many()
+        .col_expr(CommentsNS::Column::PartId, Expr::value(new_part.id))
+        .filter(
+            CommentsNS::Column::Id
+                .is_in(moved_threads.clone())
+                .or(CommentsNS::Column::ParentId.is_in(moved_threads)),
+        )
+        .exec(db)
+        .await?;
+
+    let part = refresh_part(db, part).await?;
+    let new_part = refresh_part(db, new_part).await?;
+    Ok((part, new_part))
+}
+
+/// Appends the later of two adjacent parts to the earlier one, which keeps
+/// its speaker. Returns the merged part and the id of the removed one.
+pub async fn merge<C: ConnectionTrait>(
+    db: &C,
+    a: PartsNS::Model,
+    b: PartsNS::Model,
+) -> Result<(PartsNS::Model, i32)> {
+    if a.id == b.id || a.episode_id != b.episode_id {
+        return Err(PartOperationError::NotAdjacent);
+    }
+    let (first, second) = if (a.starts_at, a.id) <= (b.starts_at, b.id) {
+        (a, b)
+    } else {
+        (b, a)
+    };
+
+    let parts_between = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeId.eq(first.episode_id))
+        .filter(PartsNS::Column::StartsAt.gt(first.starts_at))
+        .filter(PartsNS::Column::StartsAt.lt(second.starts_at))
+        .count(db)
+        .await?;
+    if parts_between > 0 {
+        return Err(PartOperationError::NotAdjacent);
+    }
+
+    let sentence_ids: Vec<i32> = SentencesNS::Entity::find()
+        .filter(SentencesNS::Column::PartId.eq(second.id))
+        .all(db)
+        .await?
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    move_sentences(db, sentence_ids, first.id).await?;
+    CommentsNS::Entity::update_many()
+        .col_expr(CommentsNS::Column::PartId, Expr::value(first.id))
+        .filter(CommentsNS::Column::PartId.eq(second.id))
+        .exec(db)
+        .await?;
+    let removed = second.id;
+    second.delete(db).await?;
+
+    Ok((refresh_part(db, first).await?, removed))
+}
+
+/// Removes the words from the anchors of comments, before the words are
+/// deleted. Threads about the words are about the whole part then.
+pub async fn clear_anchors<C: ConnectionTrait>(
+    db: &C,
+    word_ids: &[i32],
+) -> std::result::Result<(), DbErr> {
+    for column in [CommentsNS::Column::FromWordId, CommentsNS::Column::ToWordId] {
+        CommentsNS::Entity::update_many()
+            .col_expr(column, Expr::value(None::<i32>))
+            .filter(column.is_in(word_ids.iter().copied()))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Threads of the part whose anchor words are in other parts now go there
+/// too, with their replies. An anchor ending in yet another part is cut down
+/// to its first word. Returns the parts that received threads.
+pub async fn move_threads<C: ConnectionTrait>(db: &C, part_id: i32) -> Result<Vec<i32>> {
+    let threads = CommentsNS::Entity::find()
+        .filter(CommentsNS::Column::PartId.eq(part_id))
+        .filter(CommentsNS::Column::ParentId.is_null())
+        .all(db)
+        .await?;
+    let word_ids = threads
+        .iter()
+        .flat_map(|x| [x.from_word_id, x.to_word_id])
+        .flatten();
+    let parts_of_words: HashMap<i32, i32> = WordsNS::Entity::find()
+        .select_only()
+        .column(WordsNS::Column::Id)
+        .column(SentencesNS::Column::PartId)
+        .join(JoinType::InnerJoin, WordsNS::Relation::Sentences.def())
+        .filter(WordsNS::Column::Id.is_in(word_ids))
+        .into_tuple::<(i32, i32)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut targets: Vec<i32> = vec![];
+    for thread in threads {
+        let part_of = |word_id: Option<i32>| word_id.and_then(|x| parts_of_words.get(&x).copied());
+        let Some(target) = part_of(thread.from_word_id).or(part_of(thread.to_word_id)) else {
+            continue;
+        };
+        if thread.from_word_id.is_some() && part_of(thread.to_word_id).is_some_and(|x| x != target)
+        {
+            let mut item = thread.clone().into_active_model();
+            item.to_word_id = Set(None);
+            item.update(db).await?;
+        }
+        if target == part_id {
+            continue;
+        }
+        CommentsNS::Entity::update_many()
+            .col_expr(CommentsNS::Column::PartId, Expr::value(target))
+            .filter(
+                CommentsNS::Column::Id
+                    .eq(thread.id)
+                    .or(CommentsNS::Column::ParentId.eq(thread.id)),
+            )
+            .exec(db)
+            .await?;
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+/// Parts of the episode that overlap the time range and belong to the
+/// speaker of the selection
+pub async fn selected<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    selection: &Selection,
+) -> Result<Vec<PartsNS::Model>> {
+    let mut query = PartsNS::Entity::find().filter(PartsNS::Column::EpisodeId.eq(episode_id));
+    if let Some(ends_at) = selection.ends_at {
+        query = query.filter(PartsNS::Column::StartsAt.lt(ends_at));
+    }
+    if let Some(starts_at) = selection.starts_at {
+        query = query.filter(PartsNS::Column::EndsAt.gt(starts_at));
+    }
+    if let Some(from_episode_speaker_id) = selection.from_episode_speaker_id {
+        query = query.filter(PartsNS::Column::EpisodeSpeakerId.eq(from_episode_speaker_id));
+    }
+    Ok(query
+        .order_by_asc(PartsNS::Column::StartsAt)
+        .all(db)
+        .await?)
+}
+
+/// Gives all selected words to another speaker. Parts reaching over the
+/// borders of the time range are split at the first word inside and the
+/// first word after the range.
+pub async fn reassign<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    selection: &Selection,
+    episode_speaker_id: i32,
+) -> Result<Changes> {
+    let Selection {
+        starts_at,
+        ends_at,
+        from_episode_speaker_id,
+    } = *selection;
+    if starts_at.is_none() && ends_at.is_none() && from_episode_speaker_id.is_none() {
+        return Err(PartOperationError::EmptySelection);
+    }
+    if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at) {
+        if ends_at <= starts_at {
+            return Err(PartOperationError::EmptyRange);
+        }
+    }
+    check_episode_speaker(db, episode_id, episode_speaker_id).await?;
+
+    let mut changes = Changes::default();
+    for part in selected(db, episode_id, selection).await? {
+        let mut part = part;
+        let mut created = false;
+
+        if let Some(starts_at) = starts_at {
+            let (_, words) = load_in_order(db, part.id).await?;
+            match words.iter().position(|x| x.starts_at >= starts_at) {
+                // Nothing of the part is within the range
+                None => continue,
+                Some(0) => {}
+                Some(position) => {
+                    let (head, tail) = split(db, part, words[position].id, None).await?;
+                    changes.updated.push(head.id);
+                    changes.created.push(tail.id);
+                    part = tail;
+                    created = true;
+                }
+            }
+        }
+
+        if let Some(ends_at) = ends_at {
+            let (_, words) = load_in_order(db, part.id).await?;
+            match words.iter().position(|x| x.starts_at >= ends_at) {
+                None => {}
+                Some(0) => continue,
+                Some(position) => {
+                    let (head, tail) = split(db, part, words[position].id, None).await?;
+                    changes.created.push(tail.id);
+                    part = head;
+                }
+            }
+        }
+
+        let id = part.id;
+        let mut part = part.into_active_model();
+        part.episode_speaker_id = Set(episode_speaker_id);
+        part.update(db).await?;
+        if !created && !changes.updated.contains(&id) {
+            changes.updated.push(id);
+        }
+    }
+    Ok(changes)
+}
+
+/// A linear change of timestamps, `t' = scale * t + offset`. With `from` only
+/// parts starting at or after that point are changed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Retiming {
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+    pub from: Option<f64>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// Where a moment of the transcribed audio is in the new audio
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SyncPoint {
+    pub before: f64,
+    pub after: f64,
+}
+
+impl Retiming {
+    /// The change that moves the sync points to their new times. One point
+    /// only shifts the timestamps, two also stretch them.
+    pub fn resync(points: &[SyncPoint], from: Option<f64>) -> Result<Self> {
+        let (scale, offset) = match points {
+            [point] => (1.0, point.after - point.before),
+            [first, second] => {
+                let scale = (second.after - first.after) / (second.before - first.before);
+                (scale, first.after - scale * first.before)
+            }
+            _ => return Err(PartOperationError::InvalidSyncPoints),
+        };
+        if !scale.is_finite() {
+            return Err(PartOperationError::InvalidSyncPoints);
+        }
+        Ok(Self {
+            scale,
+            offset,
+            from,
+        })
+    }
+
+    fn check(&self) -> Result<()> {
+        if !self.scale.is_finite() || self.scale <= 0.0 {
+            return Err(PartOperationError::InvalidScale);
+        }
+        Ok(())
+    }
+
+    /// Sets the start and end columns to the changed timestamps
+    fn update<E: EntityTrait>(
+        &self,
+        query: sea_orm::UpdateMany<E>,
+        columns: [E::Column; 2],
+    ) -> sea_orm::UpdateMany<E> {
+        columns.into_iter().fold(query, |query, column| {
+            query.col_expr(column, Expr::col(column).mul(self.scale).add(self.offset))
+        })
+    }
+}
+
+/// Parts of the episode starting at or after `from`
+pub async fn retimed<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    from: Option<f64>,
+) -> Result<Vec<PartsNS::Model>> {
+    let mut query = PartsNS::Entity::find().filter(PartsNS::Column::EpisodeId.eq(episode_id));
+    if let Some(from) = from {
+        query = query.filter(PartsNS::Column::StartsAt.gte(from));
+    }
+    Ok(query
+        .order_by_asc(PartsNS::Column::StartsAt)
+        .all(db)
+        .await?)
+}
+
+/// Changes the timestamps of all parts, sentences and words of the episode.
+/// Returns the ids of the parts with changed timestamps.
+pub async fn retime<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    retiming: &Retiming,
+) -> Result<Vec<i32>> {
+    retiming.check()?;
+    let part_ids: Vec<i32> = retimed(db, episode_id, retiming.from)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    let sentence_ids: Vec<i32> = SentencesNS::Entity::find()
+        .filter(SentencesNS::Column::PartId.is_in(part_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+
+    let query = PartsNS::Entity::update_many()
+        .col_expr(PartsNS::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(PartsNS::Column::Id.is_in(part_ids.clone()));
+    retiming
+        .update(query, [PartsNS::Column::StartsAt, PartsNS::Column::EndsAt])
+        .exec(db)
+        .await?;
+    let query = SentencesNS::Entity::update_many()
+        .col_expr(
+            SentencesNS::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .filter(SentencesNS::Column::Id.is_in(sentence_ids.clone()));
+    retiming
+        .update(
+            query,
+            [SentencesNS::Column::StartsAt, SentencesNS::Column::EndsAt],
+        )
+        .exec(db)
+        .await?;
+    let query = WordsNS::Entity::update_many()
+        .col_expr(WordsNS::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(WordsNS::Column::SentenceId.is_in(sentence_ids.clone()));
+    retiming
+        .update(query, [WordsNS::Column::StartsAt, WordsNS::Column::EndsAt])
+        .exec(db)
+        .await?;
+
+    // The transaction is rolled back if anything moved before the start
+    let negative_parts = PartsNS::Entity::find()
+        .filter(PartsNS::Column::Id.is_in(part_ids.clone()))
+        .filter(PartsNS::Column::StartsAt.lt(0.0))
+        .count(db)
+        .await?;
+    let negative_words = WordsNS::Entity::find()
+        .filter(WordsNS::Column::SentenceId.is_in(sentence_ids.clone()))
+        .filter(WordsNS::Column::StartsAt.lt(0.0))
+        .count(db)
+        .await?;
+    if negative_parts + negative_words > 0 {
+        return Err(PartOperationError::NegativeTimestamp);
+    }
+
+    // Or if it ends before it starts, or before the parts that did not move
+    let reversed_parts = PartsNS::Entity::find()
+        .filter(PartsNS::Column::Id.is_in(part_ids.clone()))
+        .filter(Expr::col(PartsNS::Column::EndsAt).lt(Expr::col(PartsNS::Column::StartsAt)))
+        .count(db)
+        .await?;
+    let reversed_words = WordsNS::Entity::find()
+        .filter(WordsNS::Column::SentenceId.is_in(sentence_ids))
+        .filter(Expr::col(WordsNS::Column::EndsAt).lt(Expr::col(WordsNS::Column::StartsAt)))
+        .count(db)
+        .await?;
+    if reversed_parts + reversed_words > 0 {
+        return Err(PartOperationError::OutOfOrder);
+    }
+    let parts = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .all(db)
+        .await?;
+    let (moved, kept): (Vec<&PartsNS::Model>, Vec<&PartsNS::Model>) =
+        parts.iter().partition(|x| part_ids.contains(&x.id));
+    let first_moved = moved.iter().map(|x| x.starts_at).reduce(f64::min);
+    let last_kept = kept.iter().map(|x| x.ends_at).reduce(f64::max);
+    if let (Some(first_moved), Some(last_kept)) = (first_moved, last_kept) {
+        if first_moved < last_kept {
+            return Err(PartOperationError::OutOfOrder);
+        }
+    }
+    Ok(part_ids)
+}
+
+/// New texts for consecutive words of a sentence
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordReplacement {
+    pub word_ids: Vec<i32>,
+    pub texts: Vec<String>,
+}
+
+/// Text of each of `word_count` words after they were replaced with `texts`.
+/// With fewer texts than words the words without one (`None`) are merged into
+/// the last remaining word, which also gets all texts that are left over.
+pub fn replaced_texts(word_count: usize, texts: &[String]) -> Vec<Option<String>> {
+    let kept = word_count.min(texts.len());
+    (0..word_count)
+        .map(|index| match (index + 1).cmp(&kept) {
+            std::cmp::Ordering::Less => Some(texts[index].clone()),
+            std::cmp::Ordering::Equal => Some(texts[index..].join(" ")),
+            std::cmp::Ordering::Greater => None,
+        })
+        .collect()
+}
+
+/// Overwrites the words of the part, merging words where there are fewer
+/// texts than words. Merged words are removed and the remaining word lasts
+/// until the end of the last one. The words of a replacement may be given in
+/// any order. Returns the updated part.
+pub async fn replace_words<C: ConnectionTrait>(
+    db: &C,
+    part: PartsNS::Model,
+    replacements: &[WordReplacement],
+) -> Result<PartsNS::Model> {
+    let (_, words) = load_in_order(db, part.id).await?;
+    let mut changed_sentences: Vec<i32> = vec![];
+    let mut changed_words: Vec<i32> = vec![];
+    for replacement in replacements {
+        let mut positions = replacement
+            .word_ids
+            .iter()
+            .map(|id| {
+                words
+                    .iter()
+                    .position(|x| x.id == *id)
+                    .ok_or(PartOperationError::WordNotFound)
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        positions.sort_unstable();
+        let consecutive = positions
+            .windows(2)
+            .all(|x| x[1] == x[0] + 1 && words[x[1]].sentence_id == words[x[0]].sentence_id);
+        // Words of an earlier replacement may be gone already
+        let replaced_before = positions
+            .iter()
+            .any(|x| changed_words.contains(&words[*x].id));
+        if !consecutive || replaced_before {
+            return Err(PartOperationError::NotConsecutive);
+        }
+        changed_words.extend(positions.iter().map(|x| words[*x].id));
+        let matched: Vec<&WordsNS::Model> = positions.iter().map(|x| &words[*x]).collect();
+        let Some(ends_at) = matched.last().map(|x| x.ends_at) else {
+            continue;
+        };
+        let texts = replaced_texts(matched.len(), &replacement.texts);
+        let kept = texts.iter().filter(|x| x.is_some()).count();
+
+        let mut removed: Vec<i32> = vec![];
+        let mut last_kept = None;
+        for (index, (word, text)) in matched.into_iter().zip(texts).enumerate() {
+            changed_sentences.push(word.sentence_id);
+            let Some(text) = text else {
+                removed.push(word.id);
+                continue;
+            };
+            let mut item = word.clone().into_active_model();
+            item.overwrite = Set(if text == word.text {
+                String::new()
+            } else {
+                text
+            });
+            if index + 1 == kept {
+                item.ends_at = Set(ends_at);
+                last_kept = Some(word.id);
+            }
+            item.update(db).await?;
+        }
+
+        if let Some(last_kept) = last_kept {
+            // Comments about merged words are about the merged word now
+            for column in [CommentsNS::Column::FromWordId, CommentsNS::Column::ToWordId] {
+                CommentsNS::Entity::update_many()
+                    .col_expr(column, Expr::value(last_kept))
+                    .filter(column.is_in(removed.clone()))
+                    .exec(db)
+                    .await?;
+            }
+        }
+        WordsNS::Entity::delete_many()
+            .filter(WordsNS::Column::Id.is_in(removed))
+            .exec(db)
+            .await?;
+    }
+
+    let (sentences, words) = load_in_order(db, part.id).await?;
+    for sentence in sentences {
+        if !changed_sentences.contains(&sentence.id) {
+            continue;
+        }
+        let sentence_words: Vec<&WordsNS::Model> = words
+            .iter()
+            .filter(|x| x.sentence_id == sentence.id)
+            .collect();
+        refresh_sentence(db, sentence, &sentence_words).await?;
+    }
+    refresh_part(db, part).await
+}
+
+async fn check_episode_speaker<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+    episode_speaker_id: i32,
+) -> Result<()> {
+    EpisodeSpeakersNS::Entity::find_by_id(episode_speaker_id)
+        .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(episode_id))
+        .one(db)
+        .await?
+        .map(|_| ())
+        .ok_or(PartOperationError::EpisodeSpeakerNotFound)
+}
+
+/// Sentences of the part and their words, in the order they are spoken
+async fn load_in_order<C: ConnectionTrait>(
+    db: &C,
+    part_id: i32,
+) -> Result<(Vec<SentencesNS::Model>, Vec<WordsNS::Model>)> {
+    let sentences = SentencesNS::Entity::find()
+        .filter(SentencesNS::Column::PartId.eq(part_id))
+        .order_by_asc(SentencesNS::Column::StartsAt)
+        .order_by_asc(SentencesNS::Column::Id)
+        .all(db)
+        .await?;
+    let mut words = WordsNS::Entity::find()
+        .filter(WordsNS::Column::SentenceId.is_in(sentences.iter().map(|x| x.id)))
+        .order_by_asc(WordsNS::Column::StartsAt)
+        .order_by_asc(WordsNS::Column::EndsAt)
+        .order_by_asc(WordsNS::Column::Id)
+        .all(db)
+        .await?;
+    words.sort_by_key(|word| sentences.iter().position(|x| x.id == word.sentence_id));
+    Ok((sentences, words))
+}
+
+async fn move_sentences<C: ConnectionTrait>(
+    db: &C,
+    sentence_ids: Vec<i32>,
+    part_id: i32,
+) -> Result<()> {
+    SentencesNS::Entity::update_many()
+        .col_expr(SentencesNS::Column::PartId, Expr::value(part_id))
+        .col_expr(
+            SentencesNS::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .filter(SentencesNS::Column::Id.is_in(sentence_ids))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Text and timing of a sentence from its words, like the editor does
+async fn refresh_sentence<C: ConnectionTrait>(
+    db: &C,
+    sentence: SentencesNS::Model,
+    words: &[&WordsNS::Model],
+) -> Result<()> {
+    let (Some(first), Some(last)) = (words.first(), words.last()) else {
+        return Ok(());
+    };
+    let text: Vec<&str> = words
+        .iter()
+        .filter(|x| !x.hidden)
+        .map(|x| {
+            if x.overwrite.is_empty() {
+                x.text.as_str()
+            } else {
+                x.overwrite.as_str()
+            }
+        })
+        .collect();
+    let duration = last.ends_at - first.starts_at;
+
+    let mut sentence = sentence.into_active_model();
+    sentence.text = Set(text.join(" "));
+    sentence.starts_at = Set(first.starts_at);
+    sentence.ends_at = Set(last.ends_at);
+    sentence.words_per_second = Set(if duration > 0.0 {
+        text.len() as f64 / duration
+    } else {
+        0.0
+    });
+    sentence.update(db).await?;
+    Ok(())
+}
+
+/// Text and timing of a part from its sentences
+async fn refresh_part<C: ConnectionTrait>(db: &C, part: PartsNS::Model) -> Result<PartsNS::Model> {
+    let (sentences, _) = load_in_order(db, part.id).await?;
+    let (Some(first), Some(last)) = (sentences.first(), sentences.last()) else {
+        return Ok(part);
+    };
+    let text: Vec<&str> = sentences
+        .iter()
+        .map(|x| x.text.as_str())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    let starts_at = first.starts_at;
+    let ends_at = last.ends_at;
+    let mut part = part.into_active_model();
+    part.text = Set(text.join(" "));
+    part.starts_at = Set(starts_at);
+    part.ends_at = Set(ends_at);
+    Ok(part.update(db).await?)
+}
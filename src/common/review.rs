@@ -0,0 +1,290 @@
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
+use sea_orm::{entity::prelude::*, FromQueryResult, JoinType, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::common::settings::ApprovalSettings;
+use crate::models::_entities::approvals as ApprovalsNS;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::revision_parts as RevisionPartsNS;
+use crate::models::_entities::revisions as RevisionsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::users as UsersNS;
+use crate::models::_entities::words as WordsNS;
+use crate::models::parts::PART_TYPE_SPEECH;
+
+/// How many parts of the review queue get their status computed at once
+const QUEUE_CHUNK_SIZE: usize = 500;
+
+/// How far the review of a part is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartStatus {
+    /// Nobody changed or approved the part since the import
+    Untouched,
+    /// Changed, but without approvals that count
+    Edited,
+    /// Approved, but not enough yet
+    InReview,
+    Approved,
+}
+
+/// Review state of an episode
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub parts: usize,
+    pub untouched: usize,
+    pub edited: usize,
+    pub in_review: usize,
+    pub approved: usize,
+    /// Approved parts in percent
+    pub completion: f64,
+}
+
+/// A part that likely contains transcription errors
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub part: PartsNS::Model,
+    pub status: PartStatus,
+    pub words: i64,
+    pub low_probability_words: i64,
+    /// `low_probability_words` of `words`, between 0 and 1
+    pub share: f64,
+}
+
+/// Parts the review queue picks from
+#[derive(Clone, Debug, Default)]
+pub struct QueueFilter {
+    pub episode_id: Option<i32>,
+    pub podcast_id: Option<i32>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct WordCountRow {
+    part_id: i32,
+    words: i64,
+    low_probability_words: Option<i64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct EditorRow {
+    part_id: i32,
+    user_id: i32,
+}
+
+/// Status of a single part. `approvers` are the roles of the users who
+/// approved it, by user id.
+fn status(
+    settings: &ApprovalSettings,
+    last_editor: Option<i32>,
+    approvers: &[(i32, i32)],
+) -> PartStatus {
+    let counted: Vec<&(i32, i32)> = approvers
+        .iter()
+        .filter(|(user_id, _)| settings.count_editor || last_editor != Some(*user_id))
+        .collect();
+    let by_reviewer = counted
+        .iter()
+        .any(|(_, role)| *role >= settings.reviewer_role);
+
+    if counted.len() >= settings.required && (by_reviewer || !settings.reviewer_required) {
+        PartStatus::Approved
+    } else if !counted.is_empty() {
+        PartStatus::InReview
+    } else if last_editor.is_some() || !approvers.is_empty() {
+        PartStatus::Edited
+    } else {
+        PartStatus::Untouched
+    }
+}
+
+/// Status of every given part
+pub async fn statuses<C: ConnectionTrait>(
+    db: &C,
+    settings: &ApprovalSettings,
+    part_ids: &[i32],
+) -> Result<BTreeMap<i32, PartStatus>, DbErr> {
+    let approvals = ApprovalsNS::Entity::find()
+        .filter(ApprovalsNS::Column::PartId.is_in(part_ids.iter().copied()))
+        .all(db)
+        .await?;
+    let user_ids: HashSet<i32> = approvals.iter().map(|x| x.user_id).collect();
+    let roles: HashMap<i32, i32> = UsersNS::Entity::find()
+        .filter(UsersNS::Column::Id.is_in(user_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.role))
+        .collect();
+
+    // The newest revision of a part tells who edited it last
+    let editors = RevisionPartsNS::Entity::find()
+        .select_only()
+        .column(RevisionPartsNS::Column::PartId)
+        .column(RevisionsNS::Column::UserId)
+        .inner_join(RevisionsNS::Entity)
+        .filter(RevisionPartsNS::Column::PartId.is_in(part_ids.iter().copied()))
+        .order_by_asc(RevisionsNS::Column::Id)
+        .into_model::<EditorRow>()
+        .all(db)
+        .await?;
+    let last_editors: HashMap<i32, i32> = editors
+        .into_iter()
+        .map(|x| (x.part_id, x.user_id))
+        .collect();
+
+    let mut approvers = HashMap::<i32, Vec<(i32, i32)>>::new();
+    for approval in approvals {
+        let role = roles.get(&approval.user_id).copied().unwrap_or(0);
+        approvers
+            .entry(approval.part_id)
+            .or_default()
+            .push((approval.user_id, role));
+    }
+
+    Ok(part_ids
+        .iter()
+        .map(|part_id| {
+            let approvers = approvers.get(part_id).map_or(&[][..], Vec::as_slice);
+            (
+                *part_id,
+                status(settings, last_editors.get(part_id).copied(), approvers),
+            )
+        })
+        .collect())
+}
+
+/// Counts the parts by status
+pub fn progress(statuses: &BTreeMap<i32, PartStatus>) -> Progress {
+    let mut progress = Progress {
+        parts: statuses.len(),
+        ..Default::default()
+    };
+    for status in statuses.values() {
+        match status {
+            PartStatus::Untouched => progress.untouched += 1,
+            PartStatus::Edited => progress.edited += 1,
+            PartStatus::InReview => progress.in_review += 1,
+            PartStatus::Approved => progress.approved += 1,
+        }
+    }
+    if progress.parts > 0 {
+        #[allow(clippy::cast_precision_loss)]
+        let completion = progress.approved as f64 * 100.0 / progress.parts as f64;
+        progress.completion = completion;
+    }
+    progress
+}
+
+/// Review state of all parts of the episode
+pub async fn episode_progress<C: ConnectionTrait>(
+    db: &C,
+    settings: &ApprovalSettings,
+    episode_id: i32,
+) -> Result<Progress, DbErr> {
+    let part_ids: Vec<i32> = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    Ok(progress(&statuses(db, settings, &part_ids).await?))
+}
+
+/// Parts that are not approved yet, those with the largest share of words
+/// below the probability threshold first. Hidden words and words someone
+/// already corrected do not count.
+pub async fn low_probability_queue<C: ConnectionTrait>(
+    db: &C,
+    settings: &ApprovalSettings,
+    filter: &QueueFilter,
+    threshold: f64,
+    limit: usize,
+) -> Result<Vec<QueueItem>, DbErr> {
+    let low: SimpleExpr = Expr::case(
+        WordsNS::Column::Probability
+            .lt(threshold)
+            .and(WordsNS::Column::Overwrite.eq("")),
+        1,
+    )
+    .finally(0)
+    .into();
+    let mut query = WordsNS::Entity::find()
+        .select_only()
+        .column_as(PartsNS::Column::Id, "part_id")
+        .column_as(WordsNS::Column::Id.count(), "words")
+        .column_as(SimpleExpr::from(Func::sum(low)), "low_probability_words")
+        .join(JoinType::InnerJoin, WordsNS::Relation::Sentences.def())
+        .join(JoinType::InnerJoin, SentencesNS::Relation::Parts.def())
+        .filter(WordsNS::Column::Hidden.eq(false))
+        .filter(PartsNS::Column::PartType.eq(PART_TYPE_SPEECH));
+    if let Some(episode_id) = filter.episode_id {
+        query = query.filter(PartsNS::Column::EpisodeId.eq(episode_id));
+    }
+    if let Some(podcast_id) = filter.podcast_id {
+        query = query
+            .join(JoinType::InnerJoin, PartsNS::Relation::Episodes.def())
+            .filter(EpisodesNS::Column::PodcastId.eq(podcast_id));
+    }
+    let rows = query
+        .group_by(PartsNS::Column::Id)
+        .into_model::<WordCountRow>()
+        .all(db)
+        .await?;
+
+    let mut candidates: Vec<(i32, i64, i64)> = rows
+        .into_iter()
+        .filter_map(|x| {
+            let low = x.low_probability_words.unwrap_or(0);
+            (low > 0).then_some((x.part_id, x.words, low))
+        })
+        .collect();
+    #[allow(clippy::cast_precision_loss)]
+    let share = |words: i64, low: i64| low as f64 / words as f64;
+    candidates.sort_by(|a, b| {
+        share(b.1, b.2)
+            .total_cmp(&share(a.1, a.2))
+            .then(b.2.cmp(&a.2))
+            .then(a.0.cmp(&b.0))
+    });
+
+    // Statuses are only computed until enough unapproved parts are found
+    let mut unapproved: Vec<(i32, i64, i64)> = vec![];
+    let mut found = BTreeMap::new();
+    for chunk in candidates.chunks(QUEUE_CHUNK_SIZE) {
+        if unapproved.len() >= limit {
+            break;
+        }
+        let part_ids: Vec<i32> = chunk.iter().map(|x| x.0).collect();
+        let statuses = statuses(db, settings, &part_ids).await?;
+        unapproved.extend(
+            chunk
+                .iter()
+                .filter(|x| statuses.get(&x.0) != Some(&PartStatus::Approved)),
+        );
+        found.extend(statuses);
+    }
+    unapproved.truncate(limit);
+
+    let parts: HashMap<i32, PartsNS::Model> = PartsNS::Entity::find()
+        .filter(PartsNS::Column::Id.is_in(unapproved.iter().map(|x| x.0)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x))
+        .collect();
+    Ok(unapproved
+        .into_iter()
+        .filter_map(|(part_id, words, low)| {
+            Some(QueueItem {
+                part: parts.get(&part_id)?.clone(),
+                status: found.get(&part_id).copied()?,
+                words,
+                low_probability_words: low,
+                share: share(words, low),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,315 @@
+use sea_orm::{
+    entity::prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder, QuerySelect, QueryTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use crate::common::part_operations;
+use crate::models::_entities::merged_episode_speakers as MergedEpisodeSpeakersNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::revision_parts as RevisionPartsNS;
+use crate::models::_entities::revisions as RevisionsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::words as WordsNS;
+
+pub const ACTION_EDIT: &str = "edit";
+pub const ACTION_RESTORE: &str = "restore";
+pub const ACTION_SPLIT: &str = "split";
+pub const ACTION_MERGE: &str = "merge";
+pub const ACTION_REASSIGN: &str = "reassign";
+pub const ACTION_REPLACE: &str = "replace";
+pub const ACTION_RETIME: &str = "retime";
+pub const ACTION_MERGE_SPEAKERS: &str = "merge_speakers";
+
+/// A part with its sentences and words at one point in time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartSnapshot {
+    pub part: PartsNS::Model,
+    pub sentences: Vec<SentencesNS::Model>,
+    pub words: Vec<WordsNS::Model>,
+}
+
+impl PartSnapshot {
+    /// `None` if the part does not exist (anymore)
+    pub async fn load<C: ConnectionTrait>(db: &C, part_id: i32) -> Result<Option<Self>, DbErr> {
+        let Some(part) = PartsNS::Entity::find_by_id(part_id).one(db).await? else {
+            return Ok(None);
+        };
+
+        let sentences = SentencesNS::Entity::find()
+            .filter(SentencesNS::Column::PartId.eq(part.id))
+            .order_by_asc(SentencesNS::Column::StartsAt)
+            .all(db)
+            .await?;
+        let words = WordsNS::Entity::find()
+            .filter(WordsNS::Column::SentenceId.is_in(sentences.iter().map(|x| x.id)))
+            .order_by_asc(WordsNS::Column::StartsAt)
+            .order_by_asc(WordsNS::Column::EndsAt)
+            .order_by_asc(WordsNS::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(Some(Self {
+            part,
+            sentences,
+            words,
+        }))
+    }
+
+    fn from_json(value: Option<&String>) -> Result<Option<Self>, DbErr> {
+        value
+            .map(|x| serde_json::from_str(x))
+            .transpose()
+            .map_err(|e| DbErr::Json(e.to_string()))
+    }
+
+    fn to_json(snapshot: Option<&Self>) -> Result<Option<String>, DbErr> {
+        snapshot
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| DbErr::Json(e.to_string()))
+    }
+}
+
+/// Remembers the state of all parts touched by a change, so the change can be
+/// stored as a revision once it is done
+#[derive(Default)]
+pub struct RevisionRecorder {
+    before: BTreeMap<i32, Option<PartSnapshot>>,
+}
+
+impl RevisionRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a snapshot of the part unless it already is tracked. Has to be
+    /// called before the part is modified.
+    pub async fn track<C: ConnectionTrait>(&mut self, db: &C, part_id: i32) -> Result<(), DbErr> {
+        if let Entry::Vacant(entry) = self.before.entry(part_id) {
+            entry.insert(PartSnapshot::load(db, part_id).await?);
+        }
+        Ok(())
+    }
+
+    /// Tracks a part that was created by the change
+    pub fn track_created(&mut self, part_id: i32) {
+        self.before.entry(part_id).or_insert(None);
+    }
+
+    /// Stores the revision with the current state of all tracked parts
+    pub async fn save<C: ConnectionTrait>(
+        self,
+        db: &C,
+        episode_id: i32,
+        user_id: i32,
+        action: &str,
+    ) -> Result<RevisionsNS::Model, DbErr> {
+        let revision = RevisionsNS::ActiveModel {
+            episode_id: Set(episode_id),
+            user_id: Set(user_id),
+            action: Set(action.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        for (part_id, before) in self.before {
+            let after = PartSnapshot::load(db, part_id).await?;
+            RevisionPartsNS::ActiveModel {
+                revision_id: Set(revision.id),
+                part_id: Set(part_id),
+                before: Set(PartSnapshot::to_json(before.as_ref())?),
+                after: Set(PartSnapshot::to_json(after.as_ref())?),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(revision)
+    }
+}
+
+/// What a revision changed in one part
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartDiff {
+    pub part_id: i32,
+    pub before: Option<PartsNS::Model>,
+    pub after: Option<PartsNS::Model>,
+    pub words: Vec<WordDiff>,
+}
+
+/// A word that was edited, hidden or moved to another sentence
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WordDiff {
+    pub id: i32,
+    pub before: Option<WordsNS::Model>,
+    pub after: Option<WordsNS::Model>,
+}
+
+/// Compares the states before and after the revision
+pub fn diff(revision_part: &RevisionPartsNS::Model) -> Result<PartDiff, DbErr> {
+    let before = PartSnapshot::from_json(revision_part.before.as_ref())?;
+    let after = PartSnapshot::from_json(revision_part.after.as_ref())?;
+
+    let words_before = before.iter().flat_map(|x| x.words.iter());
+    let words_after = after.iter().flat_map(|x| x.words.iter());
+    let mut words: BTreeMap<i32, WordDiff> = BTreeMap::new();
+    let empty = |id| WordDiff {
+        id,
+        before: None,
+        after: None,
+    };
+    for word in words_before {
+        words
+            .entry(word.id)
+            .or_insert_with(|| empty(word.id))
+            .before = Some(word.clone());
+    }
+    for word in words_after {
+        words.entry(word.id).or_insert_with(|| empty(word.id)).after = Some(word.clone());
+    }
+
+    Ok(PartDiff {
+        part_id: revision_part.part_id,
+        before: before.map(|x| x.part),
+        after: after.map(|x| x.part),
+        words: words
+            .into_values()
+            .filter(|x| match (&x.before, &x.after) {
+                (Some(before), Some(after)) => !same_word(before, after),
+                _ => true,
+            })
+            .collect(),
+    })
+}
+
+fn same_word(a: &WordsNS::Model, b: &WordsNS::Model) -> bool {
+    a.text == b.text
+        && a.overwrite == b.overwrite
+        && a.hidden == b.hidden
+        && a.sentence_id == b.sentence_id
+}
+
+/// Episode speakers merged into another one are replaced by that one
+async fn current_episode_speaker<C: ConnectionTrait>(
+    db: &C,
+    episode_speaker_id: i32,
+) -> Result<i32, DbErr> {
+    let merged = MergedEpisodeSpeakersNS::Entity::find()
+        .filter(MergedEpisodeSpeakersNS::Column::MergedId.eq(episode_speaker_id))
+        .one(db)
+        .await?;
+    Ok(merged.map_or(episode_speaker_id, |x| x.episode_speaker_id))
+}
+
+/// Brings all parts of a revision back into the state they had before
+/// (`use_after == false`) or after the revision. Parts that did not exist at
+/// that time are removed, removed parts and sentences are created again.
+pub async fn restore<C: ConnectionTrait>(
+    db: &C,
+    revision_parts: &[RevisionPartsNS::Model],
+    use_after: bool,
+) -> Result<(), DbErr> {
+    let mut targets: Vec<(i32, Option<PartSnapshot>)> = vec![];
+    for revision_part in revision_parts {
+        let snapshot = if use_after {
+            &revision_part.after
+        } else {
+            &revision_part.before
+        };
+        targets.push((
+            revision_part.part_id,
+            PartSnapshot::from_json(snapshot.as_ref())?,
+        ));
+    }
+    let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+
+    // Parents first, so sentences and words can be moved into them
+    for snapshot in targets.iter().filter_map(|x| x.1.as_ref()) {
+        let exists = PartsNS::Entity::find_by_id(snapshot.part.id)
+            .one(db)
+            .await?
+            .is_some();
+        let mut item = snapshot.part.clone().into_active_model().reset_all();
+        item.episode_speaker_id =
+            Set(current_episode_speaker(db, snapshot.part.episode_speaker_id).await?);
+        item.updated_at = Set(now);
+        if exists {
+            item.update(db).await?;
+        } else {
+            item.insert(db).await?;
+        }
+    }
+
+    for snapshot in targets.iter().filter_map(|x| x.1.as_ref()) {
+        for sentence in &snapshot.sentences {
+            let exists = SentencesNS::Entity::find_by_id(sentence.id)
+                .one(db)
+                .await?
+                .is_some();
+            let mut item = sentence.clone().into_active_model().reset_all();
+            item.updated_at = Set(now);
+            if exists {
+                item.update(db).await?;
+            } else {
+                item.insert(db).await?;
+            }
+        }
+
+        for word in &snapshot.words {
+            let exists = WordsNS::Entity::find_by_id(word.id)
+                .one(db)
+                .await?
+                .is_some();
+            let mut item = word.clone().into_active_model().reset_all();
+            item.updated_at = Set(now);
+            if exists {
+                item.update(db).await?;
+            } else {
+                item.insert(db).await?;
+            }
+        }
+    }
+
+    // Their words were moved back already, removing them is safe now
+    for (part_id, snapshot) in &targets {
+        match snapshot {
+            Some(snapshot) => {
+                // Words added after the snapshot go, with the anchors on them
+                let sentence_ids = SentencesNS::Entity::find()
+                    .select_only()
+                    .column(SentencesNS::Column::Id)
+                    .filter(SentencesNS::Column::PartId.eq(*part_id));
+                let added: Vec<i32> = WordsNS::Entity::find()
+                    .select_only()
+                    .column(WordsNS::Column::Id)
+                    .filter(WordsNS::Column::SentenceId.in_subquery(sentence_ids.into_query()))
+                    .filter(WordsNS::Column::Id.is_not_in(snapshot.words.iter().map(|x| x.id)))
+                    .into_tuple()
+                    .all(db)
+                    .await?;
+                part_operations::clear_anchors(db, &added).await?;
+                WordsNS::Entity::delete_many()
+                    .filter(WordsNS::Column::Id.is_in(added))
+                    .exec(db)
+                    .await?;
+
+                SentencesNS::Entity::delete_many()
+                    .filter(SentencesNS::Column::PartId.eq(*part_id))
+                    .filter(
+                        SentencesNS::Column::Id.is_not_in(snapshot.sentences.iter().map(|x| x.id)),
+                    )
+                    .exec(db)
+                    .await?;
+            }
+            None => {
+                PartsNS::Entity::delete_by_id(*part_id).exec(db).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,197 @@
-use loco_rs::prelude::*;
+use loco_rs::{config::Config, prelude::*};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 // put this in src/common/settings.rs
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Settings {
     pub from_address: Option<String>,
     pub website_url: Option<String>,
+    #[serde(default)]
+    pub downloads: DownloadSettings,
+    #[serde(default)]
+    pub transcription: TranscriptionSettings,
+    #[serde(default)]
+    pub locks: LockSettings,
+    #[serde(default)]
+    pub approvals: ApprovalSettings,
+    #[serde(default)]
+    pub review_queue: ReviewQueueSettings,
+    #[serde(default)]
+    pub spelling: SpellingSettings,
 }
 
 impl Settings {
     pub fn from_json(value: &serde_json::Value) -> Result<Self> {
         Ok(serde_json::from_value(value.clone())?)
     }
+
+    /// Reads the settings from the config, falling back to the defaults if the
+    /// config has no `settings` section
+    pub fn from_config(config: &Config) -> serde_json::Result<Self> {
+        config.settings.as_ref().map_or_else(
+            || Ok(Self::default()),
+            |x| serde_json::from_value(x.clone()),
+        )
+    }
+}
+
+/// Settings for fetching episode audio from remote servers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// Largest file (in bytes) that will be downloaded
+    pub max_size: u64,
+    pub max_attempts: i32,
+    /// Delay before the first retry, doubled for every further attempt
+    pub retry_delay_ms: u64,
+    /// How long to wait for the server to accept the connection
+    pub connect_timeout_ms: u64,
+    /// How long to wait for the next bytes of a response before retrying
+    pub read_timeout_ms: u64,
+    /// Folder for incomplete downloads, so they can be resumed
+    pub directory: PathBuf,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            max_size: 500 * 1024 * 1024,
+            max_attempts: 5,
+            retry_delay_ms: 1000,
+            connect_timeout_ms: 10_000,
+            read_timeout_ms: 30_000,
+            directory: std::env::temp_dir().join("podscribe-downloads"),
+        }
+    }
+}
+
+impl DownloadSettings {
+    pub fn partial_path(&self, download_id: i32) -> PathBuf {
+        self.directory.join(format!("{}.part", download_id))
+    }
+}
+
+/// Settings for remote transcription workers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TranscriptionSettings {
+    /// How long a job stays with a worker without a heartbeat
+    pub lease_seconds: i64,
+    pub max_attempts: i32,
+    /// Transcribe on this machine instead of waiting for remote workers
+    pub local: Option<LocalTranscriptionSettings>,
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            lease_seconds: 300,
+            max_attempts: 3,
+            local: None,
+        }
+    }
+}
+
+/// Settings for contributors claiming parts they are working on
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LockSettings {
+    /// How long a lock lasts unless it is renewed
+    pub duration_seconds: i64,
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        Self {
+            duration_seconds: 600,
+        }
+    }
+}
+
+/// When a part counts as reviewed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApprovalSettings {
+    /// Approvals a part needs
+    pub required: usize,
+    /// Whether the approval of the user who edited the part last counts
+    pub count_editor: bool,
+    /// A part is only approved once a reviewer approved it
+    pub reviewer_required: bool,
+    /// Lowest role of reviewers
+    pub reviewer_role: i32,
+}
+
+impl Default for ApprovalSettings {
+    fn default() -> Self {
+        Self {
+            required: 1,
+            count_editor: true,
+            reviewer_required: false,
+            reviewer_role: 3,
+        }
+    }
+}
+
+/// Settings for ranking parts by how likely they contain transcription errors
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReviewQueueSettings {
+    /// Words with a lower probability are likely wrong
+    pub probability_threshold: f64,
+    /// Parts returned at most
+    pub limit: usize,
+}
+
+impl Default for ReviewQueueSettings {
+    fn default() -> Self {
+        Self {
+            probability_threshold: 0.5,
+            limit: 50,
+        }
+    }
+}
+
+/// Settings for checking the spelling of parts
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SpellingSettings {
+    /// Hunspell dictionary without the extension, e.g. `/usr/share/hunspell/de_DE`
+    /// for `de_DE.aff` and `de_DE.dic`. Without one, the built-in US English
+    /// dictionary is used.
+    pub dictionary: Option<PathBuf>,
+}
+
+/// Command line that transcribes an audio file, e.g. whisper.cpp. The
+/// placeholders `{input}`, `{output}`, `{model}` and `{language}` in `args`
+/// are replaced before the command is run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalTranscriptionSettings {
+    pub command: PathBuf,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default = "default_whisper_args")]
+    pub args: Vec<String>,
+}
+
+fn default_language() -> String {
+    "auto".to_string()
+}
+
+fn default_whisper_args() -> Vec<String> {
+    [
+        "--model",
+        "{model}",
+        "--language",
+        "{language}",
+        "--output-json-full",
+        "--output-file",
+        "{output}",
+        "{input}",
+    ]
+    .map(String::from)
+    .to_vec()
 }
@@ -0,0 +1,136 @@
+use sea_orm::sea_query::Expr;
+use sea_orm::{entity::prelude::*, ActiveValue::Set, IntoActiveModel};
+use std::collections::BTreeMap;
+
+use crate::common::events::{self, EventKind};
+use crate::common::revisions::{RevisionRecorder, ACTION_MERGE_SPEAKERS};
+use crate::models::_entities::episode_speakers as EpisodeSpeakersNS;
+use crate::models::_entities::merged_episode_speakers as MergedEpisodeSpeakersNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::speaker_aliases as SpeakerAliasesNS;
+use crate::models::_entities::speaker_mappings as SpeakerMappingsNS;
+use crate::models::_entities::speakers as SpeakersNS;
+
+/// Parts that got another episode speaker, by episode. Episodes without such
+/// parts are listed too if their speakers changed.
+pub type MergedParts = BTreeMap<i32, Vec<i32>>;
+
+/// Gives all parts of `duplicate` to `target` and removes `duplicate`. Both
+/// have to belong to the same episode. The moved parts are recorded as a
+/// revision of `user_id`. Returns the ids of the moved parts.
+pub async fn merge_episode_speakers<C: ConnectionTrait>(
+    db: &C,
+    duplicate: EpisodeSpeakersNS::Model,
+    target: &EpisodeSpeakersNS::Model,
+    user_id: i32,
+) -> Result<Vec<i32>, DbErr> {
+    let part_ids: Vec<i32> = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeSpeakerId.eq(duplicate.id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    let mut recorder = RevisionRecorder::new();
+    for part_id in &part_ids {
+        recorder.track(db, *part_id).await?;
+    }
+    PartsNS::Entity::update_many()
+        .col_expr(PartsNS::Column::EpisodeSpeakerId, Expr::value(target.id))
+        .col_expr(
+            PartsNS::Column::UpdatedAt,
+            Expr::value(DateTimeWithTimeZone::from(chrono::Utc::now())),
+        )
+        .filter(PartsNS::Column::EpisodeSpeakerId.eq(duplicate.id))
+        .exec(db)
+        .await?;
+    if !part_ids.is_empty() {
+        recorder
+            .save(db, duplicate.episode_id, user_id, ACTION_MERGE_SPEAKERS)
+            .await?;
+    }
+
+    // Revisions still refer to `duplicate`, restoring them gives its parts
+    // to `target`
+    MergedEpisodeSpeakersNS::Entity::update_many()
+        .col_expr(
+            MergedEpisodeSpeakersNS::Column::EpisodeSpeakerId,
+            Expr::value(target.id),
+        )
+        .filter(MergedEpisodeSpeakersNS::Column::EpisodeSpeakerId.eq(duplicate.id))
+        .exec(db)
+        .await?;
+    MergedEpisodeSpeakersNS::ActiveModel {
+        merged_id: Set(duplicate.id),
+        episode_speaker_id: Set(target.id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    // Parts are removed together with their episode speaker, so this has to
+    // come last
+    duplicate.delete(db).await?;
+    Ok(part_ids)
+}
+
+/// Gives all episodes of `duplicate` to `target` and removes `duplicate`.
+/// Episodes in which both speak end up with a single episode speaker.
+/// Aliases and import mappings of `duplicate` now name `target`.
+pub async fn merge_speakers<C: ConnectionTrait>(
+    db: &C,
+    duplicate: SpeakersNS::Model,
+    target: &SpeakersNS::Model,
+    user_id: i32,
+) -> Result<MergedParts, DbErr> {
+    let mut merged = MergedParts::new();
+    let episode_speakers = EpisodeSpeakersNS::Entity::find()
+        .filter(EpisodeSpeakersNS::Column::SpeakerId.eq(duplicate.id))
+        .all(db)
+        .await?;
+    for episode_speaker in episode_speakers {
+        let existing = EpisodeSpeakersNS::Entity::find()
+            .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(episode_speaker.episode_id))
+            .filter(EpisodeSpeakersNS::Column::SpeakerId.eq(target.id))
+            .one(db)
+            .await?;
+        let episode_id = episode_speaker.episode_id;
+        let part_ids = match existing {
+            Some(existing) => {
+                merge_episode_speakers(db, episode_speaker, &existing, user_id).await?
+            }
+            None => {
+                let mut item = episode_speaker.into_active_model();
+                item.speaker_id = Set(target.id);
+                item.update(db).await?;
+                vec![]
+            }
+        };
+        merged.entry(episode_id).or_default().extend(part_ids);
+    }
+
+    SpeakerAliasesNS::Entity::update_many()
+        .col_expr(SpeakerAliasesNS::Column::SpeakerId, Expr::value(target.id))
+        .filter(SpeakerAliasesNS::Column::SpeakerId.eq(duplicate.id))
+        .exec(db)
+        .await?;
+    SpeakerMappingsNS::Entity::update_many()
+        .col_expr(SpeakerMappingsNS::Column::SpeakerId, Expr::value(target.id))
+        .filter(SpeakerMappingsNS::Column::SpeakerId.eq(duplicate.id))
+        .exec(db)
+        .await?;
+
+    duplicate.delete(db).await?;
+    Ok(merged)
+}
+
+/// Tells the viewers of the episodes about the merge. Only call this once
+/// the merge is committed.
+pub fn publish(merged: &MergedParts) {
+    for (episode_id, part_ids) in merged {
+        events::publish(*episode_id, EventKind::SpeakersChanged);
+        for part_id in part_ids {
+            events::publish(*episode_id, EventKind::PartUpdated { part_id: *part_id });
+        }
+    }
+}
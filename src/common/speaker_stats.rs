@@ -0,0 +1,255 @@
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
+use sea_orm::{entity::prelude::*, FromQueryResult, JoinType, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::_entities::episode_speakers as EpisodeSpeakersNS;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::words as WordsNS;
+use crate::models::parts::PART_TYPE_SPEECH;
+
+/// What one episode speaker said in an episode
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EpisodeShare {
+    pub episode_id: i32,
+    pub episode_speaker_id: i32,
+    pub speaker_id: i32,
+    /// Seconds
+    pub talk_time: f64,
+    pub word_count: i64,
+    pub words_per_second: Option<f64>,
+    /// Part of the talk time of the whole episode, between 0 and 1
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EpisodeStats {
+    pub talk_time: f64,
+    pub speakers: Vec<EpisodeShare>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeakerStats {
+    pub speaker_id: i32,
+    pub talk_time: f64,
+    pub word_count: i64,
+    pub words_per_second: Option<f64>,
+    pub episode_count: usize,
+    pub first_appearance: Option<EpisodesNS::Model>,
+    pub last_appearance: Option<EpisodesNS::Model>,
+    pub episodes: Vec<EpisodeShare>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TalkTimeRow {
+    episode_speaker_id: i32,
+    talk_time: Option<f64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CountRow {
+    episode_speaker_id: i32,
+    count: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SpeedRow {
+    episode_speaker_id: i32,
+    total: Option<f64>,
+    count: i64,
+}
+
+/// Sums of one episode speaker. Averages are kept as sum and count, so they
+/// can be combined over several episodes.
+#[derive(Clone, Copy, Debug, Default)]
+struct Totals {
+    talk_time: f64,
+    word_count: i64,
+    speed_total: f64,
+    speed_count: i64,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Self) {
+        self.talk_time += other.talk_time;
+        self.word_count += other.word_count;
+        self.speed_total += other.speed_total;
+        self.speed_count += other.speed_count;
+    }
+
+    fn words_per_second(&self) -> Option<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        (self.speed_count > 0).then(|| self.speed_total / self.speed_count as f64)
+    }
+}
+
+/// Totals of every episode speaker that said something in the episodes
+async fn load_totals<C: ConnectionTrait>(
+    db: &C,
+    episode_ids: &[i32],
+) -> Result<HashMap<i32, Totals>, DbErr> {
+    let speech = || {
+        PartsNS::Column::EpisodeId
+            .is_in(episode_ids.iter().copied())
+            .and(PartsNS::Column::PartType.eq(PART_TYPE_SPEECH))
+    };
+    let mut totals = HashMap::<i32, Totals>::new();
+
+    let duration: SimpleExpr = Expr::col((PartsNS::Entity, PartsNS::Column::EndsAt))
+        .sub(Expr::col((PartsNS::Entity, PartsNS::Column::StartsAt)));
+    let rows = PartsNS::Entity::find()
+        .select_only()
+        .column(PartsNS::Column::EpisodeSpeakerId)
+        .column_as(SimpleExpr::from(Func::sum(duration)), "talk_time")
+        .filter(speech())
+        .group_by(PartsNS::Column::EpisodeSpeakerId)
+        .into_model::<TalkTimeRow>()
+        .all(db)
+        .await?;
+    for row in rows {
+        totals.entry(row.episode_speaker_id).or_default().talk_time = row.talk_time.unwrap_or(0.0);
+    }
+
+    let rows = WordsNS::Entity::find()
+        .select_only()
+        .column_as(PartsNS::Column::EpisodeSpeakerId, "episode_speaker_id")
+        .column_as(WordsNS::Column::Id.count(), "count")
+        .join(JoinType::InnerJoin, WordsNS::Relation::Sentences.def())
+        .join(JoinType::InnerJoin, SentencesNS::Relation::Parts.def())
+        .filter(speech())
+        .filter(WordsNS::Column::Hidden.eq(false))
+        .group_by(PartsNS::Column::EpisodeSpeakerId)
+        .into_model::<CountRow>()
+        .all(db)
+        .await?;
+    for row in rows {
+        totals.entry(row.episode_speaker_id).or_default().word_count = row.count;
+    }
+
+    let rows = SentencesNS::Entity::find()
+        .select_only()
+        .column_as(PartsNS::Column::EpisodeSpeakerId, "episode_speaker_id")
+        .column_as(SentencesNS::Column::WordsPerSecond.sum(), "total")
+        .column_as(SentencesNS::Column::Id.count(), "count")
+        .join(JoinType::InnerJoin, SentencesNS::Relation::Parts.def())
+        .filter(speech())
+        .group_by(PartsNS::Column::EpisodeSpeakerId)
+        .into_model::<SpeedRow>()
+        .all(db)
+        .await?;
+    for row in rows {
+        let entry = totals.entry(row.episode_speaker_id).or_default();
+        entry.speed_total = row.total.unwrap_or(0.0);
+        entry.speed_count = row.count;
+    }
+
+    Ok(totals)
+}
+
+/// Shares of the given episode speakers. Talk time of each episode is summed
+/// over all of its episode speakers.
+fn shares(
+    episode_speakers: &[EpisodeSpeakersNS::Model],
+    totals: &HashMap<i32, Totals>,
+    selected: impl Fn(&EpisodeSpeakersNS::Model) -> bool,
+) -> Vec<EpisodeShare> {
+    let mut episode_talk_time = HashMap::<i32, f64>::new();
+    for episode_speaker in episode_speakers {
+        let talk_time = totals.get(&episode_speaker.id).map_or(0.0, |x| x.talk_time);
+        *episode_talk_time
+            .entry(episode_speaker.episode_id)
+            .or_default() += talk_time;
+    }
+
+    episode_speakers
+        .iter()
+        .filter(|x| selected(x))
+        .map(|x| {
+            let own = totals.get(&x.id).copied().unwrap_or_default();
+            let total = episode_talk_time.get(&x.episode_id).copied().unwrap_or(0.0);
+            EpisodeShare {
+                episode_id: x.episode_id,
+                episode_speaker_id: x.id,
+                speaker_id: x.speaker_id,
+                talk_time: own.talk_time,
+                word_count: own.word_count,
+                words_per_second: own.words_per_second(),
+                share: if total > 0.0 {
+                    own.talk_time / total
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect()
+}
+
+/// Statistics of all speakers of the episode
+pub async fn episode_stats<C: ConnectionTrait>(
+    db: &C,
+    episode_id: i32,
+) -> Result<EpisodeStats, DbErr> {
+    let episode_speakers = EpisodeSpeakersNS::Entity::find()
+        .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(episode_id))
+        .all(db)
+        .await?;
+    let totals = load_totals(db, &[episode_id]).await?;
+    let speakers = shares(&episode_speakers, &totals, |_| true);
+    Ok(EpisodeStats {
+        talk_time: speakers.iter().map(|x| x.talk_time).sum(),
+        speakers,
+    })
+}
+
+/// Statistics of the speaker over all episodes. Episodes only count as an
+/// appearance if the speaker says something in them.
+pub async fn speaker_stats<C: ConnectionTrait>(
+    db: &C,
+    speaker_id: i32,
+) -> Result<SpeakerStats, DbErr> {
+    let episode_ids: Vec<i32> = EpisodeSpeakersNS::Entity::find()
+        .filter(EpisodeSpeakersNS::Column::SpeakerId.eq(speaker_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.episode_id)
+        .collect();
+    let episode_speakers = EpisodeSpeakersNS::Entity::find()
+        .filter(EpisodeSpeakersNS::Column::EpisodeId.is_in(episode_ids.clone()))
+        .all(db)
+        .await?;
+    let totals = load_totals(db, &episode_ids).await?;
+
+    let mut sum = Totals::default();
+    for episode_speaker in episode_speakers
+        .iter()
+        .filter(|x| x.speaker_id == speaker_id)
+    {
+        if let Some(totals) = totals.get(&episode_speaker.id) {
+            sum.add(totals);
+        }
+    }
+    let episodes: Vec<EpisodeShare> = shares(&episode_speakers, &totals, |x| {
+        x.speaker_id == speaker_id && totals.contains_key(&x.id)
+    });
+
+    // Episodes without a publication date count from when they were added
+    let mut appearances = EpisodesNS::Entity::find()
+        .filter(EpisodesNS::Column::Id.is_in(episodes.iter().map(|x| x.episode_id)))
+        .all(db)
+        .await?;
+    appearances.sort_by_key(|x| x.published_at.unwrap_or(x.created_at));
+
+    Ok(SpeakerStats {
+        speaker_id,
+        talk_time: sum.talk_time,
+        word_count: sum.word_count,
+        words_per_second: sum.words_per_second(),
+        episode_count: appearances.len(),
+        first_appearance: appearances.first().cloned(),
+        last_appearance: appearances.last().cloned(),
+        episodes,
+    })
+}
@@ -0,0 +1,230 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::common::import::{ImportPart, ImportSentence, ImportTranscription, ImportWord};
+use crate::common::settings::LocalTranscriptionSettings;
+
+/// Only the end of the log is kept, that is where the errors are
+const MAX_LOG_LENGTH: usize = 64 * 1024;
+
+/// Pause (in seconds) after which a new part is started when the backend
+/// does not tell the speakers apart
+const PART_BREAK_PAUSE: f64 = 2.0;
+
+/// Speaker assigned to all parts, contributors sort them out afterwards
+const UNKNOWN_SPEAKER: &str = "SPEAKER_00";
+
+/// Turns an audio file into a transcription
+#[async_trait]
+pub trait TranscriptionBackend: Send + Sync {
+    async fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscriptionError>;
+}
+
+pub struct Transcript {
+    pub transcription: ImportTranscription,
+    pub log: String,
+}
+
+#[derive(Debug)]
+pub struct TranscriptionError {
+    pub message: String,
+    pub log: String,
+}
+
+impl std::fmt::Display for TranscriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TranscriptionError {}
+
+/// Runs a command line, e.g. the whisper.cpp cli. The result is read from
+/// `{output}.json` if the command created it, from stdout otherwise. Both the
+/// import format and the full JSON output of whisper.cpp are understood.
+pub struct CommandBackend {
+    settings: LocalTranscriptionSettings,
+}
+
+impl CommandBackend {
+    pub fn new(settings: LocalTranscriptionSettings) -> Self {
+        Self { settings }
+    }
+
+    fn args(&self, input: &Path, output: &Path) -> Vec<String> {
+        self.settings
+            .args
+            .iter()
+            .map(|x| {
+                x.replace("{input}", &input.to_string_lossy())
+                    .replace("{output}", &output.to_string_lossy())
+                    .replace("{model}", &self.settings.model)
+                    .replace("{language}", &self.settings.language)
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for CommandBackend {
+    async fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscriptionError> {
+        let output_prefix = audio_path.with_extension("");
+        let output_path = PathBuf::from(format!("{}.json", output_prefix.to_string_lossy()));
+
+        let output = tokio::process::Command::new(&self.settings.command)
+            .args(self.args(audio_path, &output_prefix))
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| TranscriptionError {
+                message: format!("Unable to run {}: {}", self.settings.command.display(), e),
+                log: String::new(),
+            })?;
+
+        let log = tail(&String::from_utf8_lossy(&output.stderr));
+        if !output.status.success() {
+            return Err(TranscriptionError {
+                message: format!(
+                    "{} failed: {}",
+                    self.settings.command.display(),
+                    output.status
+                ),
+                log,
+            });
+        }
+
+        let content = match tokio::fs::read(&output_path).await {
+            Ok(content) => content,
+            Err(_) => output.stdout,
+        };
+        match parse(&content) {
+            Ok(transcription) => Ok(Transcript { transcription, log }),
+            Err(e) => Err(TranscriptionError {
+                message: format!("Unable to parse the transcription: {}", e),
+                log,
+            }),
+        }
+    }
+}
+
+fn tail(log: &str) -> String {
+    let mut start = log.len().saturating_sub(MAX_LOG_LENGTH);
+    while !log.is_char_boundary(start) {
+        start += 1;
+    }
+    log[start..].to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CommandOutput {
+    Import(ImportTranscription),
+    Whisper(WhisperOutput),
+}
+
+#[derive(Deserialize)]
+struct WhisperOutput {
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Deserialize)]
+struct WhisperSegment {
+    offsets: WhisperOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperToken>,
+    /// Set by whisper.cpp when tinydiarize detects a new speaker
+    #[serde(default)]
+    speaker_turn_next: bool,
+}
+
+#[derive(Deserialize)]
+struct WhisperOffsets {
+    /// Milliseconds
+    from: i64,
+    to: i64,
+}
+
+#[derive(Deserialize)]
+struct WhisperToken {
+    text: String,
+    offsets: WhisperOffsets,
+    p: f64,
+}
+
+/// Parses the output of a transcription command into the import structures
+pub fn parse(content: &[u8]) -> serde_json::Result<ImportTranscription> {
+    match serde_json::from_slice(content)? {
+        CommandOutput::Import(transcription) => Ok(transcription),
+        CommandOutput::Whisper(output) => Ok(convert_whisper(output)),
+    }
+}
+
+/// Same conversion as `tools/transcribe.py`: every segment becomes a
+/// sentence, tokens without a leading space are appended to the last word.
+fn convert_whisper(output: WhisperOutput) -> ImportTranscription {
+    let mut parts: Vec<ImportPart> = vec![];
+    let mut new_part = true;
+
+    for segment in output.transcription {
+        let start = segment.offsets.from as f64 / 1000.0;
+        let end = segment.offsets.to as f64 / 1000.0;
+        if end <= start {
+            continue;
+        }
+
+        let mut words: Vec<ImportWord> = vec![];
+        for token in segment.tokens {
+            if token.text.starts_with('[') {
+                continue;
+            }
+
+            let token_end = token.offsets.to as f64 / 1000.0;
+            match words.last_mut() {
+                Some(word) if !token.text.starts_with(' ') => {
+                    word.text.push_str(&token.text);
+                    word.end = token_end;
+                    word.probability = word.probability.min(token.p);
+                }
+                _ => words.push(ImportWord {
+                    text: token.text.trim().to_string(),
+                    start: token.offsets.from as f64 / 1000.0,
+                    end: token_end,
+                    probability: token.p,
+                }),
+            }
+        }
+
+        let text = segment.text.trim().to_string();
+        let sentence = ImportSentence {
+            words_per_second: text.split_whitespace().count() as f64 / (end - start),
+            text,
+            words,
+            start,
+            end,
+        };
+
+        match parts.last_mut() {
+            Some(part) if !new_part && start - part.end < PART_BREAK_PAUSE => {
+                part.text = format!("{} {}", part.text, sentence.text);
+                part.end = end;
+                part.sentences.push(sentence);
+            }
+            _ => parts.push(ImportPart {
+                start,
+                end,
+                speaker: UNKNOWN_SPEAKER.to_string(),
+                text: sentence.text.clone(),
+                sentences: vec![sentence],
+            }),
+        }
+        new_part = segment.speaker_turn_next;
+    }
+
+    ImportTranscription {
+        transcription: parts,
+        speakers: HashMap::new(),
+    }
+}
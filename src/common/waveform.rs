@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Zoom levels (samples per pixel) that are precomputed for every episode.
+/// Each level is a multiple of the first one so coarser levels can be derived
+/// from the finest one without decoding the audio again.
+pub const ZOOM_LEVELS: [u32; 5] = [256, 512, 1024, 2048, 4096];
+
+/// Zoom level used when the client does not ask for a specific one
+pub const DEFAULT_ZOOM_LEVEL: u32 = 512;
+
+/// Peak data in the JSON format of
+/// [audiowaveform](https://github.com/bbc/audiowaveform/blob/master/doc/DataFormat.md)
+/// (version 2, one channel, 8 bit resolution). `data` holds alternating min
+/// and max values, one pair per pixel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Waveform {
+    pub version: u32,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub samples_per_pixel: u32,
+    pub bits: u32,
+    pub length: usize,
+    pub data: Vec<i8>,
+}
+
+impl Waveform {
+    /// Location of the peak data of an episode in the storage
+    pub fn storage_path(episode_id: i32, samples_per_pixel: u32) -> PathBuf {
+        PathBuf::from("waveforms").join(format!("{}-{}.json", episode_id, samples_per_pixel))
+    }
+
+    /// Combines `factor` neighbouring pixels into one
+    pub fn downsample(&self, factor: u32) -> Waveform {
+        let factor = factor.max(1) as usize;
+        let data: Vec<i8> = self
+            .data
+            .chunks(2 * factor)
+            .flat_map(|pixels| {
+                let min = pixels.iter().step_by(2).copied().min().unwrap_or(0);
+                let max = pixels.iter().skip(1).step_by(2).copied().max().unwrap_or(0);
+                [min, max]
+            })
+            .collect();
+
+        Waveform {
+            samples_per_pixel: self.samples_per_pixel * factor as u32,
+            length: data.len() / 2,
+            data,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the pixels between `start` and `end` (in seconds). The range is
+    /// widened to full pixels.
+    pub fn slice(&self, start: Option<f64>, end: Option<f64>) -> Waveform {
+        let seconds_per_pixel = f64::from(self.samples_per_pixel) / f64::from(self.sample_rate);
+        let first = start
+            .map_or(0, |x| (x.max(0.0) / seconds_per_pixel).floor() as usize)
+            .min(self.length);
+        let last = end
+            .map_or(self.length, |x| {
+                (x.max(0.0) / seconds_per_pixel).ceil() as usize
+            })
+            .clamp(first, self.length);
+
+        Waveform {
+            length: last - first,
+            data: self.data[2 * first..2 * last].to_vec(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Collects min/max peaks while the audio is being decoded
+pub struct PeakCollector {
+    samples_per_pixel: u32,
+    sample_rate: u32,
+    count: u32,
+    min: f32,
+    max: f32,
+    data: Vec<i8>,
+}
+
+impl PeakCollector {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            sample_rate: 0,
+            count: 0,
+            min: f32::MAX,
+            max: f32::MIN,
+            data: vec![],
+        }
+    }
+
+    pub fn push(&mut self, sample_rate: u32, samples: &[f32]) {
+        self.sample_rate = sample_rate;
+        for sample in samples {
+            self.min = self.min.min(*sample);
+            self.max = self.max.max(*sample);
+            self.count += 1;
+
+            if self.count == self.samples_per_pixel {
+                self.flush();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Waveform {
+        if self.count > 0 {
+            self.flush();
+        }
+
+        Waveform {
+            version: 2,
+            channels: 1,
+            sample_rate: self.sample_rate,
+            samples_per_pixel: self.samples_per_pixel,
+            bits: 8,
+            length: self.data.len() / 2,
+            data: self.data,
+        }
+    }
+
+    fn flush(&mut self) {
+        self.data.push(to_8_bit(self.min));
+        self.data.push(to_8_bit(self.max));
+        self.count = 0;
+        self.min = f32::MAX;
+        self.max = f32::MIN;
+    }
+}
+
+fn to_8_bit(sample: f32) -> i8 {
+    (sample.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
@@ -0,0 +1,399 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use axum::extract::Query;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::common::check_auth;
+use crate::common::review::{self, PartStatus, Progress};
+use crate::common::settings::Settings;
+use crate::models::_entities::assignments::{ActiveModel, Column, Entity, Model};
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::users as UsersNS;
+use crate::models::assignments::{STATUSES, STATUS_DONE, STATUS_OPEN};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub episode_id: i32,
+    pub user_id: i32,
+    /// Without a time range the whole episode is assigned
+    pub starts_at: Option<f64>,
+    pub ends_at: Option<f64>,
+    pub due_at: Option<DateTimeWithTimeZone>,
+}
+
+impl Params {
+    fn update(&self, item: &mut ActiveModel) {
+        item.episode_id = Set(self.episode_id);
+        item.user_id = Set(self.user_id);
+        item.starts_at = Set(self.starts_at);
+        item.ends_at = Set(self.ends_at);
+        item.due_at = Set(self.due_at);
+    }
+
+    fn validate(&self) -> std::result::Result<(), &'static str> {
+        if let (Some(starts_at), Some(ends_at)) = (self.starts_at, self.ends_at) {
+            if ends_at <= starts_at {
+                return Err("Time range is empty");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusParams {
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReassignParams {
+    pub user_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ListQueryParams {
+    episode_id: Option<i32>,
+    user_id: Option<i32>,
+}
+
+/// An assignment with the review state of its parts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssignmentDisplay {
+    pub assignment: Model,
+    pub user_name: String,
+    pub episode_title: String,
+    /// Due, but not done yet
+    pub overdue: bool,
+    pub progress: Progress,
+}
+
+/// Everything assigned to one user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssigneeOverview {
+    pub user_id: i32,
+    pub user_name: String,
+    pub assignments: usize,
+    pub done: usize,
+    pub overdue: usize,
+    /// Review state of all assigned parts
+    pub progress: Progress,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+async fn check_references(ctx: &AppContext, episode_id: i32, user_id: i32) -> Result<()> {
+    EpisodesNS::Entity::find_by_id(episode_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::BadRequest("Episode does not exist".to_string()))?;
+    UsersNS::Entity::find_by_id(user_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::BadRequest("User does not exist".to_string()))?;
+    Ok(())
+}
+
+fn is_overdue(item: &Model, now: DateTimeWithTimeZone) -> bool {
+    item.status != STATUS_DONE && item.due_at.is_some_and(|x| x < now)
+}
+
+/// Ids of the parts within each assignment, by assignment id. The parts of
+/// all episodes are loaded at once.
+async fn assigned_parts(ctx: &AppContext, items: &[Model]) -> Result<HashMap<i32, Vec<i32>>> {
+    let episode_ids: BTreeSet<i32> = items.iter().map(|x| x.episode_id).collect();
+    let mut parts = HashMap::<i32, Vec<PartsNS::Model>>::new();
+    for part in PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeId.is_in(episode_ids))
+        .order_by_asc(PartsNS::Column::StartsAt)
+        .all(&ctx.db)
+        .await?
+    {
+        parts.entry(part.episode_id).or_default().push(part);
+    }
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let selection = item.selection();
+            let part_ids = parts
+                .get(&item.episode_id)
+                .map(|parts| {
+                    parts
+                        .iter()
+                        .filter(|x| selection.includes(x))
+                        .map(|x| x.id)
+                        .collect()
+                })
+                .unwrap_or_default();
+            (item.id, part_ids)
+        })
+        .collect())
+}
+
+/// Statuses of all parts of the assignments
+async fn assigned_statuses(
+    ctx: &AppContext,
+    parts: &HashMap<i32, Vec<i32>>,
+) -> Result<BTreeMap<i32, PartStatus>> {
+    let settings = Settings::from_config(&ctx.config)?.approvals;
+    let part_ids: Vec<i32> = parts
+        .values()
+        .flatten()
+        .copied()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Ok(review::statuses(&ctx.db, &settings, &part_ids).await?)
+}
+
+/// Progress of the given parts
+fn progress_of<'a>(
+    statuses: &BTreeMap<i32, PartStatus>,
+    part_ids: impl IntoIterator<Item = &'a i32>,
+) -> Progress {
+    let statuses: BTreeMap<i32, PartStatus> = part_ids
+        .into_iter()
+        .filter_map(|x| statuses.get(x).map(|status| (*x, *status)))
+        .collect();
+    review::progress(&statuses)
+}
+
+async fn load_display(ctx: &AppContext, items: Vec<Model>) -> Result<Vec<AssignmentDisplay>> {
+    let users: HashMap<i32, String> = UsersNS::Entity::find()
+        .filter(UsersNS::Column::Id.is_in(items.iter().map(|x| x.user_id)))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.name))
+        .collect();
+    let episodes: HashMap<i32, String> = EpisodesNS::Entity::find()
+        .filter(EpisodesNS::Column::Id.is_in(items.iter().map(|x| x.episode_id)))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.title))
+        .collect();
+    let parts = assigned_parts(ctx, &items).await?;
+    let statuses = assigned_statuses(ctx, &parts).await?;
+    let now = chrono::Utc::now().into();
+
+    Ok(items
+        .into_iter()
+        .map(|item| AssignmentDisplay {
+            user_name: users.get(&item.user_id).cloned().unwrap_or_default(),
+            episode_title: episodes.get(&item.episode_id).cloned().unwrap_or_default(),
+            overdue: is_overdue(&item, now),
+            progress: progress_of(&statuses, parts.get(&item.id).into_iter().flatten()),
+            assignment: item,
+        })
+        .collect())
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let mut query = Entity::find();
+    if let Some(episode_id) = params.episode_id {
+        query = query.filter(Column::EpisodeId.eq(episode_id));
+    }
+    if let Some(user_id) = params.user_id {
+        query = query.filter(Column::UserId.eq(user_id));
+    }
+    let items = query.order_by_asc(Column::Id).all(&ctx.db).await?;
+    format::json(load_display(&ctx, items).await?)
+}
+
+/// Work assigned to the user, soonest due first
+#[debug_handler]
+pub async fn mine(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let mut items = Entity::find()
+        .filter(Column::UserId.eq(auth.user.id))
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await?;
+    items.sort_by_key(|x| (x.status == STATUS_DONE, x.due_at.is_none(), x.due_at));
+    format::json(load_display(&ctx, items).await?)
+}
+
+/// Progress of every user with assignments
+#[debug_handler]
+pub async fn overview(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let items = Entity::find().all(&ctx.db).await?;
+    let users: HashMap<i32, String> = UsersNS::Entity::find()
+        .filter(UsersNS::Column::Id.is_in(items.iter().map(|x| x.user_id)))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.name))
+        .collect();
+    let parts = assigned_parts(&ctx, &items).await?;
+    let statuses = assigned_statuses(&ctx, &parts).await?;
+    let now = chrono::Utc::now().into();
+
+    let mut by_user = BTreeMap::<i32, Vec<Model>>::new();
+    for item in items {
+        by_user.entry(item.user_id).or_default().push(item);
+    }
+
+    let mut output = vec![];
+    for (user_id, items) in by_user {
+        // Assignments may overlap, every part counts once
+        let part_ids: BTreeSet<i32> = items
+            .iter()
+            .filter_map(|x| parts.get(&x.id))
+            .flatten()
+            .copied()
+            .collect();
+        output.push(AssigneeOverview {
+            user_id,
+            user_name: users.get(&user_id).cloned().unwrap_or_default(),
+            assignments: items.len(),
+            done: items.iter().filter(|x| x.status == STATUS_DONE).count(),
+            overdue: items.iter().filter(|x| is_overdue(x, now)).count(),
+            progress: progress_of(&statuses, &part_ids),
+        });
+    }
+    format::json(output)
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    check_references(&ctx, params.episode_id, params.user_id).await?;
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item);
+    item.status = Set(STATUS_OPEN.to_string());
+    let item = item.insert(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn update(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    check_references(&ctx, params.episode_id, params.user_id).await?;
+    let item = load_item(&ctx, id).await?;
+    let mut item = item.into_active_model();
+    params.update(&mut item);
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+/// Gives the work to another user, who starts it from the beginning
+#[debug_handler]
+pub async fn reassign(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<ReassignParams>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    check_references(&ctx, item.episode_id, params.user_id).await?;
+    let mut item = item.into_active_model();
+    item.user_id = Set(params.user_id);
+    item.status = Set(STATUS_OPEN.to_string());
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+/// The assignee reports how far they are
+#[debug_handler]
+pub async fn update_status(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<StatusParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    if item.user_id != auth.user.id {
+        check_auth::check_admin(&auth.user)?;
+    }
+    if !STATUSES.contains(&params.status.as_str()) {
+        return Err(Error::BadRequest(format!(
+            "Status has to be one of {}",
+            STATUSES.join(", ")
+        )));
+    }
+    let mut item = item.into_active_model();
+    item.status = Set(params.status);
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    let mut output = load_display(&ctx, vec![item]).await?;
+    format::json(output.pop())
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/assignments/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("mine", get(mine))
+        .add("overview", get(overview))
+        .add("{id}", get(get_one))
+        .add("{id}", delete(remove))
+        .add("{id}", put(update))
+        .add("{id}", patch(update))
+        .add("{id}/reassign", post(reassign))
+        .add("{id}/status", post(update_status))
+}
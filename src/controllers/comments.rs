@@ -0,0 +1,297 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use axum::extract::Query;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::{JoinType, QueryOrder, QuerySelect, RelationTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::common::check_auth;
+use crate::common::events::{self, EventKind};
+use crate::models::_entities::comments::{ActiveModel, Column, Entity, Model, Relation};
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::users as UsersNS;
+use crate::models::_entities::words as WordsNS;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub text: String,
+    /// Words the comment is about, the whole part without them
+    pub from_word_id: Option<i32>,
+    pub to_word_id: Option<i32>,
+    /// Comment this one replies to
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextParams {
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResolveParams {
+    pub resolved: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ListQueryParams {
+    resolved: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentDisplay {
+    pub comment: Model,
+    pub user_name: String,
+}
+
+/// A comment with all of its replies, oldest first
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Thread {
+    pub comment: CommentDisplay,
+    pub replies: Vec<CommentDisplay>,
+}
+
+async fn load_part(ctx: &AppContext, episode_id: i32, part_id: i32) -> Result<PartsNS::Model> {
+    let part = PartsNS::Entity::find_by_id(part_id)
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .one(&ctx.db)
+        .await?;
+    part.ok_or_else(|| Error::NotFound)
+}
+
+async fn load_item(ctx: &AppContext, part_id: i32, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id)
+        .filter(Column::PartId.eq(part_id))
+        .one(&ctx.db)
+        .await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+/// Checks that the anchor consists of words of the part
+async fn check_anchor(ctx: &AppContext, part_id: i32, params: &Params) -> Result<()> {
+    let word_ids: Vec<i32> = match (params.from_word_id, params.to_word_id) {
+        (None, None) => return Ok(()),
+        (Some(from), Some(to)) => vec![from, to],
+        (Some(id), None) | (None, Some(id)) => vec![id],
+    };
+    let found = WordsNS::Entity::find()
+        .join(JoinType::InnerJoin, WordsNS::Relation::Sentences.def())
+        .filter(SentencesNS::Column::PartId.eq(part_id))
+        .filter(WordsNS::Column::Id.is_in(word_ids.clone()))
+        .all(&ctx.db)
+        .await?;
+    if word_ids.iter().any(|id| !found.iter().any(|x| x.id == *id)) {
+        return Err(Error::BadRequest(
+            "Words are not part of the part".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Groups the comments into threads, in the order of their first comment
+async fn load_threads(ctx: &AppContext, comments: Vec<Model>) -> Result<Vec<Thread>> {
+    let users: HashMap<i32, String> = UsersNS::Entity::find()
+        .filter(UsersNS::Column::Id.is_in(comments.iter().map(|x| x.user_id)))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.name))
+        .collect();
+    let display = |comment: Model| CommentDisplay {
+        user_name: users.get(&comment.user_id).cloned().unwrap_or_default(),
+        comment,
+    };
+
+    let mut replies = BTreeMap::<i32, Vec<CommentDisplay>>::new();
+    let mut threads = vec![];
+    for comment in comments {
+        match comment.parent_id {
+            Some(parent_id) => replies.entry(parent_id).or_default().push(display(comment)),
+            None => threads.push(display(comment)),
+        }
+    }
+    Ok(threads
+        .into_iter()
+        .map(|comment| Thread {
+            replies: replies.remove(&comment.comment.id).unwrap_or_default(),
+            comment,
+        })
+        .collect())
+}
+
+/// Threads and their replies of the given root comments
+async fn threads_of(ctx: &AppContext, roots: Vec<Model>) -> Result<Vec<Thread>> {
+    let replies = Entity::find()
+        .filter(Column::ParentId.is_in(roots.iter().map(|x| x.id)))
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await?;
+    load_threads(ctx, roots.into_iter().chain(replies).collect()).await
+}
+
+/// Threads of the whole episode, only open ones unless asked otherwise
+#[debug_handler]
+pub async fn list_episode(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(episode_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let roots = Entity::find()
+        .join(JoinType::InnerJoin, Relation::Parts.def())
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .filter(Column::ParentId.is_null())
+        .filter(Column::Resolved.eq(params.resolved.unwrap_or(false)))
+        .order_by_asc(PartsNS::Column::StartsAt)
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await?;
+    format::json(threads_of(&ctx, roots).await?)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    load_part(&ctx, episode_id, part_id).await?;
+    let roots = Entity::find()
+        .filter(Column::PartId.eq(part_id))
+        .filter(Column::ParentId.is_null())
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await?;
+    format::json(threads_of(&ctx, roots).await?)
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_part(&ctx, episode_id, part_id).await?;
+    if params.text.trim().is_empty() {
+        return Err(Error::BadRequest("Comment is empty".to_string()));
+    }
+    if let Some(parent_id) = params.parent_id {
+        // Threads are flat, replies always go to the first comment
+        let parent = load_item(&ctx, part_id, parent_id).await?;
+        if parent.parent_id.is_some() {
+            return Err(Error::BadRequest(
+                "Replies can not be replied to".to_string(),
+            ));
+        }
+        if params.from_word_id.is_some() || params.to_word_id.is_some() {
+            return Err(Error::BadRequest("Replies can not be anchored".to_string()));
+        }
+    }
+    check_anchor(&ctx, part_id, &params).await?;
+
+    let item = ActiveModel {
+        text: Set(params.text),
+        from_word_id: Set(params.from_word_id),
+        to_word_id: Set(params.to_word_id),
+        parent_id: Set(params.parent_id),
+        resolved: Set(false),
+        part_id: Set(part_id),
+        user_id: Set(auth.user.id),
+        ..Default::default()
+    };
+    let item = item.insert(&ctx.db).await?;
+    events::publish(episode_id, EventKind::CommentsChanged { part_id });
+    format::json(item)
+}
+
+/// Only the author can change the text
+#[debug_handler]
+pub async fn update(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id, id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<TextParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_part(&ctx, episode_id, part_id).await?;
+    let item = load_item(&ctx, part_id, id).await?;
+    if item.user_id != auth.user.id {
+        return Err(Error::Unauthorized(
+            "Only the author can edit a comment".to_string(),
+        ));
+    }
+    if params.text.trim().is_empty() {
+        return Err(Error::BadRequest("Comment is empty".to_string()));
+    }
+    let mut item = item.into_active_model();
+    item.text = Set(params.text);
+    let item = item.update(&ctx.db).await?;
+    events::publish(episode_id, EventKind::CommentsChanged { part_id });
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn resolve(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id, id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<ResolveParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_part(&ctx, episode_id, part_id).await?;
+    let item = load_item(&ctx, part_id, id).await?;
+    if item.parent_id.is_some() {
+        return Err(Error::BadRequest(
+            "Only whole threads can be resolved".to_string(),
+        ));
+    }
+    let mut item = item.into_active_model();
+    item.resolved = Set(params.resolved);
+    let item = item.update(&ctx.db).await?;
+    events::publish(episode_id, EventKind::CommentsChanged { part_id });
+    format::json(item)
+}
+
+/// Removing the first comment of a thread removes its replies too
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id, id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_part(&ctx, episode_id, part_id).await?;
+    let item = load_item(&ctx, part_id, id).await?;
+    if item.user_id != auth.user.id {
+        check_auth::check_admin(&auth.user)?;
+    }
+    let txn = ctx.db.begin().await?;
+    Entity::delete_many()
+        .filter(Column::ParentId.eq(item.id))
+        .exec(&txn)
+        .await?;
+    item.delete(&txn).await?;
+    txn.commit().await?;
+    events::publish(episode_id, EventKind::CommentsChanged { part_id });
+    format::empty()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/episodes/{episode_id}/")
+        .add("comments", get(list_episode))
+        .add("parts/{part_id}/comments", get(list))
+        .add("parts/{part_id}/comments", post(add))
+        .add("parts/{part_id}/comments/{id}", put(update))
+        .add("parts/{part_id}/comments/{id}", patch(update))
+        .add("parts/{part_id}/comments/{id}", delete(remove))
+        .add("parts/{part_id}/comments/{id}/resolve", post(resolve))
+}
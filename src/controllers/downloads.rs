@@ -0,0 +1,104 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_auth;
+use crate::models::_entities::downloads::{ActiveModel, Column, Entity, Model};
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::downloads::STATUS_QUEUED;
+use crate::workers::downloader::{DownloadWorker, DownloadWorkerArgs};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    /// Falls back to the `audio_url` of the episode
+    pub url: Option<String>,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(episode_id): Path<i32>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    format::json(
+        Entity::find()
+            .filter(Column::EpisodeId.eq(episode_id))
+            .order_by_desc(Column::Id)
+            .all(&ctx.db)
+            .await?,
+    )
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(episode_id): Path<i32>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let episode = EpisodesNS::Entity::find_by_id(episode_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+
+    let url = params
+        .url
+        .or(episode.audio_url)
+        .filter(|x| !x.is_empty())
+        .ok_or_else(|| Error::BadRequest("Episode has no audio url".into()))?;
+
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    item.episode_id = Set(episode_id);
+    item.url = Set(url);
+    item.status = Set(STATUS_QUEUED.into());
+    item.attempts = Set(0);
+    item.bytes_downloaded = Set(0);
+    let item = item.insert(&ctx.db).await?;
+
+    DownloadWorker::perform_later(
+        &ctx,
+        DownloadWorkerArgs {
+            download_id: item.id,
+        },
+    )
+    .await?;
+
+    // Depending on the worker mode the download might already be done
+    format::json(load_item(&ctx, item.id).await?)
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    if item.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    format::json(item)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/episodes/{episode_id}/downloads/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(get_one))
+}
@@ -2,13 +2,16 @@
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
 use axum::debug_handler;
+use axum::http::StatusCode;
 use loco_rs::controller::middleware;
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::check_auth,
+    common::{check_auth, speaker_merge, speaker_stats},
     models::_entities::episode_speakers::{ActiveModel, Column, Entity, Model},
+    models::_entities::part_locks as PartLocksNS,
+    models::_entities::parts as PartsNS,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -22,6 +25,12 @@ impl Params {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergeParams {
+    /// The duplicate, it is removed
+    pub episode_speaker_id: i32,
+}
+
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
     let item = Entity::find_by_id(id).one(&ctx.db).await?;
     item.ok_or_else(|| Error::NotFound)
@@ -88,6 +97,59 @@ pub async fn remove(
     format::empty()
 }
 
+/// Merges a duplicate into the episode speaker. All its parts are given to
+/// the episode speaker, then it is removed.
+#[debug_handler]
+pub async fn merge(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<MergeParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    if params.episode_speaker_id == id {
+        return Err(Error::BadRequest(
+            "Cannot merge a speaker into itself".to_string(),
+        ));
+    }
+    let item = load_item(&ctx, id).await?;
+    let duplicate = load_item(&ctx, params.episode_speaker_id).await?;
+    if item.episode_id != episode_id || duplicate.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+
+    let part_ids: Vec<i32> = PartsNS::Entity::find()
+        .filter(PartsNS::Column::EpisodeSpeakerId.eq(duplicate.id))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    let held_by_others = PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let part_ids = speaker_merge::merge_episode_speakers(&txn, duplicate, &item, auth.user.id).await?;
+    txn.commit().await?;
+    speaker_merge::publish(&[(episode_id, part_ids)].into());
+    format::json(item)
+}
+
+/// Talk time and word count of all speakers of the episode
+#[debug_handler]
+pub async fn get_stats(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(episode_id): Path<i32>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    format::json(speaker_stats::episode_stats(&ctx.db, episode_id).await?)
+}
+
 #[debug_handler]
 pub async fn get_one(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
@@ -103,8 +165,10 @@ pub fn routes() -> Routes {
         .prefix("api/episodes/{episode_id}/speakers/")
         .add("/", get(list))
         .add("/", post(add))
+        .add("stats", get(get_stats))
         .add("{id}", get(get_one))
         .add("{id}", delete(remove))
         .add("{id}", put(update))
         .add("{id}", patch(update))
+        .add("{id}/merge", post(merge))
 }
@@ -2,26 +2,39 @@
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
 use axum::extract::Query;
+use axum::http::header;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
 use axum::{debug_handler, Extension};
 use loco_rs::controller::middleware;
 use loco_rs::prelude::*;
-use sea_orm::QueryOrder;
+use sea_orm::{QueryOrder, QuerySelect};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use tantivy::query::{BooleanQuery, Occur, QueryParser, TermSetQuery};
 use tantivy::schema::Value;
-use tantivy::{doc, Score, TantivyDocument};
+use tantivy::{doc, Score, TantivyDocument, Term};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
 
+use crate::common::audio::{self, episode_audio_path};
 use crate::common::check_auth;
+use crate::common::events;
+use crate::common::import::{self, ImportTranscription};
+use crate::common::review::{self, PartStatus, Progress};
+use crate::common::settings::Settings;
+use crate::common::waveform::{Waveform, DEFAULT_ZOOM_LEVEL, ZOOM_LEVELS};
 use crate::initializers::tantivy_search::TantivyContainer;
 use crate::models::_entities::approvals as ApprovalsNS;
+use crate::models::_entities::comments as CommentsNS;
 use crate::models::_entities::episode_speakers as EpisodeSpeakersNS;
 use crate::models::_entities::episodes::{ActiveModel, Column, Entity, Model};
 use crate::models::_entities::parts as PartsNS;
 use crate::models::_entities::sentences as SentencesNS;
 use crate::models::_entities::speakers as SpeakersNS;
-use crate::models::_entities::words as WordsNS;
+use crate::models::part_locks as PartLocksNS;
+use crate::workers::waveform::{WaveformWorker, WaveformWorkerArgs};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
@@ -32,6 +45,8 @@ pub struct Params {
     pub published_at: Option<chrono::DateTime<chrono::FixedOffset>>,
     pub filename: String,
     pub has_audio_file: bool,
+    pub audio_url: Option<String>,
+    pub podcast_id: Option<i32>,
 }
 
 impl Params {
@@ -43,9 +58,14 @@ impl Params {
         item.published_at = Set(self.published_at.clone());
         item.filename = Set(self.filename.clone());
         item.has_audio_file = Set(self.has_audio_file.clone());
+        item.audio_url = Set(self.audio_url.clone());
+        item.podcast_id = Set(self.podcast_id);
     }
 }
 
+/// Longest clip (in seconds) that can be cut out of an episode
+const MAX_CLIP_LENGTH: f64 = 600.0;
+
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
     let item = Entity::find_by_id(id).one(&ctx.db).await?;
     item.ok_or_else(|| Error::NotFound)
@@ -127,10 +147,29 @@ pub async fn get_display(
     let part_ids: Vec<i32> = parts.iter().map(|x| x.id).collect();
 
     let approvals = ApprovalsNS::Entity::find()
-        .filter(ApprovalsNS::Column::PartId.is_in(part_ids))
+        .filter(ApprovalsNS::Column::PartId.is_in(part_ids.clone()))
         .all(&ctx.db)
         .await?;
 
+    let settings = Settings::from_config(&ctx.config)?.approvals;
+    let statuses = review::statuses(&ctx.db, &settings, &part_ids).await?;
+    let progress = review::progress(&statuses);
+
+    let comment_counts: BTreeMap<i32, i64> = CommentsNS::Entity::find()
+        .select_only()
+        .column(CommentsNS::Column::PartId)
+        .column_as(CommentsNS::Column::Id.count(), "count")
+        .filter(CommentsNS::Column::PartId.is_in(part_ids.clone()))
+        .group_by(CommentsNS::Column::PartId)
+        .into_tuple::<(i32, i64)>()
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let locks =
+        PartLocksNS::Entity::find_active(&ctx.db, part_ids, chrono::Utc::now().into()).await?;
+
     let episode_speakers = EpisodeSpeakersNS::Entity::find()
         .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(id))
         .all(&ctx.db)
@@ -144,11 +183,57 @@ pub async fn get_display(
         episode_speakers,
         speakers,
         approvals,
+        statuses,
+        progress,
+        comment_counts,
+        locks,
     };
 
     format::json(output)
 }
 
+/// How many parts of the episode are reviewed
+#[debug_handler]
+pub async fn get_progress(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let episode = load_item(&ctx, id).await?;
+    let settings = Settings::from_config(&ctx.config)?.approvals;
+    format::json(review::episode_progress(&ctx.db, &settings, episode.id).await?)
+}
+
+/// Server-sent events about changes to the episode, so viewers can update
+/// their page without reloading it. The token may be given as `?token=` or
+/// in a `token` cookie.
+#[debug_handler]
+pub async fn get_events(
+    auth: check_auth::EventStreamAuth,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    load_item(&ctx, id).await?;
+
+    let stream = BroadcastStream::new(events::subscribe()).filter_map(move |x| match x {
+        Ok(event) if event.episode_id == id => Some(
+            SseEvent::default()
+                .event(event.kind.name())
+                .json_data(&event),
+        ),
+        Ok(_) => None,
+        // Events were dropped, the client has to reload the episode
+        Err(BroadcastStreamRecvError::Lagged(_)) => {
+            Some(Ok(SseEvent::default().event("lagged").data("")))
+        }
+    });
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
 #[debug_handler]
 pub async fn attach_audio(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
@@ -158,7 +243,7 @@ pub async fn attach_audio(
 ) -> Result<Response> {
     check_auth::check_admin(&auth.user)?;
     let item = load_item(&ctx, id).await?;
-    let path = std::path::PathBuf::from("episodes").join(format!("{}.mp3", id));
+    let path = episode_audio_path(id);
     ctx.storage
         .as_ref()
         .upload(path.as_path(), &content)
@@ -168,6 +253,8 @@ pub async fn attach_audio(
     item.has_audio_file = Set(true);
     let item = item.update(&ctx.db).await?;
 
+    WaveformWorker::perform_later(&ctx, WaveformWorkerArgs { episode_id: id }).await?;
+
     format::json(item)
 }
 
@@ -183,11 +270,170 @@ pub async fn get_audio(
         return Err(Error::BadRequest("Episode has no audio file".into()));
     }
 
-    let path = std::path::PathBuf::from("episodes").join(format!("{}.mp3", id));
+    let path = episode_audio_path(id);
     let content: Vec<u8> = ctx.storage.download(&path.as_path()).await?;
     Ok(axum::body::Bytes::from(content).into_response())
 }
 
+#[debug_handler]
+pub async fn get_waveform(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    params: Query<WaveformQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    if !item.has_audio_file {
+        return Err(Error::BadRequest("Episode has no audio file".into()));
+    }
+
+    let samples_per_pixel = params.samples_per_pixel.unwrap_or(DEFAULT_ZOOM_LEVEL);
+    if !ZOOM_LEVELS.contains(&samples_per_pixel) {
+        return Err(Error::BadRequest(format!(
+            "samples_per_pixel must be one of {:?}",
+            ZOOM_LEVELS
+        )));
+    }
+
+    // The worker might not have finished (or even run) yet
+    let path = Waveform::storage_path(id, samples_per_pixel);
+    let content: Vec<u8> = ctx
+        .storage
+        .download(&path)
+        .await
+        .map_err(|_| Error::NotFound)?;
+    let waveform: Waveform = serde_json::from_slice(&content)?;
+
+    format::json(waveform.slice(params.start, params.end))
+}
+
+/// `Content-Disposition` of a download. Plain ASCII for old clients, the
+/// exact name percent-encoded as of RFC 5987 for the others.
+fn attachment(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut encoded = String::new();
+    for byte in filename.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+#[debug_handler]
+pub async fn get_clip(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    params: Query<ClipQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    if !item.has_audio_file {
+        return Err(Error::BadRequest("Episode has no audio file".into()));
+    }
+
+    let (start, end) = find_clip_range(&ctx, id, &params).await?;
+    let padding = params.padding.unwrap_or(0.0).max(0.0);
+    let start = (start - padding).max(0.0);
+    let end = end + padding;
+    let fade = params.fade.unwrap_or(0.0).max(0.0);
+    if end <= start {
+        return Err(Error::BadRequest("Clip must not be empty".into()));
+    }
+    if end - start > MAX_CLIP_LENGTH {
+        return Err(Error::BadRequest(format!(
+            "Clips must not be longer than {} seconds",
+            MAX_CLIP_LENGTH
+        )));
+    }
+
+    // The timestamp of the episode is part of the name so a new audio file
+    // does not serve outdated clips.
+    let path = std::path::PathBuf::from("clips").join(format!(
+        "{}-{}-{}-{}-{}.wav",
+        id,
+        item.updated_at.timestamp(),
+        (start * 1000.0).round(),
+        (end * 1000.0).round(),
+        (fade * 1000.0).round()
+    ));
+
+    let content: Vec<u8> = match ctx.storage.download(&path).await {
+        Ok(content) => content,
+        Err(_) => {
+            let source: Vec<u8> = ctx.storage.download(&episode_audio_path(id)).await?;
+            let content =
+                tokio::task::spawn_blocking(move || audio::cut_clip(source, start, end, fade))
+                    .await
+                    .map_err(|e| Error::Message(e.to_string()))?
+                    .map_err(|e| Error::Message(e.to_string()))?;
+            ctx.storage.upload(&path, &content.clone().into()).await?;
+            content
+        }
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "audio/wav".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment(&format!("{}-{}.wav", item.filename, start.round())),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+/// Resolves the clip boundaries from either a part, a sentence or explicit
+/// timestamps
+async fn find_clip_range(
+    ctx: &AppContext,
+    episode_id: i32,
+    params: &ClipQueryParams,
+) -> Result<(f64, f64)> {
+    if let Some(part_id) = params.part_id {
+        let part = PartsNS::Entity::find_by_id(part_id)
+            .one(&ctx.db)
+            .await?
+            .filter(|x| x.episode_id == episode_id)
+            .ok_or_else(|| Error::NotFound)?;
+        return Ok((part.starts_at, part.ends_at));
+    }
+
+    if let Some(sentence_id) = params.sentence_id {
+        let (sentence, part) = SentencesNS::Entity::find_by_id(sentence_id)
+            .find_also_related(PartsNS::Entity)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+        if part.map(|x| x.episode_id) != Some(episode_id) {
+            return Err(Error::NotFound);
+        }
+        return Ok((sentence.starts_at, sentence.ends_at));
+    }
+
+    match (params.start, params.end) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(Error::BadRequest(
+            "Either start and end, part_id or sentence_id are required".into(),
+        )),
+    }
+}
+
 #[debug_handler]
 pub async fn search(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
@@ -201,12 +447,30 @@ pub async fn search(
     let schema = tantivy.schema;
     let index_id = schema.get_field("id").unwrap();
     let index_text = schema.get_field("text").unwrap();
+    let index_episode_id = schema.get_field("episode_id").unwrap();
 
     let query_parser = QueryParser::for_index(&tantivy.index, vec![index_text]);
-    let query = query_parser
+    let mut query = query_parser
         .parse_query(&search.query)
         .map_err(|e| Error::Message(e.to_string()))?;
 
+    if let Some(podcast_id) = search.podcast_id {
+        let episode_ids: Vec<i32> = Entity::find()
+            .select_only()
+            .column(Column::Id)
+            .filter(Column::PodcastId.eq(podcast_id))
+            .into_tuple()
+            .all(&ctx.db)
+            .await?;
+        let terms = episode_ids
+            .iter()
+            .map(|x| Term::from_field_text(index_episode_id, &x.to_string()));
+        query = Box::new(BooleanQuery::new(vec![
+            (Occur::Must, query),
+            (Occur::Must, Box::new(TermSetQuery::new(terms))),
+        ]));
+    }
+
     let top_docs = searcher
         .search(&query, &TopDocs::with_limit(50))
         .map_err(|e| Error::Message(e.to_string()))?;
@@ -268,148 +532,7 @@ pub async fn import(
     Json(transcription): Json<ImportTranscription>,
 ) -> Result<Response> {
     check_auth::check_admin(&auth.user)?;
-    // Bad request if anything already exists
-    let existing_parts = PartsNS::Entity::find()
-        .filter(PartsNS::Column::EpisodeId.eq(id))
-        .all(&ctx.db)
-        .await?;
-    if existing_parts.len() != 0 {
-        return Err(Error::BadRequest(String::from(
-            "Import only works for blank episodes",
-        )));
-    }
-
-    // Bad request if anything already exists
-    let existing_speakers = EpisodeSpeakersNS::Entity::find()
-        .filter(EpisodeSpeakersNS::Column::EpisodeId.eq(id))
-        .all(&ctx.db)
-        .await?;
-    if existing_speakers.len() != 0 {
-        return Err(Error::BadRequest(String::from(
-            "Import only works for blank episodes",
-        )));
-    }
-
-    // All new. Start with speakers.
-    let mut speaker_map = transcription
-        .transcription
-        .iter()
-        .map(|x| (x.speaker.clone(), 0))
-        .collect::<HashMap<_, _>>();
-
-    let speaker_names = speaker_map.keys().map(|x| x.clone()).collect::<Vec<_>>();
-
-    let existing_speakers = SpeakersNS::Entity::find()
-        .filter(SpeakersNS::Column::Name.is_in(speaker_names))
-        .all(&ctx.db)
-        .await?;
-
-    for speaker in existing_speakers {
-        speaker_map
-            .entry(speaker.name)
-            .and_modify(|s| *s = speaker.id);
-    }
-
-    // Now all speakers that do not yet exist have id "0"
-    for speaker_entry in speaker_map.iter_mut().filter(|x| *x.1 == 0) {
-        let mut item = SpeakersNS::ActiveModel {
-            ..Default::default()
-        };
-        item.name = Set(speaker_entry.0.clone());
-        let item = item.insert(&ctx.db).await?;
-        *(speaker_entry.1) = item.id;
-    }
-
-    // Now assign all speakers to episode speakers
-    let mut episode_speaker_map = HashMap::<String, i32>::new();
-    for speaker_entry in speaker_map {
-        let mut item = EpisodeSpeakersNS::ActiveModel {
-            ..Default::default()
-        };
-        item.episode_id = Set(id);
-        item.speaker_id = Set(speaker_entry.1);
-        let item = item.insert(&ctx.db).await?;
-        episode_speaker_map.insert(speaker_entry.0.clone(), item.id);
-    }
-
-    // Prepare indexer
-    let index = tantivy.writer.clone();
-    let schema = tantivy.schema;
-    let index_id = schema.get_field("id").unwrap();
-    let index_text = schema.get_field("text").unwrap();
-
-    // Now got for the parts
-    for import_part in transcription.transcription {
-        if import_part.text.is_empty() {
-            continue;
-        }
-
-        let mut item = PartsNS::ActiveModel {
-            ..Default::default()
-        };
-        item.episode_speaker_id = Set(*episode_speaker_map.get(&import_part.speaker).unwrap());
-        item.episode_id = Set(id);
-        item.text = Set(import_part.text.clone());
-        item.part_type = Set(0);
-        item.starts_at = Set(import_part.start);
-        item.ends_at = Set(import_part.end);
-        let part = item.insert(&ctx.db).await?;
-
-        index
-            .read()
-            .unwrap()
-            .add_document(doc!(
-                index_id => part.id.to_string(),
-                index_text => part.text.clone()))
-            .map_err(|e| Error::Message(e.to_string()))?;
-
-        for import_sentence in import_part.sentences {
-            if import_sentence.text.is_empty() {
-                continue;
-            }
-
-            let mut item = SentencesNS::ActiveModel {
-                ..Default::default()
-            };
-            item.part_id = Set(part.id);
-            item.text = Set(import_sentence.text.clone());
-            item.starts_at = Set(import_sentence.start);
-            item.ends_at = Set(import_sentence.end);
-            item.words_per_second = Set(import_sentence.words_per_second);
-            let sentence = item.insert(&ctx.db).await?;
-
-            let words = import_sentence
-                .words
-                .iter()
-                .map(|x| {
-                    let mut item = WordsNS::ActiveModel {
-                        ..Default::default()
-                    };
-
-                    item.sentence_id = Set(sentence.id);
-                    item.hidden = Set(false);
-                    item.overwrite = Set("".into());
-                    item.text = Set(x.text.clone());
-                    item.probability = Set(x.probability);
-                    item.starts_at = Set(x.start);
-                    item.ends_at = Set(x.end);
-                    item
-                })
-                .collect::<Vec<WordsNS::ActiveModel>>();
-
-            WordsNS::Entity::insert_many(words)
-                .on_empty_do_nothing()
-                .exec(&ctx.db)
-                .await?;
-        }
-    }
-
-    index
-        .write()
-        .unwrap()
-        .commit()
-        .map_err(|e| Error::Message(e.to_string()))?;
-
+    import::import_transcription(&ctx.db, &tantivy, id, transcription).await?;
     format::empty()
 }
 
@@ -422,8 +545,12 @@ pub fn routes() -> Routes {
         .add("{id}", get(get_one))
         .add("{id}", post(import))
         .add("{id}/display", get(get_display))
+        .add("{id}/events", get(get_events))
+        .add("{id}/progress", get(get_progress))
         .add("{id}/audio", get(get_audio))
         .add("{id}/audio", post(attach_audio))
+        .add("{id}/waveform", get(get_waveform))
+        .add("{id}/clip", get(get_clip))
         .add("{id}", delete(remove))
         .add("{id}", put(update))
         .add("{id}", patch(update))
@@ -436,42 +563,36 @@ pub struct Display {
     pub episode_speakers: Vec<EpisodeSpeakersNS::Model>,
     pub speakers: Vec<SpeakersNS::Model>,
     pub approvals: Vec<ApprovalsNS::Model>,
+    /// Review status by part id
+    pub statuses: BTreeMap<i32, PartStatus>,
+    pub progress: Progress,
+    /// Number of comments including replies by part id, parts without
+    /// comments are left out
+    pub comment_counts: BTreeMap<i32, i64>,
+    pub locks: Vec<PartLocksNS::LockDisplay>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ImportTranscription {
-    pub transcription: Vec<ImportPart>,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ImportPart {
-    pub start: f64,
-    pub end: f64,
-    pub speaker: String,
-    pub text: String,
-    pub sentences: Vec<ImportSentence>,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ImportSentence {
-    pub text: String,
-    pub words: Vec<ImportWord>,
-    pub start: f64,
-    pub end: f64,
-    pub words_per_second: f64,
+#[derive(Deserialize)]
+pub struct WaveformQueryParams {
+    samples_per_pixel: Option<u32>,
+    start: Option<f64>,
+    end: Option<f64>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ImportWord {
-    pub text: String,
-    pub start: f64,
-    pub end: f64,
-    pub probability: f64,
+#[derive(Deserialize)]
+pub struct ClipQueryParams {
+    start: Option<f64>,
+    end: Option<f64>,
+    part_id: Option<i32>,
+    sentence_id: Option<i32>,
+    padding: Option<f64>,
+    fade: Option<f64>,
 }
 
 #[derive(Deserialize)]
 pub struct SearchQueryParams {
     query: String,
+    podcast_id: Option<i32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
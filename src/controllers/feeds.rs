@@ -0,0 +1,124 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_auth;
+use crate::models::_entities::feeds::{ActiveModel, Entity, Model};
+use crate::workers::feed_sync::{FeedSyncWorker, FeedSyncWorkerArgs};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub url: String,
+    /// Minutes between two polls
+    pub poll_interval: i32,
+    /// Everything in the description starting at this text is removed
+    pub crop_description_at: Option<String>,
+    pub download_audio: bool,
+    /// Podcast the episodes of this feed are added to
+    pub podcast_id: Option<i32>,
+}
+
+impl Params {
+    fn update(&self, item: &mut ActiveModel) {
+        item.url = Set(self.url.clone());
+        item.poll_interval = Set(self.poll_interval);
+        item.crop_description_at = Set(self.crop_description_at.clone());
+        item.download_audio = Set(self.download_audio);
+        item.podcast_id = Set(self.podcast_id);
+    }
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    format::json(Entity::find().all(&ctx.db).await?)
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item);
+    let item = item.insert(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn update(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    let mut item = item.into_active_model();
+    params.update(&mut item);
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    format::json(load_item(&ctx, id).await?)
+}
+
+#[debug_handler]
+pub async fn sync(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    FeedSyncWorker::perform_later(&ctx, FeedSyncWorkerArgs { feed_id: item.id }).await?;
+
+    // Depending on the worker mode the sync might already be done
+    format::json(load_item(&ctx, id).await?)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/feeds/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(get_one))
+        .add("{id}/sync", post(sync))
+        .add("{id}", delete(remove))
+        .add("{id}", put(update))
+        .add("{id}", patch(update))
+}
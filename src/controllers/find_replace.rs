@@ -0,0 +1,152 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::http::StatusCode;
+use axum::{debug_handler, Extension};
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::common::check_auth;
+use crate::common::events::{self, EventKind};
+use crate::common::find_replace::{self, Pattern, Scope, SentenceMatch, CHUNK_SIZE};
+use crate::common::part_operations::{self, WordReplacement};
+use crate::common::revisions::{RevisionRecorder, ACTION_REPLACE};
+use crate::initializers::tantivy_search::TantivyContainer;
+use crate::models::_entities::approvals as ApprovalsNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::part_locks as PartLocksNS;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    /// One or more words
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(flatten)]
+    pub scope: Scope,
+    /// Only replace within these sentences, e.g. the ones picked from a preview
+    pub sentence_ids: Option<Vec<i32>>,
+    /// Only show what would change
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FindReplaceResult {
+    pub preview: bool,
+    pub matches: Vec<SentenceMatch>,
+}
+
+/// Replaces words in an episode, a podcast or all episodes. Each episode gets
+/// a revision of the user, approvals of the changed parts are dropped.
+#[debug_handler]
+pub async fn find_replace(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    if params.scope.episode_id.is_none() {
+        check_auth::check_admin(&auth.user)?;
+    }
+    let pattern = Pattern::new(&params.find, &params.replace, params.case_sensitive)
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    let mut matches = find_replace::find_matches(&ctx.db, &params.scope, &pattern).await?;
+    if let Some(sentence_ids) = &params.sentence_ids {
+        let sentence_ids: HashSet<i32> = sentence_ids.iter().copied().collect();
+        matches.retain(|x| sentence_ids.contains(&x.sentence_id));
+    }
+    if params.preview {
+        return format::json(FindReplaceResult {
+            preview: true,
+            matches,
+        });
+    }
+
+    let mut by_episode = BTreeMap::<i32, BTreeMap<i32, Vec<WordReplacement>>>::new();
+    for item in &matches {
+        by_episode
+            .entry(item.episode_id)
+            .or_default()
+            .entry(item.part_id)
+            .or_default()
+            .extend(item.replacements.iter().cloned());
+    }
+    let part_ids: Vec<i32> = by_episode
+        .values()
+        .flat_map(|x| x.keys().copied())
+        .collect();
+
+    // Parts claimed by somebody else are off limits, admins may ignore that
+    let mut held_by_others = vec![];
+    for chunk in part_ids.chunks(CHUNK_SIZE) {
+        held_by_others.extend(
+            PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, chunk.to_vec()).await?,
+        );
+    }
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut updated: Vec<(i32, PartsNS::Model)> = vec![];
+    for (episode_id, parts) in by_episode {
+        let mut recorder = RevisionRecorder::new();
+        for (part_id, replacements) in parts {
+            let part = PartsNS::Entity::find_by_id(part_id)
+                .one(&txn)
+                .await?
+                .ok_or_else(|| Error::NotFound)?;
+            recorder.track(&txn, part_id).await?;
+            let part = part_operations::replace_words(&txn, part, &replacements).await?;
+            updated.push((episode_id, part));
+        }
+        recorder
+            .save(&txn, episode_id, auth.user.id, ACTION_REPLACE)
+            .await?;
+    }
+    for chunk in part_ids.chunks(CHUNK_SIZE) {
+        ApprovalsNS::Entity::delete_many()
+            .filter(ApprovalsNS::Column::PartId.is_in(chunk.iter().copied()))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    for (_, part) in &updated {
+        tantivy
+            .update_part(part)
+            .map_err(|e| Error::Message(e.to_string()))?;
+    }
+    tantivy
+        .commit()
+        .map_err(|e| Error::Message(e.to_string()))?;
+    for (episode_id, part) in updated {
+        events::publish(episode_id, EventKind::PartUpdated { part_id: part.id });
+        events::publish(
+            episode_id,
+            EventKind::ApprovalChanged {
+                part_id: part.id,
+                approvals: 0,
+            },
+        );
+    }
+
+    format::json(FindReplaceResult {
+        preview: false,
+        matches,
+    })
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/find_replace/")
+        .add("/", post(find_replace))
+}
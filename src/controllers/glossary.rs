@@ -0,0 +1,196 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use axum::extract::Query;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::{DatabaseTransaction, QueryOrder, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_auth;
+use crate::common::find_replace::Pattern;
+use crate::common::glossary;
+use crate::models::_entities::glossary_misrecognitions as GlossaryMisrecognitionsNS;
+use crate::models::_entities::glossary_terms::{ActiveModel, Column, Entity, Model};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub term: String,
+    pub note: Option<String>,
+    /// Without a podcast the term applies to all podcasts
+    pub podcast_id: Option<i32>,
+    /// Ways the transcription got the term wrong before
+    #[serde(default)]
+    pub misrecognitions: Vec<String>,
+}
+
+impl Params {
+    fn update(&self, item: &mut ActiveModel) {
+        item.term = Set(self.term.trim().to_string());
+        item.note = Set(self.note.clone());
+        item.podcast_id = Set(self.podcast_id);
+    }
+
+    fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.term.trim().is_empty() {
+            return Err("Term is empty");
+        }
+        for misrecognition in &self.misrecognitions {
+            Pattern::new(misrecognition, &self.term, false)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListQueryParams {
+    podcast_id: Option<i32>,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+/// Replaces the misrecognitions of the term
+async fn save_misrecognitions(
+    txn: &DatabaseTransaction,
+    glossary_term_id: i32,
+    misrecognitions: &[String],
+) -> Result<()> {
+    GlossaryMisrecognitionsNS::Entity::delete_many()
+        .filter(GlossaryMisrecognitionsNS::Column::GlossaryTermId.eq(glossary_term_id))
+        .exec(txn)
+        .await?;
+    let mut saved: Vec<&str> = vec![];
+    for text in misrecognitions.iter().map(|x| x.trim()) {
+        if saved.contains(&text) {
+            continue;
+        }
+        saved.push(text);
+        GlossaryMisrecognitionsNS::ActiveModel {
+            text: Set(text.to_string()),
+            glossary_term_id: Set(glossary_term_id),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn load_display(ctx: &AppContext, item: Model) -> Result<glossary::TermDisplay> {
+    let mut output = glossary::load_misrecognitions(&ctx.db, vec![item]).await?;
+    output.pop().ok_or_else(|| Error::NotFound)
+}
+
+/// All terms, or those that apply to a podcast
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let terms = match params.podcast_id {
+        Some(podcast_id) => glossary::terms(&ctx.db, Some(podcast_id)).await?,
+        None => {
+            let items = Entity::find()
+                .order_by_asc(Column::Term)
+                .order_by_asc(Column::Id)
+                .all(&ctx.db)
+                .await?;
+            glossary::load_misrecognitions(&ctx.db, items).await?
+        }
+    };
+    format::json(terms)
+}
+
+/// The terms of a podcast as an initial prompt for whisper
+#[debug_handler]
+pub async fn prompt(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let terms = glossary::terms(&ctx.db, params.podcast_id).await?;
+    format::text(&glossary::prompt(&terms))
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    let txn = ctx.db.begin().await?;
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item);
+    let item = item.insert(&txn).await?;
+    save_misrecognitions(&txn, item.id, &params.misrecognitions).await?;
+    txn.commit().await?;
+    format::json(load_display(&ctx, item).await?)
+}
+
+#[debug_handler]
+pub async fn update(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    params
+        .validate()
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    let item = load_item(&ctx, id).await?;
+    let txn = ctx.db.begin().await?;
+    let mut item = item.into_active_model();
+    params.update(&mut item);
+    let item = item.update(&txn).await?;
+    save_misrecognitions(&txn, item.id, &params.misrecognitions).await?;
+    txn.commit().await?;
+    format::json(load_display(&ctx, item).await?)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    format::json(load_display(&ctx, item).await?)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/glossary/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("prompt", get(prompt))
+        .add("{id}", get(get_one))
+        .add("{id}", delete(remove))
+        .add("{id}", put(update))
+        .add("{id}", patch(update))
+}
@@ -0,0 +1,67 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::{debug_handler, Extension};
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use std::sync::Arc;
+
+use crate::common::check_auth;
+use crate::common::glossary;
+use crate::common::hunspell::Dictionary;
+use crate::common::lint::Checker;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::sentences as SentencesNS;
+use crate::models::_entities::words as WordsNS;
+
+async fn load_part(ctx: &AppContext, episode_id: i32, part_id: i32) -> Result<PartsNS::Model> {
+    let part = PartsNS::Entity::find_by_id(part_id)
+        .filter(PartsNS::Column::EpisodeId.eq(episode_id))
+        .one(&ctx.db)
+        .await?;
+    part.ok_or_else(|| Error::NotFound)
+}
+
+/// Spelling and punctuation issues of a part, keyed by the id of the word to
+/// underline
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(dictionary): Extension<Arc<Dictionary>>,
+    Path((episode_id, part_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let part = load_part(&ctx, episode_id, part_id).await?;
+
+    let podcast_id = EpisodesNS::Entity::find_by_id(part.episode_id)
+        .one(&ctx.db)
+        .await?
+        .and_then(|x| x.podcast_id);
+    let terms = glossary::terms(&ctx.db, podcast_id).await?;
+
+    let sentence_ids: Vec<i32> = SentencesNS::Entity::find()
+        .filter(SentencesNS::Column::PartId.eq(part.id))
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    let words = WordsNS::Entity::find()
+        .filter(WordsNS::Column::SentenceId.is_in(sentence_ids))
+        .order_by_asc(WordsNS::Column::StartsAt)
+        .order_by_asc(WordsNS::Column::EndsAt)
+        .order_by_asc(WordsNS::Column::Id)
+        .all(&ctx.db)
+        .await?;
+
+    format::json(Checker::new(&dictionary, &terms).check(&words))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/episodes/{episode_id}/parts/{part_id}/")
+        .add("lint", get(list))
+}
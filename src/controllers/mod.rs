@@ -1,9 +1,23 @@
 pub mod auth;
 
+pub mod assignments;
+pub mod comments;
+pub mod downloads;
+
 pub mod episode_speakers;
 pub mod episodes;
+pub mod feeds;
+pub mod find_replace;
 pub mod frontend;
+pub mod glossary;
+pub mod lint;
 pub mod parts;
+pub mod podcasts;
+pub mod review_queue;
+pub mod revisions;
 pub mod sentences;
+pub mod speaker_aliases;
+pub mod speaker_mappings;
 pub mod speakers;
+pub mod transcription_jobs;
 pub mod words;
@@ -1,22 +1,38 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
+use axum::extract::Query;
+use axum::http::StatusCode;
 use axum::{debug_handler, Extension};
 use loco_rs::controller::middleware;
 use loco_rs::prelude::*;
-use sea_orm::{QueryOrder, QuerySelect};
+use sea_orm::sea_query::{Alias, Expr};
+use sea_orm::{
+    DatabaseTransaction, PaginatorTrait, QueryOrder, QuerySelect, SqlErr, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
-use tantivy::collector::TopDocs;
-use tantivy::query::TermQuery;
-use tantivy::schema::IndexRecordOption;
-use tantivy::{doc, IndexReader, TantivyDocument, Term};
+use tantivy::doc;
 
 use crate::common::check_auth;
+use crate::common::events::{self, EventKind};
+use crate::common::glossary::{self, Suggestion};
+use crate::common::part_operations::{
+    self, Changes, Retiming, Selection, SyncPoint, WordReplacement,
+};
+use crate::common::review::{self, PartStatus};
+use crate::common::revisions::{
+    RevisionRecorder, ACTION_EDIT, ACTION_MERGE, ACTION_REASSIGN, ACTION_REPLACE, ACTION_RETIME,
+    ACTION_SPLIT,
+};
+use crate::common::settings::Settings;
 use crate::initializers::tantivy_search::TantivyContainer;
 use crate::models::_entities::approvals as ApprovalsNS;
+use crate::models::_entities::comments as CommentsNS;
+use crate::models::_entities::episodes as EpisodesNS;
 use crate::models::_entities::parts::{ActiveModel, Column, Entity, Model};
 use crate::models::_entities::sentences as SentencesNS;
 use crate::models::_entities::words as WordsNS;
+use crate::models::part_locks as PartLocksNS;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
@@ -73,21 +89,14 @@ pub async fn add(
     item.episode_id = Set(episode_id);
     let item = item.insert(&ctx.db).await?;
 
-    let index = tantivy.writer.clone();
-    let mut index_writer = index.write().unwrap();
-    let schema = tantivy.schema;
-    let id = schema.get_field("id").unwrap();
-    let text = schema.get_field("text").unwrap();
-
-    index_writer
-        .add_document(doc!(
-            id => item.id.to_string(),
-            text => item.text.clone()))
+    tantivy
+        .update_part(&item)
         .map_err(|e| Error::Message(e.to_string()))?;
-    index_writer
+    tantivy
         .commit()
         .map_err(|e| Error::Message(e.to_string()))?;
 
+    events::publish(episode_id, EventKind::PartCreated { part_id: item.id });
     format::json(item)
 }
 
@@ -106,6 +115,7 @@ pub async fn update(
     let mut item = item.into_active_model();
     params.update(&mut item);
     let item = item.update(&ctx.db).await?;
+    events::publish(episode_id, EventKind::PartUpdated { part_id: item.id });
     format::json(item)
 }
 
@@ -116,7 +126,10 @@ pub async fn remove(
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     check_auth::check_admin(&auth.user)?;
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    let item = load_item(&ctx, id).await?;
+    let episode_id = item.episode_id;
+    item.delete(&ctx.db).await?;
+    events::publish(episode_id, EventKind::PartDeleted { part_id: id });
     format::empty()
 }
 
@@ -138,9 +151,13 @@ pub async fn get_display(
 ) -> Result<Response> {
     check_auth::check_reader(&auth.user)?;
     let part = load_item(&ctx, id).await?;
+    format::json(load_display(&ctx, part).await?)
+}
 
+/// The part with all its sentences and words, as shown in the editor
+async fn load_display(ctx: &AppContext, part: Model) -> Result<Display> {
     let sentences = SentencesNS::Entity::find()
-        .filter(SentencesNS::Column::PartId.eq(id))
+        .filter(SentencesNS::Column::PartId.eq(part.id))
         .order_by_asc(SentencesNS::Column::StartsAt)
         .all(&ctx.db)
         .await?;
@@ -150,6 +167,8 @@ pub async fn get_display(
     let words = WordsNS::Entity::find()
         .filter(WordsNS::Column::SentenceId.is_in(sentence_ids))
         .order_by_asc(WordsNS::Column::StartsAt)
+        .order_by_asc(WordsNS::Column::EndsAt)
+        .order_by_asc(WordsNS::Column::Id)
         .all(&ctx.db)
         .await?;
 
@@ -166,17 +185,35 @@ pub async fn get_display(
         .collect();
 
     let approvals = ApprovalsNS::Entity::find()
-        .filter(ApprovalsNS::Column::PartId.eq(id))
+        .filter(ApprovalsNS::Column::PartId.eq(part.id))
         .all(&ctx.db)
         .await?;
 
-    let output = Display {
+    let status = part_status(ctx, part.id).await?;
+
+    let podcast_id = EpisodesNS::Entity::find_by_id(part.episode_id)
+        .one(&ctx.db)
+        .await?
+        .and_then(|x| x.podcast_id);
+    let terms = glossary::terms(&ctx.db, podcast_id).await?;
+    let suggestions = glossary::suggestions(&terms, &words);
+
+    Ok(Display {
         part,
         sentences: display_sentences,
         approvals: u32::try_from(approvals.len()).map_err(|e| Error::Message(e.to_string()))?,
-    };
+        status,
+        suggestions,
+    })
+}
 
-    format::json(output)
+async fn part_status(ctx: &AppContext, part_id: i32) -> Result<PartStatus> {
+    let settings = Settings::from_config(&ctx.config)?.approvals;
+    let statuses = review::statuses(&ctx.db, &settings, &[part_id]).await?;
+    Ok(statuses
+        .get(&part_id)
+        .copied()
+        .unwrap_or(PartStatus::Untouched))
 }
 
 #[debug_handler]
@@ -185,7 +222,7 @@ pub async fn ui_update(
     Extension(tantivy): Extension<TantivyContainer>,
     Path((episode_id, id)): Path<(i32, i32)>,
     State(ctx): State<AppContext>,
-    Json(params): Json<UiUpdateParams>,
+    Json(mut params): Json<UiUpdateParams>,
 ) -> Result<Response> {
     check_auth::check_contributor(&auth.user)?;
 
@@ -195,6 +232,33 @@ pub async fn ui_update(
         return Err(Error::NotFound);
     }
 
+    // Somebody else saved the part since the client loaded it. Send the
+    // current state, so the client can merge the changes.
+    if params.part.updated_at != original_part.updated_at {
+        return conflict(&ctx, id).await;
+    }
+
+    // Parts claimed by somebody else are off limits
+    {
+        let mut part_ids = vec![original_part.id];
+        let moves: Vec<Option<MoveSentence>> =
+            params.sentences.iter().map(|x| x.move_sentence).collect();
+        if moves.contains(&Some(MoveSentence::Up)) {
+            part_ids.extend(previous_part(&original_part, &ctx.db).await?.map(|x| x.id));
+        }
+        if moves.contains(&Some(MoveSentence::Down)) {
+            part_ids.extend(next_part(&original_part, &ctx.db).await?.map(|x| x.id));
+        }
+
+        let held_by_others =
+            PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids).await?;
+        if !held_by_others.is_empty() {
+            return format::render()
+                .status(StatusCode::LOCKED)
+                .json(held_by_others);
+        }
+    }
+
     let original_sentences = SentencesNS::Entity::find()
         .filter(SentencesNS::Column::PartId.eq(original_part.id))
         .all(&ctx.db)
@@ -207,18 +271,12 @@ pub async fn ui_update(
         .all(&ctx.db)
         .await?;
 
-    // Sanity check: all words in list
+    // Sanity check: unique word ids, new words have temporary ids <= 0
     let ui_words: Vec<UiUpdateParamsSentenceWord> = params
         .sentences
         .iter()
         .flat_map(|x| x.words.clone())
         .collect();
-    if ui_words.len() != original_words.len() {
-        return Err(Error::BadRequest(String::from(
-            "Amount of words does not match",
-        )));
-    }
-
     if !ui_words
         .iter()
         .all(|x| ui_words.iter().filter(|y| y.id == x.id).count() == 1)
@@ -228,14 +286,26 @@ pub async fn ui_update(
         )));
     }
 
-    if !original_words.iter().all(|original_word| {
-        ui_words
-            .iter()
-            .any(|ui_word| original_word.id == ui_word.id)
-    }) {
-        return Err(Error::BadRequest(String::from("Not all words included")));
+    // Sanity check: all word ids > 0 loaded. Loaded words that are missing
+    // get deleted.
+    if !ui_words
+        .iter()
+        .filter(|x| x.id > 0)
+        .all(|x| original_words.iter().any(|y| y.id == x.id))
+    {
+        return Err(Error::BadRequest(String::from("Not all words were loaded")));
+    }
+
+    if ui_words
+        .iter()
+        .any(|x| x.id <= 0 && x.text.trim().is_empty())
+    {
+        return Err(Error::BadRequest(String::from("New words need a text")));
     }
 
+    resolve_word_timings(&mut params.sentences, &original_sentences, &original_words)
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
     // Sanity check: no duplicate sentence ids
     if !params.sentences.iter().all(|x| {
         params
@@ -262,6 +332,11 @@ pub async fn ui_update(
         )));
     }
 
+    // Sanity check: the sentences and comments of the part need somewhere to go
+    if params.sentences.is_empty() {
+        return Err(Error::BadRequest(String::from("The part has no sentences")));
+    }
+
     // Sanity check: No empty sentences
     if !params.sentences.iter().all(|x| x.words.len() > 0) {
         return Err(Error::BadRequest(String::from(
@@ -269,12 +344,15 @@ pub async fn ui_update(
         )));
     }
 
-    // Prepare indexer
-    let index = tantivy.writer.clone();
-    let reader = tantivy.reader;
-    let schema = tantivy.schema;
-    let index_id = schema.get_field("id").unwrap();
-    let index_text = schema.get_field("text").unwrap();
+    let txn = ctx.db.begin().await?;
+    if !bump_part(&txn, id, params.part.updated_at).await? {
+        txn.rollback().await?;
+        return conflict(&ctx, id).await;
+    }
+
+    let mut recorder = RevisionRecorder::new();
+    recorder.track(&txn, original_part.id).await?;
+    let mut changes: Vec<EventKind> = vec![];
 
     // Find out which sentences stay and which ones are moved.
     let moved_sentences: Vec<&UiUpdateParamsSentence> = params
@@ -289,16 +367,32 @@ pub async fn ui_update(
         .collect();
 
     // First we work on sentences that move
+    let mut target_part_ids: Vec<i32> = vec![];
     for ui_sentence in moved_sentences {
-        let target_part = match ui_sentence.move_sentence.as_deref() {
-            Some("up") => find_previous_part(&original_part, &ctx).await?,
-            Some("upnew") => create_part(&original_part, &ctx).await?,
-            Some("downnew") => create_part(&original_part, &ctx).await?,
-            Some("down") => find_next_part(&original_part, &ctx).await?,
-            _ => panic!("Unexpected data"),
+        let Some(move_sentence) = ui_sentence.move_sentence else {
+            continue;
         };
+        let target_part = match move_sentence {
+            MoveSentence::Up => find_previous_part(&original_part, &txn).await?,
+            MoveSentence::UpNew | MoveSentence::DownNew => {
+                create_part(&original_part, &txn).await?
+            }
+            MoveSentence::Down => find_next_part(&original_part, &txn).await?,
+        };
+        target_part_ids.push(target_part.id);
+        if target_part.starts_at == target_part.ends_at {
+            recorder.track_created(target_part.id);
+            changes.push(EventKind::PartCreated {
+                part_id: target_part.id,
+            });
+        } else {
+            recorder.track(&txn, target_part.id).await?;
+            changes.push(EventKind::PartUpdated {
+                part_id: target_part.id,
+            });
+        }
 
-        let new_text = update_sentence(&ui_sentence, &target_part, &ctx).await?;
+        let new_text = update_sentence(&ui_sentence, &target_part, &txn).await?;
 
         // Update part
         let old_starts_at = target_part.starts_at;
@@ -311,7 +405,7 @@ pub async fn ui_update(
             target_part.starts_at = Set(ui_sentence.words[0].starts_at);
             target_part.ends_at = Set(ui_sentence.words[ui_sentence.words.len() - 1].ends_at);
             target_part.text = Set(new_text)
-        } else if ui_sentence.move_sentence.as_deref() == Some("up") {
+        } else if move_sentence == MoveSentence::Up {
             // We are appending to the previous
             target_part.ends_at = Set(ui_sentence.words[ui_sentence.words.len() - 1].ends_at);
             let text = vec![old_text, new_text]
@@ -333,31 +427,12 @@ pub async fn ui_update(
             target_part.text = Set(text);
         };
 
-        let target_part: Model = target_part.update(&ctx.db).await?;
-
-        // Tantivy remove index
-        let index_writer = index.read().unwrap();
-        let index_id_term = Term::from_field_text(index_id, &target_part.id.to_string());
-        let extracted_item = extract_part_from_search_index(&reader, &index_id_term)
-            .map_err(|e| Error::Message(e.to_string()))?;
-
-        if extracted_item.is_some() {
-            index_writer.delete_term(index_id_term.clone());
-        }
-
-        // Tantivy update index
-        index
-            .read()
-            .unwrap()
-            .add_document(doc!(
-                index_id => target_part.id.to_string(),
-                index_text => target_part.text.clone()))
-            .map_err(|e| Error::Message(e.to_string()))?;
+        target_part.update(&txn).await?;
     }
 
     let mut texts: Vec<String> = Vec::with_capacity(sticky_sentences.len());
     for ui_sentence in &sticky_sentences {
-        let new_text = update_sentence(&ui_sentence, &original_part, &ctx).await?;
+        let new_text = update_sentence(&ui_sentence, &original_part, &txn).await?;
         texts.push(new_text);
     }
     let complete_text: String = texts
@@ -367,20 +442,39 @@ pub async fn ui_update(
         .collect::<Vec<String>>()
         .join(" ");
 
-    {
-        let index_writer = index.read().unwrap();
-        let index_id_term = Term::from_field_text(index_id, &id.to_string());
-        let extracted_item = extract_part_from_search_index(&reader, &index_id_term)
-            .map_err(|e| Error::Message(e.to_string()))?;
-
-        if extracted_item.is_some() {
-            index_writer.delete_term(index_id_term.clone());
-        }
+    // Threads follow the words they are about, threads about deleted words
+    // are about the whole part
+    let deleted_word_ids: Vec<i32> = original_words
+        .iter()
+        .map(|x| x.id)
+        .filter(|x| !ui_words.iter().any(|y| y.id == *x))
+        .collect();
+    part_operations::clear_anchors(&txn, &deleted_word_ids).await?;
+    for part_id in part_operations::move_threads(&txn, id).await? {
+        changes.push(EventKind::CommentsChanged { part_id });
     }
 
     if sticky_sentences.len() == 0 {
-        original_part.delete(&ctx.db).await?;
+        // The remaining threads go with the first moved sentence
+        let target_part_id = target_part_ids[0];
+        let moved = CommentsNS::Entity::update_many()
+            .col_expr(CommentsNS::Column::PartId, Expr::value(target_part_id))
+            .filter(CommentsNS::Column::PartId.eq(id))
+            .exec(&txn)
+            .await?;
+        if moved.rows_affected > 0 {
+            changes.push(EventKind::CommentsChanged {
+                part_id: target_part_id,
+            });
+        }
+        original_part.delete(&txn).await?;
+        changes.push(EventKind::PartDeleted { part_id: id });
     } else {
+        changes.push(EventKind::PartUpdated { part_id: id });
+        changes.push(EventKind::ApprovalChanged {
+            part_id: id,
+            approvals: 1,
+        });
         // Update part
         let last_sentence = sticky_sentences[sticky_sentences.len() - 1];
         let mut original_part = original_part.into_active_model();
@@ -389,62 +483,123 @@ pub async fn ui_update(
         original_part.text = Set(complete_text.clone());
         original_part.part_type = Set(params.part.part_type);
         original_part.episode_speaker_id = Set(params.part.episode_speaker_id);
-        let original_part = original_part.update(&ctx.db).await?;
-
-        // Tantivy update index (Only add if part type is "normal")
-        if original_part.part_type == 0 {
-            index
-                .read()
-                .unwrap()
-                .add_document(doc!(
-                index_id => original_part.id.to_string(),
-                index_text => original_part.text.clone()))
-                .map_err(|e| Error::Message(e.to_string()))?;
-        }
+        original_part.update(&txn).await?;
     }
 
+    // Remove words that are not required anymore
+    WordsNS::Entity::delete_many()
+        .filter(WordsNS::Column::Id.is_in(deleted_word_ids))
+        .exec(&txn)
+        .await?;
+
     // Remove sentences that are not required anymore
     for sentence in original_sentences
         .iter()
         .filter(|x| !params.sentences.iter().any(|y| y.sentence.id == x.id))
     {
         let sentence = sentence.clone();
-        sentence.delete(&ctx.db).await?;
+        sentence.delete(&txn).await?;
     }
 
     // Remove all approvals, then add ours
     ApprovalsNS::Entity::delete_many()
         .filter(ApprovalsNS::Column::PartId.eq(id))
-        .exec(&ctx.db)
+        .exec(&txn)
         .await?;
 
-    {
+    if !sticky_sentences.is_empty() {
         let mut item = ApprovalsNS::ActiveModel {
             ..Default::default()
         };
         item.part_id = Set(id);
         item.user_id = Set(auth.user.id);
-        item.insert(&ctx.db).await?;
+        item.insert(&txn).await?;
     }
 
-    index
-        .write()
-        .unwrap()
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_EDIT)
+        .await?;
+    txn.commit().await?;
+
+    // The search index only learns about the edit once it is stored
+    for change in &changes {
+        match change {
+            EventKind::PartCreated { part_id } | EventKind::PartUpdated { part_id } => {
+                let part = load_item(&ctx, *part_id).await?;
+                tantivy
+                    .update_part(&part)
+                    .map_err(|e| Error::Message(e.to_string()))?;
+            }
+            EventKind::PartDeleted { part_id } => tantivy.remove_part(*part_id),
+            _ => {}
+        }
+    }
+    tantivy
         .commit()
         .map_err(|e| Error::Message(e.to_string()))?;
 
-    reader.reload().map_err(|e| Error::Message(e.to_string()))?;
+    // A part may receive several sentences, one event is enough
+    let mut published: Vec<EventKind> = vec![];
+    for change in changes {
+        if !published.contains(&change) {
+            events::publish(episode_id, change.clone());
+            published.push(change);
+        }
+    }
 
     format::empty()
 }
 
+/// The current state of the part, sent when an edit is based on an outdated one
+async fn conflict(ctx: &AppContext, id: i32) -> Result<Response> {
+    let part = load_item(ctx, id).await?;
+    format::render()
+        .status(StatusCode::CONFLICT)
+        .json(load_display(ctx, part).await?)
+}
+
+/// Sets a new `updated_at`, unless the part changed since the client loaded
+/// it at `expected`. The stored value is compared as text, so a concurrent
+/// save makes this fail even between reading and writing.
+async fn bump_part<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    expected: DateTimeWithTimeZone,
+) -> Result<bool> {
+    let as_text = || Expr::col(Column::UpdatedAt).cast_as(Alias::new("text"));
+    let current: Option<(DateTimeWithTimeZone, String)> = Entity::find_by_id(id)
+        .select_only()
+        .column(Column::UpdatedAt)
+        .column_as(as_text(), "updated_at_text")
+        .into_tuple()
+        .one(db)
+        .await?;
+    let Some((updated_at, stored)) = current else {
+        return Ok(false);
+    };
+    if updated_at != expected {
+        return Ok(false);
+    }
+    let result = Entity::update_many()
+        .col_expr(Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(Column::Id.eq(id))
+        .filter(as_text().eq(stored))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
 #[debug_handler]
 pub async fn approve(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
-    Path((_episode_id, id)): Path<(i32, i32)>,
+    Path((episode_id, id)): Path<(i32, i32)>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     check_auth::check_contributor(&auth.user)?;
+    let part = load_item(&ctx, id).await?;
+    if part.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
     let approvals = ApprovalsNS::Entity::find()
         .filter(ApprovalsNS::Column::PartId.eq(id))
         .all(&ctx.db)
@@ -453,6 +608,7 @@ pub async fn approve(
     if approvals.iter().any(|x| x.user_id == auth.user.id) {
         let output = ApprovalResult {
             approvals: u32::try_from(approvals.len()).map_err(|e| Error::Message(e.to_string()))?,
+            status: part_status(&ctx, id).await?,
         };
 
         return format::json(output);
@@ -467,58 +623,622 @@ pub async fn approve(
 
     let output = ApprovalResult {
         approvals: u32::try_from(approvals.len()).map_err(|e| Error::Message(e.to_string()))? + 1,
+        status: part_status(&ctx, id).await?,
     };
+    events::publish(
+        episode_id,
+        EventKind::ApprovalChanged {
+            part_id: id,
+            approvals: output.approvals,
+        },
+    );
 
     return format::json(output);
 }
 
+/// Takes back the approval of the user
+#[debug_handler]
+pub async fn withdraw_approval(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let part = load_item(&ctx, id).await?;
+    if part.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    let result = ApprovalsNS::Entity::delete_many()
+        .filter(ApprovalsNS::Column::PartId.eq(id))
+        .filter(ApprovalsNS::Column::UserId.eq(auth.user.id))
+        .exec(&ctx.db)
+        .await?;
+    let approvals = ApprovalsNS::Entity::find()
+        .filter(ApprovalsNS::Column::PartId.eq(id))
+        .count(&ctx.db)
+        .await?;
+
+    let output = ApprovalResult {
+        approvals: u32::try_from(approvals).map_err(|e| Error::Message(e.to_string()))?,
+        status: part_status(&ctx, id).await?,
+    };
+    if result.rows_affected > 0 {
+        events::publish(
+            episode_id,
+            EventKind::ApprovalChanged {
+                part_id: id,
+                approvals: output.approvals,
+            },
+        );
+    }
+    format::json(output)
+}
+
+#[debug_handler]
+pub async fn lock(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    params: Query<LockQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let duration = Settings::from_config(&ctx.config)?.locks.duration_seconds;
+    let part_ids = lock_range(&ctx, episode_id, id, params.until_part_id).await?;
+
+    let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::seconds(duration);
+    let held_by_others = match PartLocksNS::Entity::acquire(
+        &ctx.db,
+        part_ids.clone(),
+        auth.user.id,
+        now.into(),
+        expires_at.into(),
+    )
+    .await
+    {
+        Ok(held_by_others) => held_by_others,
+        // Someone else locked one of the parts at the same time
+        Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            PartLocksNS::Entity::find_active(&ctx.db, part_ids.clone(), now.into())
+                .await?
+                .into_iter()
+                .filter(|x| x.user_id != auth.user.id)
+                .collect()
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::CONFLICT)
+            .json(held_by_others);
+    }
+
+    let locks = PartLocksNS::Entity::find_active(&ctx.db, part_ids, now.into()).await?;
+    events::publish(
+        episode_id,
+        EventKind::LockAcquired {
+            locks: locks.clone(),
+        },
+    );
+    format::json(locks)
+}
+
+#[debug_handler]
+pub async fn renew_lock(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    params: Query<LockQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let duration = Settings::from_config(&ctx.config)?.locks.duration_seconds;
+    let part_ids = lock_range(&ctx, episode_id, id, params.until_part_id).await?;
+
+    let now = chrono::Utc::now();
+    let renewed = PartLocksNS::Entity::renew(
+        &ctx.db,
+        part_ids.clone(),
+        auth.user.id,
+        now.into(),
+        (now + chrono::Duration::seconds(duration)).into(),
+    )
+    .await?;
+    let locks = PartLocksNS::Entity::find_active(&ctx.db, part_ids.clone(), now.into()).await?;
+    if renewed < part_ids.len() as u64 {
+        // Expired or taken over by someone else, the client has to lock again
+        return format::render().status(StatusCode::CONFLICT).json(locks);
+    }
+    events::publish(
+        episode_id,
+        EventKind::LockAcquired {
+            locks: locks.clone(),
+        },
+    );
+    format::json(locks)
+}
+
+/// Admins may release locks of other users
+#[debug_handler]
+pub async fn unlock(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    params: Query<LockQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let part_ids = lock_range(&ctx, episode_id, id, params.until_part_id).await?;
+    let user_id = match check_auth::check_admin(&auth.user) {
+        Ok(()) => None,
+        Err(_) => Some(auth.user.id),
+    };
+    let released: Vec<i32> =
+        PartLocksNS::Entity::find_active(&ctx.db, part_ids.clone(), chrono::Utc::now().into())
+            .await?
+            .into_iter()
+            .filter(|x| user_id.is_none_or(|user_id| x.user_id == user_id))
+            .map(|x| x.part_id)
+            .collect();
+    PartLocksNS::Entity::release(&ctx.db, part_ids, user_id).await?;
+    if !released.is_empty() {
+        events::publish(episode_id, EventKind::LockReleased { part_ids: released });
+    }
+    format::empty()
+}
+
+#[derive(Deserialize)]
+pub struct SplitParams {
+    /// First word of the new part
+    pub word_id: i32,
+    /// Speaker of the new part, the same as before if not given
+    pub episode_speaker_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct MergeParams {
+    /// The part before or after
+    pub part_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ReassignParams {
+    #[serde(flatten)]
+    pub selection: Selection,
+    /// The new speaker
+    pub episode_speaker_id: i32,
+    /// Only show what would change
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReassignResult {
+    pub preview: bool,
+    /// Selected parts before the change
+    pub before: Vec<Model>,
+    /// Resulting parts. Ids of new parts in a preview are not kept.
+    pub after: Vec<Model>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetimeParams {
+    #[serde(flatten)]
+    pub retiming: Retiming,
+    /// Times in the transcribed audio and where they are in the new audio,
+    /// used instead of scale and offset
+    pub sync_points: Option<Vec<SyncPoint>>,
+    /// Only show what would change
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetimeResult {
+    pub preview: bool,
+    /// The change that was applied, also if it came from sync points
+    pub retiming: Retiming,
+    /// Parts with changed timestamps
+    pub parts: Vec<Model>,
+}
+
+#[debug_handler]
+pub async fn split(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<SplitParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let part = load_item(&ctx, id).await?;
+    if part.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    let held_by_others =
+        PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, vec![part.id]).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    recorder.track(&txn, part.id).await?;
+    let (part, new_part) =
+        part_operations::split(&txn, part, params.word_id, params.episode_speaker_id).await?;
+    let changes = Changes {
+        created: vec![new_part.id],
+        updated: vec![part.id],
+        deleted: vec![],
+    };
+    for part_id in &changes.created {
+        recorder.track_created(*part_id);
+    }
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_SPLIT)
+        .await?;
+    let parts = apply_changes(&ctx, &tantivy, txn, episode_id, changes).await?;
+    format::json(parts)
+}
+
+#[debug_handler]
+pub async fn merge(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<MergeParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let part = load_item(&ctx, id).await?;
+    let other = load_item(&ctx, params.part_id).await?;
+    if part.episode_id != episode_id || other.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    let held_by_others =
+        PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, vec![part.id, other.id]).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    recorder.track(&txn, part.id).await?;
+    recorder.track(&txn, other.id).await?;
+    let (merged, removed) = part_operations::merge(&txn, part, other).await?;
+    let changes = Changes {
+        created: vec![],
+        updated: vec![merged.id],
+        deleted: vec![removed],
+    };
+    for part_id in &changes.created {
+        recorder.track_created(*part_id);
+    }
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_MERGE)
+        .await?;
+    let parts = apply_changes(&ctx, &tantivy, txn, episode_id, changes).await?;
+    format::json(parts)
+}
+
+/// Gives words of the part new texts, e.g. to accept a glossary suggestion
+#[debug_handler]
+pub async fn replace_words(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path((episode_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<WordReplacement>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let part = load_item(&ctx, id).await?;
+    if part.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    if params.word_ids.is_empty() || params.texts.iter().all(|x| x.trim().is_empty()) {
+        return Err(Error::BadRequest(
+            "Words and texts are required".to_string(),
+        ));
+    }
+    let held_by_others =
+        PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, vec![part.id]).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    recorder.track(&txn, part.id).await?;
+    let part = part_operations::replace_words(&txn, part, &[params]).await?;
+    let changes = Changes {
+        created: vec![],
+        updated: vec![part.id],
+        deleted: vec![],
+    };
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_REPLACE)
+        .await?;
+    let parts = apply_changes(&ctx, &tantivy, txn, episode_id, changes).await?;
+    format::json(parts)
+}
+
+/// Gives a time range of the episode, or all parts of one speaker, to
+/// another speaker
+#[debug_handler]
+pub async fn reassign(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path(episode_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<ReassignParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let before = part_operations::selected(&ctx.db, episode_id, &params.selection).await?;
+    let part_ids: Vec<i32> = before.iter().map(|x| x.id).collect();
+    if !params.preview {
+        let held_by_others =
+            PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids.clone()).await?;
+        if !held_by_others.is_empty() {
+            return format::render()
+                .status(StatusCode::LOCKED)
+                .json(held_by_others);
+        }
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    for part_id in part_ids {
+        recorder.track(&txn, part_id).await?;
+    }
+    let changes = part_operations::reassign(
+        &txn,
+        episode_id,
+        &params.selection,
+        params.episode_speaker_id,
+    )
+    .await?;
+
+    if params.preview {
+        // Nothing is kept, but the result looks exactly like the real thing
+        let after = Entity::find()
+            .filter(Column::Id.is_in(changes.existing()))
+            .order_by_asc(Column::StartsAt)
+            .all(&txn)
+            .await?;
+        txn.rollback().await?;
+        return format::json(ReassignResult {
+            preview: true,
+            before,
+            after,
+        });
+    }
+
+    for part_id in &changes.created {
+        recorder.track_created(*part_id);
+    }
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_REASSIGN)
+        .await?;
+    let after = apply_changes(&ctx, &tantivy, txn, episode_id, changes).await?;
+    format::json(ReassignResult {
+        preview: false,
+        before,
+        after,
+    })
+}
+
+/// Shifts and stretches the timestamps of the episode, e.g. to resync the
+/// transcript with a new edit of the audio. Approvals are kept as the text
+/// does not change.
+#[debug_handler]
+pub async fn retime(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(episode_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<RetimeParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let retiming = match &params.sync_points {
+        Some(points) => Retiming::resync(points, params.retiming.from)?,
+        None => params.retiming.clone(),
+    };
+    let part_ids: Vec<i32> = part_operations::retimed(&ctx.db, episode_id, retiming.from)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    if !params.preview {
+        let held_by_others =
+            PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids.clone()).await?;
+        if !held_by_others.is_empty() {
+            return format::render()
+                .status(StatusCode::LOCKED)
+                .json(held_by_others);
+        }
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    for part_id in &part_ids {
+        recorder.track(&txn, *part_id).await?;
+    }
+    let part_ids = part_operations::retime(&txn, episode_id, &retiming).await?;
+    let parts = Entity::find()
+        .filter(Column::Id.is_in(part_ids.clone()))
+        .order_by_asc(Column::StartsAt)
+        .all(&txn)
+        .await?;
+    if params.preview {
+        txn.rollback().await?;
+        return format::json(RetimeResult {
+            preview: true,
+            retiming,
+            parts,
+        });
+    }
+
+    recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_RETIME)
+        .await?;
+    txn.commit().await?;
+    for part_id in part_ids {
+        events::publish(episode_id, EventKind::PartUpdated { part_id });
+    }
+    format::json(RetimeResult {
+        preview: false,
+        retiming,
+        parts,
+    })
+}
+
+/// Finishes a split, merge or reassignment: approvals of the changed parts
+/// are dropped and the transaction is committed. Then the search index and
+/// the viewers of the episode are updated. Returns the resulting parts.
+async fn apply_changes(
+    ctx: &AppContext,
+    tantivy: &TantivyContainer,
+    txn: DatabaseTransaction,
+    episode_id: i32,
+    changes: Changes,
+) -> Result<Vec<Model>> {
+    let changed = changes.existing();
+    ApprovalsNS::Entity::delete_many()
+        .filter(ApprovalsNS::Column::PartId.is_in(changed.clone()))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    let parts = Entity::find()
+        .filter(Column::Id.is_in(changed))
+        .order_by_asc(Column::StartsAt)
+        .all(&ctx.db)
+        .await?;
+    for part in &parts {
+        tantivy
+            .update_part(part)
+            .map_err(|e| Error::Message(e.to_string()))?;
+    }
+    for part_id in &changes.deleted {
+        tantivy.remove_part(*part_id);
+    }
+    tantivy
+        .commit()
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+    for part_id in changes.created {
+        events::publish(episode_id, EventKind::PartCreated { part_id });
+    }
+    for part_id in changes.updated {
+        events::publish(episode_id, EventKind::PartUpdated { part_id });
+        events::publish(
+            episode_id,
+            EventKind::ApprovalChanged {
+                part_id,
+                approvals: 0,
+            },
+        );
+    }
+    for part_id in changes.deleted {
+        events::publish(episode_id, EventKind::PartDeleted { part_id });
+    }
+    Ok(parts)
+}
+
 pub fn routes() -> Routes {
     Routes::new()
         .prefix("api/episodes/{episode_id}/parts/")
         .add("/", get(list))
         .add("/", post(add))
+        .add("reassign", post(reassign))
+        .add("retime", post(retime))
         .add("{id}", get(get_one))
         .add("{id}/display", get(get_display))
         .add("{id}/approve", post(approve))
+        .add("{id}/approve", delete(withdraw_approval))
         .add("{id}/update", post(ui_update))
+        .add("{id}/split", post(split))
+        .add("{id}/merge", post(merge))
+        .add("{id}/replace_words", post(replace_words))
+        .add("{id}/lock", post(lock))
+        .add("{id}/lock", put(renew_lock))
+        .add("{id}/lock", delete(unlock))
         .add("{id}", delete(remove))
         .add("{id}", put(update))
         .add("{id}", patch(update))
 }
 
-async fn find_previous_part(part: &Model, ctx: &AppContext) -> Result<Model> {
+async fn previous_part<C: ConnectionTrait>(part: &Model, db: &C) -> Result<Option<Model>> {
     let previous_part = Entity::find()
         .filter(Column::EpisodeId.eq(part.episode_id))
         .filter(Column::StartsAt.lt(part.starts_at))
         .order_by_desc(Column::StartsAt)
         .limit(1)
-        .one(&ctx.db)
+        .one(db)
         .await?;
-
-    if let Some(prev) = previous_part {
-        return Ok(prev);
-    }
-
-    create_part(part, ctx).await
+    Ok(previous_part)
 }
 
-async fn find_next_part(part: &Model, ctx: &AppContext) -> Result<Model> {
-    let previous_part = Entity::find()
+async fn next_part<C: ConnectionTrait>(part: &Model, db: &C) -> Result<Option<Model>> {
+    let next_part = Entity::find()
         .filter(Column::EpisodeId.eq(part.episode_id))
         .filter(Column::StartsAt.gt(part.starts_at))
         .order_by_asc(Column::StartsAt)
         .limit(1)
-        .one(&ctx.db)
+        .one(db)
         .await?;
+    Ok(next_part)
+}
 
-    if let Some(prev) = previous_part {
+async fn find_previous_part<C: ConnectionTrait>(part: &Model, db: &C) -> Result<Model> {
+    if let Some(prev) = previous_part(part, db).await? {
         return Ok(prev);
     }
 
-    create_part(part, ctx).await
+    create_part(part, db).await
+}
+
+async fn find_next_part<C: ConnectionTrait>(part: &Model, db: &C) -> Result<Model> {
+    if let Some(next) = next_part(part, db).await? {
+        return Ok(next);
+    }
+
+    create_part(part, db).await
 }
 
-async fn create_part(part: &Model, ctx: &AppContext) -> Result<Model> {
+/// The part and, with `until_part_id`, all parts up to that one
+async fn lock_range(
+    ctx: &AppContext,
+    episode_id: i32,
+    id: i32,
+    until_part_id: Option<i32>,
+) -> Result<Vec<i32>> {
+    let part = load_item(ctx, id).await?;
+    if part.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+    let Some(until_part_id) = until_part_id else {
+        return Ok(vec![part.id]);
+    };
+    let until = load_item(ctx, until_part_id).await?;
+    if until.episode_id != episode_id {
+        return Err(Error::NotFound);
+    }
+
+    let parts = Entity::find()
+        .filter(Column::EpisodeId.eq(episode_id))
+        .filter(Column::StartsAt.gte(part.starts_at.min(until.starts_at)))
+        .filter(Column::StartsAt.lte(part.starts_at.max(until.starts_at)))
+        .order_by_asc(Column::StartsAt)
+        .all(&ctx.db)
+        .await?;
+    Ok(parts.iter().map(|x| x.id).collect())
+}
+
+async fn create_part<C: ConnectionTrait>(part: &Model, db: &C) -> Result<Model> {
     let mut item = ActiveModel {
         ..Default::default()
     };
@@ -528,18 +1248,18 @@ async fn create_part(part: &Model, ctx: &AppContext) -> Result<Model> {
     item.ends_at = Set(part.starts_at);
     item.episode_speaker_id = Set(part.episode_speaker_id);
     item.episode_id = Set(part.episode_id);
-    let item = item.insert(&ctx.db).await?;
+    let item = item.insert(db).await?;
 
     Ok(item)
 }
 
-async fn update_sentence(
+async fn update_sentence<C: ConnectionTrait>(
     ui_sentence: &UiUpdateParamsSentence,
     target_part: &Model,
-    ctx: &AppContext,
+    db: &C,
 ) -> Result<String> {
     // Create or fetch sentence
-    let sentence = find_or_create_sentence(&ui_sentence.sentence, &target_part, &ctx).await?;
+    let sentence = find_or_create_sentence(&ui_sentence.sentence, &target_part, db).await?;
     let sentence_id = sentence.id;
     let mut sentence = sentence.into_active_model();
     let text: Vec<String> = ui_sentence
@@ -569,13 +1289,13 @@ async fn update_sentence(
     sentence.starts_at = Set(ui_sentence.words[0].starts_at);
     sentence.ends_at = Set(ui_sentence.words[ui_sentence.words.len() - 1].ends_at);
     sentence.words_per_second = Set(words_per_second);
-    sentence.save(&ctx.db).await?;
+    sentence.save(db).await?;
 
     // Update words
     let word_ids: Vec<i32> = ui_sentence.words.iter().map(|x| x.id).collect();
     let words = WordsNS::Entity::find()
         .filter(WordsNS::Column::Id.is_in(word_ids))
-        .all(&ctx.db)
+        .all(db)
         .await?;
     for word in words {
         let ui_word = ui_sentence.words.iter().find(|x| x.id == word.id).unwrap();
@@ -584,16 +1304,32 @@ async fn update_sentence(
         word.hidden = Set(ui_word.hidden);
         word.text = Set(ui_word.text.clone());
         word.overwrite = Set(ui_word.overwrite.clone());
-        word.save(&ctx.db).await?;
+        word.save(db).await?;
+    }
+
+    // Words the ASR missed
+    for ui_word in ui_sentence.words.iter().filter(|x| x.id <= 0) {
+        let mut word = WordsNS::ActiveModel {
+            ..Default::default()
+        };
+        word.sentence_id = Set(sentence_id);
+        word.text = Set(ui_word.text.trim().to_string());
+        word.overwrite = Set(ui_word.overwrite.clone());
+        word.hidden = Set(ui_word.hidden);
+        word.starts_at = Set(ui_word.starts_at);
+        word.ends_at = Set(ui_word.ends_at);
+        // Typed in by a human, as certain as it gets
+        word.probability = Set(1.0);
+        word.insert(db).await?;
     }
 
     Ok(new_text)
 }
 
-async fn find_or_create_sentence(
+async fn find_or_create_sentence<C: ConnectionTrait>(
     sentence: &UiUpdateParamsSentenceSentence,
     part: &Model,
-    ctx: &AppContext,
+    db: &C,
 ) -> Result<SentencesNS::Model> {
     if sentence.id <= 0 {
         let mut item = SentencesNS::ActiveModel {
@@ -602,33 +1338,110 @@ async fn find_or_create_sentence(
         item.text = Set("".into());
         item.starts_at = Set(sentence.starts_at);
         item.ends_at = Set(sentence.ends_at);
+        item.words_per_second = Set(sentence.words_per_second);
         item.part_id = Set(part.id);
-        let item = item.insert(&ctx.db).await?;
+        let item = item.insert(db).await?;
 
         return Ok(item);
     }
 
-    let item = SentencesNS::Entity::find_by_id(sentence.id)
-        .one(&ctx.db)
-        .await?;
+    let item = SentencesNS::Entity::find_by_id(sentence.id).one(db).await?;
     Ok(item.unwrap())
 }
 
-fn extract_part_from_search_index(
-    reader: &IndexReader,
-    id_term: &Term,
-) -> tantivy::Result<Option<TantivyDocument>> {
-    let searcher = reader.searcher();
+/// Takes the timings of loaded words from the database and places new words
+/// without timing evenly into the gap between their neighbours. Afterwards
+/// the words of every sentence have to be in order, and new words must not
+/// overlap their neighbours.
+fn resolve_word_timings(
+    sentences: &mut [UiUpdateParamsSentence],
+    original_sentences: &[SentencesNS::Model],
+    original_words: &[WordsNS::Model],
+) -> std::result::Result<(), &'static str> {
+    // Sentences made up of new words only stay inside the part
+    let part_bounds = original_sentences
+        .iter()
+        .map(|x| (x.starts_at, x.ends_at))
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)));
 
-    let term_query = TermQuery::new(id_term.clone(), IndexRecordOption::Basic);
-    let top_docs = searcher.search(&term_query, &TopDocs::with_limit(1))?;
+    for sentence in sentences.iter_mut() {
+        let original_sentence = original_sentences
+            .iter()
+            .find(|x| x.id == sentence.sentence.id);
+        let words = &mut sentence.words;
+        for word in words.iter_mut() {
+            if let Some(original) = original_words.iter().find(|x| x.id == word.id) {
+                word.starts_at = original.starts_at;
+                word.ends_at = original.ends_at;
+            }
+        }
 
-    if let Some((_score, doc_address)) = top_docs.first() {
-        let doc = searcher.doc(*doc_address)?;
-        Ok(Some(doc))
-    } else {
-        Ok(None)
+        let mut i = 0;
+        while i < words.len() {
+            if words[i].has_timing() {
+                i += 1;
+                continue;
+            }
+            let run_end = (i..words.len())
+                .find(|x| words[*x].has_timing())
+                .unwrap_or(words.len());
+            let previous_end = i.checked_sub(1).map(|x| words[x].ends_at);
+            let next_start = words.get(run_end).map(|x| x.starts_at);
+            let (from, to) = match (previous_end, next_start) {
+                (Some(from), Some(to)) => (from, to.max(from)),
+                (Some(from), None) => (from, from),
+                (None, Some(to)) => (to, to),
+                (None, None) => return Err("New words need timestamps"),
+            };
+            let step = (to - from) / (run_end - i) as f64;
+            for (n, word) in words[i..run_end].iter_mut().enumerate() {
+                word.starts_at = from + step * n as f64;
+                word.ends_at = from + step * (n + 1) as f64;
+            }
+            i = run_end;
+        }
+
+        // New words stay inside their sentence, which spans its loaded words
+        // even if they came from other sentences
+        let bounds = original_sentence
+            .map(|x| (x.starts_at, x.ends_at))
+            .into_iter()
+            .chain(
+                words
+                    .iter()
+                    .filter(|x| x.id > 0)
+                    .map(|x| (x.starts_at, x.ends_at)),
+            )
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+            .or(part_bounds);
+
+        for (n, word) in words.iter().enumerate() {
+            if word.ends_at < word.starts_at {
+                return Err("Words must not end before they start");
+            }
+            if n > 0 && word.starts_at < words[n - 1].starts_at {
+                return Err("Words are not in order");
+            }
+            if word.id > 0 {
+                continue;
+            }
+            let after_previous = n == 0 || word.starts_at >= words[n - 1].ends_at;
+            let before_next = words.get(n + 1).is_none_or(|x| word.ends_at <= x.starts_at);
+            if !after_previous || !before_next {
+                return Err("New words must not overlap other words");
+            }
+            if bounds.is_some_and(|(from, to)| word.starts_at < from || word.ends_at > to) {
+                return Err("New words must stay inside their sentence");
+            }
+        }
     }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct LockQueryParams {
+    /// Lock a range of parts, from the part in the path up to this one
+    until_part_id: Option<i32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -636,11 +1449,15 @@ pub struct Display {
     pub part: Model,
     pub sentences: Vec<SentenceDisplay>,
     pub approvals: u32,
+    pub status: PartStatus,
+    /// Words that likely are misrecognized glossary terms
+    pub suggestions: Vec<Suggestion>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApprovalResult {
     pub approvals: u32,
+    pub status: PartStatus,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -663,13 +1480,30 @@ pub struct UiUpdateParamsPart {
     pub part_type: i32,
     pub ends_at: f64,
     pub episode_speaker_id: i32,
+    /// `updated_at` of the part the edit is based on. The update is refused
+    /// if the part was changed in the meantime.
+    pub updated_at: DateTimeWithTimeZone,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UiUpdateParamsSentence {
     pub sentence: UiUpdateParamsSentenceSentence,
     pub words: Vec<UiUpdateParamsSentenceWord>,
-    pub move_sentence: Option<String>,
+    pub move_sentence: Option<MoveSentence>,
+}
+
+/// Where a sentence goes that does not stay in the part
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveSentence {
+    /// Append to the previous part
+    Up,
+    /// New part before this one
+    UpNew,
+    /// New part after this one
+    DownNew,
+    /// Prepend to the next part
+    Down,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -681,13 +1515,28 @@ pub struct UiUpdateParamsSentenceSentence {
     pub words_per_second: f64,
 }
 
+/// Words with an id <= 0 are new. Their timing may be left out, they are
+/// placed between their neighbours then.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UiUpdateParamsSentenceWord {
     pub id: i32,
     pub text: String,
     pub overwrite: String,
+    #[serde(default = "missing_timing")]
     pub starts_at: f64,
+    #[serde(default = "missing_timing")]
     pub ends_at: f64,
+    #[serde(default)]
     pub probability: f64,
     pub hidden: bool,
 }
+
+impl UiUpdateParamsSentenceWord {
+    fn has_timing(&self) -> bool {
+        self.starts_at.is_finite() && self.ends_at.is_finite()
+    }
+}
+
+fn missing_timing() -> f64 {
+    f64::NAN
+}
@@ -0,0 +1,145 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::sea_query::Expr;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_auth;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::feeds as FeedsNS;
+use crate::models::_entities::glossary_terms as GlossaryTermsNS;
+use crate::models::_entities::podcasts::{ActiveModel, Entity, Model};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub title: String,
+    pub description: Option<String>,
+    pub artwork_url: Option<String>,
+    pub language: Option<String>,
+}
+
+impl Params {
+    fn update(&self, item: &mut ActiveModel) {
+        item.title = Set(self.title.clone());
+        item.description = Set(self.description.clone());
+        item.artwork_url = Set(self.artwork_url.clone());
+        item.language = Set(self.language.clone());
+    }
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    format::json(Entity::find().all(&ctx.db).await?)
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item);
+    let item = item.insert(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn update(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    let mut item = item.into_active_model();
+    params.update(&mut item);
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+
+    // Episodes and feeds stay, they are just not assigned to a podcast anymore
+    EpisodesNS::Entity::update_many()
+        .col_expr(EpisodesNS::Column::PodcastId, Expr::value(None::<i32>))
+        .filter(EpisodesNS::Column::PodcastId.eq(item.id))
+        .exec(&ctx.db)
+        .await?;
+    FeedsNS::Entity::update_many()
+        .col_expr(FeedsNS::Column::PodcastId, Expr::value(None::<i32>))
+        .filter(FeedsNS::Column::PodcastId.eq(item.id))
+        .exec(&ctx.db)
+        .await?;
+    // Terms of the podcast would apply to all podcasts without it, their
+    // misrecognitions are deleted with them
+    GlossaryTermsNS::Entity::delete_many()
+        .filter(GlossaryTermsNS::Column::PodcastId.eq(item.id))
+        .exec(&ctx.db)
+        .await?;
+
+    item.delete(&ctx.db).await?;
+    format::empty()
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    format::json(load_item(&ctx, id).await?)
+}
+
+#[debug_handler]
+pub async fn list_episodes(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    let episodes = EpisodesNS::Entity::find()
+        .filter(EpisodesNS::Column::PodcastId.eq(item.id))
+        .order_by_asc(EpisodesNS::Column::PublishedAt)
+        .all(&ctx.db)
+        .await?;
+    format::json(episodes)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/podcasts/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", get(get_one))
+        .add("{id}/episodes", get(list_episodes))
+        .add("{id}", delete(remove))
+        .add("{id}", put(update))
+        .add("{id}", patch(update))
+}
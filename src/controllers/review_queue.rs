@@ -0,0 +1,54 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use axum::extract::Query;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use serde::Deserialize;
+
+use crate::common::check_auth;
+use crate::common::review::{self, QueueFilter};
+use crate::common::settings::Settings;
+
+#[derive(Deserialize)]
+pub struct ListQueryParams {
+    episode_id: Option<i32>,
+    podcast_id: Option<i32>,
+    /// Overrides the configured probability threshold
+    threshold: Option<f64>,
+    limit: Option<usize>,
+}
+
+/// Parts most likely to contain transcription errors, of an episode, a
+/// podcast or all episodes
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let settings = Settings::from_config(&ctx.config)?;
+    let filter = QueueFilter {
+        episode_id: params.episode_id,
+        podcast_id: params.podcast_id,
+    };
+    let queue = review::low_probability_queue(
+        &ctx.db,
+        &settings.approvals,
+        &filter,
+        params
+            .threshold
+            .unwrap_or(settings.review_queue.probability_threshold),
+        params.limit.unwrap_or(settings.review_queue.limit),
+    )
+    .await?;
+    format::json(queue)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/review_queue/")
+        .add("/", get(list))
+}
@@ -0,0 +1,236 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::{debug_handler, Extension};
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use sea_orm::{QueryOrder, QuerySelect, QueryTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_auth;
+use crate::common::events::{self, EventKind};
+use crate::common::revisions::{self, PartDiff, RevisionRecorder, ACTION_RESTORE};
+use crate::initializers::tantivy_search::TantivyContainer;
+use crate::models::_entities::approvals as ApprovalsNS;
+use crate::models::_entities::parts as PartsNS;
+use crate::models::_entities::revision_parts as RevisionPartsNS;
+use crate::models::_entities::revisions::{Column, Entity, Model};
+use crate::models::_entities::users as UsersNS;
+use crate::models::part_locks as PartLocksNS;
+
+#[derive(Deserialize)]
+pub struct RestoreQueryParams {
+    /// Restore the state after the revision instead of undoing it
+    after: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevisionListItem {
+    pub revision: Model,
+    pub user_name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevisionDisplay {
+    pub revision: Model,
+    pub user_name: String,
+    pub parts: Vec<PartDiff>,
+}
+
+/// Loads a revision that touched the part
+async fn load_item(ctx: &AppContext, episode_id: i32, part_id: i32, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id)
+        .filter(Column::EpisodeId.eq(episode_id))
+        .filter(
+            Column::Id.in_subquery(
+                RevisionPartsNS::Entity::find()
+                    .select_only()
+                    .column(RevisionPartsNS::Column::RevisionId)
+                    .filter(RevisionPartsNS::Column::PartId.eq(part_id))
+                    .into_query(),
+            ),
+        )
+        .one(&ctx.db)
+        .await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+async fn user_names(ctx: &AppContext, revisions: &[Model]) -> Result<Vec<String>> {
+    let users = UsersNS::Entity::find()
+        .filter(UsersNS::Column::Id.is_in(revisions.iter().map(|x| x.user_id)))
+        .all(&ctx.db)
+        .await?;
+    Ok(revisions
+        .iter()
+        .map(|x| {
+            users
+                .iter()
+                .find(|user| user.id == x.user_id)
+                .map(|user| user.name.clone())
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let revision_ids = RevisionPartsNS::Entity::find()
+        .select_only()
+        .column(RevisionPartsNS::Column::RevisionId)
+        .filter(RevisionPartsNS::Column::PartId.eq(part_id))
+        .into_query();
+    let items = Entity::find()
+        .filter(Column::EpisodeId.eq(episode_id))
+        .filter(Column::Id.in_subquery(revision_ids))
+        .order_by_desc(Column::Id)
+        .all(&ctx.db)
+        .await?;
+
+    let user_names = user_names(&ctx, &items).await?;
+    let output: Vec<RevisionListItem> = items
+        .into_iter()
+        .zip(user_names)
+        .map(|(revision, user_name)| RevisionListItem {
+            revision,
+            user_name,
+        })
+        .collect();
+    format::json(output)
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((episode_id, part_id, id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let revision = load_item(&ctx, episode_id, part_id, id).await?;
+    let revision_parts = RevisionPartsNS::Entity::find()
+        .filter(RevisionPartsNS::Column::RevisionId.eq(revision.id))
+        .order_by_asc(RevisionPartsNS::Column::PartId)
+        .all(&ctx.db)
+        .await?;
+
+    let parts = revision_parts
+        .iter()
+        .map(revisions::diff)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let user_name = user_names(&ctx, std::slice::from_ref(&revision))
+        .await?
+        .remove(0);
+
+    format::json(RevisionDisplay {
+        revision,
+        user_name,
+        parts,
+    })
+}
+
+/// Undoes a revision by bringing all parts it touched back into their
+/// previous state. The restore itself is recorded as a new revision.
+#[debug_handler]
+pub async fn restore(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path((episode_id, part_id, id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+    params: Query<RestoreQueryParams>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    let revision = load_item(&ctx, episode_id, part_id, id).await?;
+    let revision_parts = RevisionPartsNS::Entity::find()
+        .filter(RevisionPartsNS::Column::RevisionId.eq(revision.id))
+        .all(&ctx.db)
+        .await?;
+    let part_ids: Vec<i32> = revision_parts.iter().map(|x| x.part_id).collect();
+
+    // Parts claimed by somebody else are off limits
+    let held_by_others =
+        PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids.clone()).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut recorder = RevisionRecorder::new();
+    for part_id in &part_ids {
+        recorder.track(&txn, *part_id).await?;
+    }
+    let existing: Vec<i32> = PartsNS::Entity::find()
+        .select_only()
+        .column(PartsNS::Column::Id)
+        .filter(PartsNS::Column::Id.is_in(part_ids.clone()))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    revisions::restore(&txn, &revision_parts, params.after.unwrap_or(false)).await?;
+
+    // The text changed, earlier approvals do not apply anymore
+    ApprovalsNS::Entity::delete_many()
+        .filter(ApprovalsNS::Column::PartId.is_in(part_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    let revision = recorder
+        .save(&txn, episode_id, auth.user.id, ACTION_RESTORE)
+        .await?;
+    txn.commit().await?;
+
+    // The search index only learns about the restore once it is stored
+    let parts = PartsNS::Entity::find()
+        .filter(PartsNS::Column::Id.is_in(part_ids.clone()))
+        .all(&ctx.db)
+        .await?;
+    for part_id in &part_ids {
+        match parts.iter().find(|x| x.id == *part_id) {
+            Some(part) => tantivy.update_part(part),
+            None => {
+                tantivy.remove_part(*part_id);
+                Ok(())
+            }
+        }
+        .map_err(|e| Error::Message(e.to_string()))?;
+    }
+    tantivy
+        .commit()
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+    for part_id in part_ids {
+        let restored = parts.iter().any(|x| x.id == part_id);
+        let kind = match (existing.contains(&part_id), restored) {
+            (true, true) => EventKind::PartUpdated { part_id },
+            (false, true) => EventKind::PartCreated { part_id },
+            (_, false) => EventKind::PartDeleted { part_id },
+        };
+        events::publish(episode_id, kind);
+        if restored {
+            events::publish(
+                episode_id,
+                EventKind::ApprovalChanged {
+                    part_id,
+                    approvals: 0,
+                },
+            );
+        }
+    }
+    format::json(revision)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/episodes/{episode_id}/parts/{part_id}/revisions/")
+        .add("/", get(list))
+        .add("{id}", get(get_one))
+        .add("{id}/restore", post(restore))
+}
@@ -0,0 +1,102 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::check_auth,
+    models::_entities::speaker_aliases::{ActiveModel, Column, Entity, Model},
+    models::_entities::speakers as SpeakersNS,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub name: String,
+}
+
+impl Params {
+    fn update(&self, item: &mut ActiveModel) {
+        item.name = Set(self.name.clone());
+    }
+}
+
+async fn load_item(ctx: &AppContext, speaker_id: i32, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id)
+        .filter(Column::SpeakerId.eq(speaker_id))
+        .one(&ctx.db)
+        .await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(speaker_id): Path<i32>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    format::json(
+        Entity::find()
+            .filter(Column::SpeakerId.eq(speaker_id))
+            .all(&ctx.db)
+            .await?,
+    )
+}
+
+/// Adds another name the speaker is known by. Imports resolve it to the
+/// speaker.
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(speaker_id): Path<i32>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    SpeakersNS::Entity::find_by_id(speaker_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+    let existing = Entity::find()
+        .filter(Column::Name.eq(&params.name))
+        .one(&ctx.db)
+        .await?;
+    if existing.is_some() {
+        return Err(Error::BadRequest(
+            "Alias is already used by a speaker".to_string(),
+        ));
+    }
+
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    params.update(&mut item);
+    item.speaker_id = Set(speaker_id);
+    let item = item.insert(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((speaker_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    load_item(&ctx, speaker_id, id)
+        .await?
+        .delete(&ctx.db)
+        .await?;
+    format::empty()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/speakers/{speaker_id}/aliases/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", delete(remove))
+}
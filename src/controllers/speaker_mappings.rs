@@ -0,0 +1,110 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::debug_handler;
+use loco_rs::controller::middleware;
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::check_auth,
+    models::_entities::podcasts as PodcastsNS,
+    models::_entities::speaker_mappings::{ActiveModel, Column, Entity, Model},
+    models::_entities::speakers as SpeakersNS,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    /// Diarization label, e.g. `SPEAKER_00`
+    pub label: String,
+    pub speaker_id: i32,
+}
+
+async fn load_item(ctx: &AppContext, podcast_id: i32, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id)
+        .filter(Column::PodcastId.eq(podcast_id))
+        .one(&ctx.db)
+        .await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(podcast_id): Path<i32>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    format::json(
+        Entity::find()
+            .filter(Column::PodcastId.eq(podcast_id))
+            .all(&ctx.db)
+            .await?,
+    )
+}
+
+/// Remembers the speaker behind a diarization label for future imports into
+/// episodes of the podcast. Replaces an existing mapping of the label.
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Path(podcast_id): Path<i32>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    PodcastsNS::Entity::find_by_id(podcast_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+    SpeakersNS::Entity::find_by_id(params.speaker_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::BadRequest("Speaker does not exist".to_string()))?;
+
+    let existing = Entity::find()
+        .filter(Column::PodcastId.eq(podcast_id))
+        .filter(Column::Label.eq(&params.label))
+        .one(&ctx.db)
+        .await?;
+    let item = match existing {
+        Some(item) => {
+            let mut item = item.into_active_model();
+            item.speaker_id = Set(params.speaker_id);
+            item.update(&ctx.db).await?
+        }
+        None => {
+            ActiveModel {
+                podcast_id: Set(podcast_id),
+                label: Set(params.label.clone()),
+                speaker_id: Set(params.speaker_id),
+                ..Default::default()
+            }
+            .insert(&ctx.db)
+            .await?
+        }
+    };
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path((podcast_id, id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    load_item(&ctx, podcast_id, id)
+        .await?
+        .delete(&ctx.db)
+        .await?;
+    format::empty()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/podcasts/{podcast_id}/speaker_mappings/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("{id}", delete(remove))
+}
@@ -2,13 +2,20 @@
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
 use axum::debug_handler;
+use axum::extract::Query;
+use axum::http::StatusCode;
 use loco_rs::controller::middleware;
 use loco_rs::prelude::*;
+use sea_orm::{QuerySelect, QueryTrait};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::check_auth,
-    models::_entities::speakers::{ActiveModel, Entity, Model},
+    common::{check_auth, speaker_merge, speaker_stats},
+    models::_entities::episode_speakers as EpisodeSpeakersNS,
+    models::_entities::episodes as EpisodesNS,
+    models::_entities::parts as PartsNS,
+    models::part_locks as PartLocksNS,
+    models::_entities::speakers::{ActiveModel, Column, Entity, Model},
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,6 +31,17 @@ impl Params {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergeParams {
+    /// The duplicate, it is removed
+    pub speaker_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ListQueryParams {
+    podcast_id: Option<i32>,
+}
+
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
     let item = Entity::find_by_id(id).one(&ctx.db).await?;
     item.ok_or_else(|| Error::NotFound)
@@ -33,9 +51,25 @@ async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
 pub async fn list(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
     State(ctx): State<AppContext>,
+    params: Query<ListQueryParams>,
 ) -> Result<Response> {
     check_auth::check_reader(&auth.user)?;
-    format::json(Entity::find().all(&ctx.db).await?)
+    let mut query = Entity::find();
+    if let Some(podcast_id) = params.podcast_id {
+        // Speakers are shared between podcasts, only list those appearing in one of its episodes
+        let episode_ids = EpisodesNS::Entity::find()
+            .select_only()
+            .column(EpisodesNS::Column::Id)
+            .filter(EpisodesNS::Column::PodcastId.eq(podcast_id))
+            .into_query();
+        let speaker_ids = EpisodeSpeakersNS::Entity::find()
+            .select_only()
+            .column(EpisodeSpeakersNS::Column::SpeakerId)
+            .filter(EpisodeSpeakersNS::Column::EpisodeId.in_subquery(episode_ids))
+            .into_query();
+        query = query.filter(Column::Id.in_subquery(speaker_ids));
+    }
+    format::json(query.all(&ctx.db).await?)
 }
 
 #[debug_handler]
@@ -79,6 +113,65 @@ pub async fn remove(
     format::empty()
 }
 
+/// Merges a duplicate into the speaker. Its episodes are given to the
+/// speaker, then it is removed.
+#[debug_handler]
+pub async fn merge(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<MergeParams>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    if params.speaker_id == id {
+        return Err(Error::BadRequest(
+            "Cannot merge a speaker into itself".to_string(),
+        ));
+    }
+    let item = load_item(&ctx, id).await?;
+    let duplicate = load_item(&ctx, params.speaker_id).await?;
+
+    let part_ids: Vec<i32> = PartsNS::Entity::find()
+        .select_only()
+        .column(PartsNS::Column::Id)
+        .filter(
+            PartsNS::Column::EpisodeSpeakerId.in_subquery(
+                EpisodeSpeakersNS::Entity::find()
+                    .select_only()
+                    .column(EpisodeSpeakersNS::Column::Id)
+                    .filter(EpisodeSpeakersNS::Column::SpeakerId.eq(duplicate.id))
+                    .into_query(),
+            ),
+        )
+        .into_tuple()
+        .all(&ctx.db)
+        .await?;
+    let held_by_others = PartLocksNS::Entity::held_by_others(&ctx.db, &auth.user, part_ids).await?;
+    if !held_by_others.is_empty() {
+        return format::render()
+            .status(StatusCode::LOCKED)
+            .json(held_by_others);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let merged = speaker_merge::merge_speakers(&txn, duplicate, &item, auth.user.id).await?;
+    txn.commit().await?;
+    speaker_merge::publish(&merged);
+    format::json(item)
+}
+
+/// Talk time, word count and appearances of the speaker
+#[debug_handler]
+pub async fn get_stats(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_reader(&auth.user)?;
+    let item = load_item(&ctx, id).await?;
+    format::json(speaker_stats::speaker_stats(&ctx.db, item.id).await?)
+}
+
 #[debug_handler]
 pub async fn get_one(
     auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
@@ -95,7 +188,9 @@ pub fn routes() -> Routes {
         .add("/", get(list))
         .add("/", post(add))
         .add("{id}", get(get_one))
+        .add("{id}/stats", get(get_stats))
         .add("{id}", delete(remove))
         .add("{id}", put(update))
         .add("{id}", patch(update))
+        .add("{id}/merge", post(merge))
 }
@@ -0,0 +1,267 @@
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::unnecessary_struct_initialization)]
+#![allow(clippy::unused_async)]
+use axum::http::StatusCode;
+use axum::{debug_handler, Extension};
+use loco_rs::controller::{middleware, ErrorDetail};
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use serde::{Deserialize, Serialize};
+
+use crate::common::audio::episode_audio_path;
+use crate::common::check_auth;
+use crate::common::import::{self, ImportTranscription};
+use crate::common::settings::Settings;
+use crate::initializers::tantivy_search::TantivyContainer;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::transcription_jobs::{ActiveModel, Column, Entity, Model};
+use crate::models::transcription_jobs::{STATUS_DONE, STATUS_FAILED, STATUS_LEASED, STATUS_QUEUED};
+use crate::workers::transcriber::{TranscriptionWorker, TranscriptionWorkerArgs};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Params {
+    pub episode_id: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeartbeatParams {
+    /// Between 0 and 1
+    pub progress: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FailureParams {
+    pub error: String,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+
+/// Loads a job the worker is currently holding the lease for
+async fn load_leased_item(
+    ctx: &AppContext,
+    id: i32,
+    worker: &crate::models::users::Model,
+) -> Result<Model> {
+    let item = load_item(ctx, id).await?;
+    if !item.is_leased_by(worker.id, chrono::Utc::now().into()) {
+        return Err(Error::CustomError(
+            StatusCode::CONFLICT,
+            ErrorDetail::new("lease_lost", "The job is not leased by this worker"),
+        ));
+    }
+    Ok(item)
+}
+
+#[debug_handler]
+pub async fn list(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    format::json(
+        Entity::find()
+            .order_by_desc(Column::Id)
+            .all(&ctx.db)
+            .await?,
+    )
+}
+
+#[debug_handler]
+pub async fn add(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<Params>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    let episode = EpisodesNS::Entity::find_by_id(params.episode_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+    if !episode.has_audio_file {
+        return Err(Error::BadRequest("Episode has no audio file".into()));
+    }
+
+    let pending = Entity::find()
+        .filter(Column::EpisodeId.eq(episode.id))
+        .filter(Column::Status.is_in([STATUS_QUEUED, STATUS_LEASED]))
+        .one(&ctx.db)
+        .await?;
+    if pending.is_some() {
+        return Err(Error::BadRequest(
+            "Episode is already queued for transcription".into(),
+        ));
+    }
+
+    let mut item = ActiveModel {
+        ..Default::default()
+    };
+    item.episode_id = Set(episode.id);
+    item.status = Set(STATUS_QUEUED.into());
+    item.attempts = Set(0);
+    item.progress = Set(0.0);
+    let item = item.insert(&ctx.db).await?;
+
+    if Settings::from_config(&ctx.config)?
+        .transcription
+        .local
+        .is_some()
+    {
+        TranscriptionWorker::perform_later(&ctx, TranscriptionWorkerArgs { job_id: item.id })
+            .await?;
+    }
+
+    // Depending on the worker mode the transcription might already be done
+    format::json(load_item(&ctx, item.id).await?)
+}
+
+#[debug_handler]
+pub async fn get_one(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_contributor(&auth.user)?;
+    format::json(load_item(&ctx, id).await?)
+}
+
+#[debug_handler]
+pub async fn remove(
+    auth: middleware::auth::JWTWithUser<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_admin(&auth.user)?;
+    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    format::empty()
+}
+
+/// Hands the next job to the worker, `null` if there is nothing to do
+#[debug_handler]
+pub async fn claim(
+    auth: middleware::auth::ApiToken<crate::models::users::Model>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_worker(&auth.user)?;
+    let settings = Settings::from_config(&ctx.config)?.transcription;
+    let now = chrono::Utc::now();
+    Entity::requeue_expired(&ctx.db, now.into(), settings.max_attempts).await?;
+
+    let lease_expires_at = now + chrono::Duration::seconds(settings.lease_seconds);
+    let item = Entity::claim(&ctx.db, auth.user.id, lease_expires_at.into()).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn get_audio(
+    auth: middleware::auth::ApiToken<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    check_auth::check_worker(&auth.user)?;
+    let item = load_leased_item(&ctx, id, &auth.user).await?;
+    let content: Vec<u8> = ctx
+        .storage
+        .download(&episode_audio_path(item.episode_id))
+        .await?;
+    Ok(axum::body::Bytes::from(content).into_response())
+}
+
+/// Extends the lease and records the progress
+#[debug_handler]
+pub async fn heartbeat(
+    auth: middleware::auth::ApiToken<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<HeartbeatParams>,
+) -> Result<Response> {
+    check_auth::check_worker(&auth.user)?;
+    let settings = Settings::from_config(&ctx.config)?.transcription;
+    let item = load_leased_item(&ctx, id, &auth.user).await?;
+
+    let lease_expires_at = chrono::Utc::now() + chrono::Duration::seconds(settings.lease_seconds);
+    let mut item = item.into_active_model();
+    item.lease_expires_at = Set(Some(lease_expires_at.into()));
+    if let Some(progress) = params.progress {
+        item.progress = Set(progress.clamp(0.0, 1.0));
+    }
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+#[debug_handler]
+pub async fn complete(
+    auth: middleware::auth::ApiToken<crate::models::users::Model>,
+    Extension(tantivy): Extension<TantivyContainer>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(transcription): Json<ImportTranscription>,
+) -> Result<Response> {
+    check_auth::check_worker(&auth.user)?;
+    let item = load_leased_item(&ctx, id, &auth.user).await?;
+    let episode_id = item.episode_id;
+
+    let result = import::import_transcription(&ctx.db, &tantivy, episode_id, transcription).await;
+
+    let mut item = item.into_active_model();
+    item.worker_id = Set(None);
+    item.lease_expires_at = Set(None);
+    match &result {
+        Ok(()) => {
+            item.status = Set(STATUS_DONE.into());
+            item.progress = Set(1.0);
+        }
+        Err(e) => {
+            // Trying again will not help, the episode has to be cleaned up first
+            item.status = Set(STATUS_FAILED.into());
+            item.error = Set(Some(e.to_string()));
+        }
+    }
+    let item = item.update(&ctx.db).await?;
+
+    result?;
+    format::json(item)
+}
+
+/// The worker gave up on the job. It is queued again until it failed
+/// `max_attempts` times.
+#[debug_handler]
+pub async fn fail(
+    auth: middleware::auth::ApiToken<crate::models::users::Model>,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(params): Json<FailureParams>,
+) -> Result<Response> {
+    check_auth::check_worker(&auth.user)?;
+    let settings = Settings::from_config(&ctx.config)?.transcription;
+    let item = load_leased_item(&ctx, id, &auth.user).await?;
+    let status = if item.attempts < settings.max_attempts {
+        STATUS_QUEUED
+    } else {
+        STATUS_FAILED
+    };
+
+    let mut item = item.into_active_model();
+    item.status = Set(status.into());
+    item.worker_id = Set(None);
+    item.lease_expires_at = Set(None);
+    item.error = Set(Some(params.error));
+    let item = item.update(&ctx.db).await?;
+    format::json(item)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api/transcription_jobs/")
+        .add("/", get(list))
+        .add("/", post(add))
+        .add("claim", post(claim))
+        .add("{id}", get(get_one))
+        .add("{id}", delete(remove))
+        .add("{id}/audio", get(get_audio))
+        .add("{id}/heartbeat", post(heartbeat))
+        .add("{id}/complete", post(complete))
+        .add("{id}/fail", post(fail))
+}
@@ -125,6 +125,8 @@ async fn update_sentence_and_part(ctx: &AppContext, part_id: i32, sentence_id: i
     let words = Entity::find()
         .filter(Column::SentenceId.eq(sentence_id))
         .order_by_asc(Column::StartsAt)
+        .order_by_asc(Column::EndsAt)
+        .order_by_asc(Column::Id)
         .all(&ctx.db)
         .await?;
 
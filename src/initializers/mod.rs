@@ -1 +1,2 @@
+pub mod spelling;
 pub mod tantivy_search;
@@ -0,0 +1,39 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use axum::{Extension, Router as AxumRouter};
+use loco_rs::prelude::*;
+
+use crate::common::hunspell::{self, Dictionary};
+use crate::common::settings::Settings;
+
+pub struct SpellingInitializer;
+
+/// Parsing a dictionary takes a while, so it is done once per process
+static DICTIONARY: OnceLock<Arc<Dictionary>> = OnceLock::new();
+
+/// The dictionary of the spell check, the configured one or the built-in one
+pub async fn dictionary(ctx: &AppContext) -> Result<Arc<Dictionary>> {
+    if let Some(dictionary) = DICTIONARY.get() {
+        return Ok(dictionary.clone());
+    }
+
+    let path = Settings::from_config(&ctx.config)?.spelling.dictionary;
+    let dictionary = tokio::task::spawn_blocking(move || hunspell::load(path.as_deref()))
+        .await
+        .map_err(|e| Error::Message(e.to_string()))?
+        .map_err(|e| Error::Message(format!("Cannot read the dictionary: {e}")))?;
+
+    Ok(DICTIONARY.get_or_init(|| Arc::new(dictionary)).clone())
+}
+
+#[async_trait]
+impl Initializer for SpellingInitializer {
+    fn name(&self) -> String {
+        "spelling".to_string()
+    }
+
+    async fn after_routes(&self, router: AxumRouter, ctx: &AppContext) -> Result<AxumRouter> {
+        Ok(router.layer(Extension(dictionary(ctx).await?)))
+    }
+}
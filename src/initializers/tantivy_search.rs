@@ -1,69 +1,103 @@
 use std::fs;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
 
 use async_trait::async_trait;
 use axum::{Extension, Router as AxumRouter};
 use loco_rs::prelude::*;
+use sea_orm::{PaginatorTrait, QueryOrder};
 use tantivy::{doc, Index, IndexWriter, ReloadPolicy};
 use tantivy::{schema::*, IndexReader};
 
 pub struct TantivySearchInitializer;
 
-#[async_trait]
-impl Initializer for TantivySearchInitializer {
-    fn name(&self) -> String {
-        "tantivy_search".to_string()
+/// Only one writer may exist per index, so the container is shared by all
+/// routers and background workers of the process
+static CONTAINER: OnceLock<TantivyContainer> = OnceLock::new();
+
+/// The search index of the process, opened on first use. Workers call this
+/// directly as the initializer only runs for processes that serve requests.
+#[allow(clippy::result_large_err)]
+pub fn container(ctx: &AppContext) -> Result<TantivyContainer> {
+    if let Some(tantivy_container) = CONTAINER.get() {
+        return Ok(tantivy_container.clone());
     }
 
-    async fn after_routes(&self, router: AxumRouter, ctx: &AppContext) -> Result<AxumRouter> {
-        let tantivy_search_config = ctx
-            .config
-            .initializers
-            .clone()
-            .ok_or_else(|| Error::Message("initializers config not configured".to_string()))?;
-
-        let tantivy_search_value =
-            tantivy_search_config.get("tantivy_search").ok_or_else(|| {
-                Error::Message("tantivy search not configured as initializer".to_string())
-            })?;
-
-        let tantivy_search: TantivySearchConfig =
-            serde_json::from_value(tantivy_search_value.clone())
-                .map_err(|e| Error::Message(e.to_string()))?;
-
-        let index_path = &tantivy_search.index_path;
+    let tantivy_search_config = ctx
+        .config
+        .initializers
+        .clone()
+        .ok_or_else(|| Error::Message("initializers config not configured".to_string()))?;
+
+    let tantivy_search_value = tantivy_search_config.get("tantivy_search").ok_or_else(|| {
+        Error::Message("tantivy search not configured as initializer".to_string())
+    })?;
+
+    let tantivy_search: TantivySearchConfig = serde_json::from_value(tantivy_search_value.clone())
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+    let index_path = &tantivy_search.index_path;
+    fs::create_dir_all(index_path)?;
+
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("id", STRING | STORED);
+    schema_builder.add_text_field("text", TEXT);
+    schema_builder.add_text_field("episode_id", STRING);
+
+    let schema = schema_builder.build();
+    let dir = tantivy::directory::MmapDirectory::open(index_path)
+        .map_err(|e| Error::Message(e.to_string()))?;
+    // Indexes of older versions lack fields, they are built again from scratch
+    let outdated = Index::exists(&dir).map_err(|e| Error::Message(e.to_string()))?
+        && Index::open(dir.clone())
+            .map_err(|e| Error::Message(e.to_string()))?
+            .schema()
+            != schema;
+    if outdated {
+        tracing::warn!(index_path, "search index is outdated, rebuilding it");
+        fs::remove_dir_all(index_path)?;
         fs::create_dir_all(index_path)?;
+    }
+    let dir = tantivy::directory::MmapDirectory::open(index_path)
+        .map_err(|e| Error::Message(e.to_string()))?;
+    let index =
+        Index::open_or_create(dir, schema.clone()).map_err(|e| Error::Message(e.to_string()))?;
 
-        let mut schema_builder = Schema::builder();
-        schema_builder.add_text_field("id", STRING | STORED);
-        schema_builder.add_text_field("text", TEXT);
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::OnCommitWithDelay)
+        .try_into()
+        .map_err(|e| Error::Message(e.to_string()))?;
 
-        let schema = schema_builder.build();
-        let dir = tantivy::directory::MmapDirectory::open(&index_path)
-            .map_err(|e| Error::Message(e.to_string()))?;
-        let index = Index::open_or_create(dir, schema.clone())
-            .map_err(|e| Error::Message(e.to_string()))?;
+    let writer: Arc<RwLock<IndexWriter>> = Arc::new(RwLock::new(
+        index
+            .writer(50_000_000)
+            .map_err(|e| Error::Message(e.to_string()))?,
+    ));
 
-        let reader = index
-            .reader_builder()
-            .reload_policy(ReloadPolicy::OnCommitWithDelay)
-            .try_into()
-            .map_err(|e| Error::Message(e.to_string()))?;
+    let tantivy_container = TantivyContainer {
+        schema,
+        index,
+        reader,
+        writer,
+        outdated: Arc::new(AtomicBool::new(outdated)),
+    };
 
-        let writer: Arc<RwLock<IndexWriter>> = Arc::new(RwLock::new(
-            index
-                .writer(50_000_000)
-                .map_err(|e| Error::Message(e.to_string()))?,
-        ));
+    Ok(CONTAINER.get_or_init(|| tantivy_container).clone())
+}
 
-        let tantivy_container = TantivyContainer {
-            schema,
-            index,
-            reader,
-            writer,
-        };
+#[async_trait]
+impl Initializer for TantivySearchInitializer {
+    fn name(&self) -> String {
+        "tantivy_search".to_string()
+    }
 
-        Ok(router.layer(Extension(tantivy_container)))
+    async fn after_routes(&self, router: AxumRouter, ctx: &AppContext) -> Result<AxumRouter> {
+        let container = container(ctx)?;
+        if container.outdated.swap(false, Ordering::SeqCst) {
+            container.rebuild(&ctx.db).await?;
+        }
+        Ok(router.layer(Extension(container)))
     }
 }
 
@@ -78,4 +112,60 @@ pub struct TantivyContainer {
     pub index: Index,
     pub reader: IndexReader,
     pub writer: Arc<RwLock<IndexWriter>>,
+    /// The index was created again and still has to be filled
+    outdated: Arc<AtomicBool>,
+}
+
+impl TantivyContainer {
+    /// Replaces the indexed text of a part. Only normal parts (type 0) are
+    /// searchable. Changes are visible after `commit`.
+    pub fn update_part(
+        &self,
+        part: &crate::models::_entities::parts::Model,
+    ) -> tantivy::Result<()> {
+        self.remove_part(part.id);
+        if part.part_type == 0 {
+            let index_id = self.schema.get_field("id")?;
+            let index_text = self.schema.get_field("text")?;
+            let index_episode_id = self.schema.get_field("episode_id")?;
+            self.writer.read().unwrap().add_document(doc!(
+                index_id => part.id.to_string(),
+                index_text => part.text.clone(),
+                index_episode_id => part.episode_id.to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_part(&self, part_id: i32) {
+        if let Ok(index_id) = self.schema.get_field("id") {
+            self.writer
+                .read()
+                .unwrap()
+                .delete_term(Term::from_field_text(index_id, &part_id.to_string()));
+        }
+    }
+
+    pub fn commit(&self) -> tantivy::Result<()> {
+        self.writer.write().unwrap().commit()?;
+        self.reader.reload()
+    }
+
+    /// Replaces the whole index with the parts in the database
+    pub async fn rebuild(&self, db: &DatabaseConnection) -> Result<()> {
+        self.writer
+            .read()
+            .unwrap()
+            .delete_all_documents()
+            .map_err(|e| Error::Message(e.to_string()))?;
+        let mut pages = crate::models::_entities::parts::Entity::find()
+            .order_by_asc(crate::models::_entities::parts::Column::Id)
+            .paginate(db, 1000);
+        while let Some(parts) = pages.fetch_and_next().await? {
+            for part in &parts {
+                self.update_part(part)
+                    .map_err(|e| Error::Message(e.to_string()))?;
+            }
+        }
+        self.commit().map_err(|e| Error::Message(e.to_string()))
+    }
 }
@@ -24,6 +24,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Episodes,
+    #[sea_orm(has_many = "super::merged_episode_speakers::Entity")]
+    MergedEpisodeSpeakers,
     #[sea_orm(has_many = "super::parts::Entity")]
     Parts,
     #[sea_orm(
@@ -42,6 +44,12 @@ impl Related<super::episodes::Entity> for Entity {
     }
 }
 
+impl Related<super::merged_episode_speakers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MergedEpisodeSpeakers.def()
+    }
+}
+
 impl Related<super::parts::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Parts.def()
@@ -18,14 +18,42 @@ pub struct Model {
     pub published_at: Option<DateTimeWithTimeZone>,
     pub filename: String,
     pub has_audio_file: bool,
+    pub audio_url: Option<String>,
+    pub podcast_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::assignments::Entity")]
+    Assignments,
+    #[sea_orm(has_many = "super::downloads::Entity")]
+    Downloads,
     #[sea_orm(has_many = "super::episode_speakers::Entity")]
     EpisodeSpeakers,
     #[sea_orm(has_many = "super::parts::Entity")]
     Parts,
+    #[sea_orm(
+        belongs_to = "super::podcasts::Entity",
+        from = "Column::PodcastId",
+        to = "super::podcasts::Column::Id"
+    )]
+    Podcasts,
+    #[sea_orm(has_many = "super::revisions::Entity")]
+    Revisions,
+    #[sea_orm(has_many = "super::transcription_jobs::Entity")]
+    TranscriptionJobs,
+}
+
+impl Related<super::assignments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Assignments.def()
+    }
+}
+
+impl Related<super::downloads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Downloads.def()
+    }
 }
 
 impl Related<super::episode_speakers::Entity> for Entity {
@@ -39,3 +67,21 @@ impl Related<super::parts::Entity> for Entity {
         Relation::Parts.def()
     }
 }
+
+impl Related<super::podcasts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Podcasts.def()
+    }
+}
+
+impl Related<super::revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Revisions.def()
+    }
+}
+
+impl Related<super::transcription_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TranscriptionJobs.def()
+    }
+}
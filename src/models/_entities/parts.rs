@@ -25,6 +25,10 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::approvals::Entity")]
     Approvals,
+    #[sea_orm(has_many = "super::comments::Entity")]
+    Comments,
+    #[sea_orm(has_many = "super::part_locks::Entity")]
+    PartLocks,
     #[sea_orm(
         belongs_to = "super::episode_speakers::Entity",
         from = "Column::EpisodeSpeakerId",
@@ -51,6 +55,18 @@ impl Related<super::approvals::Entity> for Entity {
     }
 }
 
+impl Related<super::comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comments.def()
+    }
+}
+
+impl Related<super::part_locks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PartLocks.def()
+    }
+}
+
 impl Related<super::episode_speakers::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::EpisodeSpeakers.def()
@@ -1,10 +1,24 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.4
 
 pub use super::approvals::Entity as Approvals;
+pub use super::assignments::Entity as Assignments;
+pub use super::comments::Entity as Comments;
+pub use super::downloads::Entity as Downloads;
 pub use super::episode_speakers::Entity as EpisodeSpeakers;
 pub use super::episodes::Entity as Episodes;
+pub use super::feeds::Entity as Feeds;
+pub use super::glossary_misrecognitions::Entity as GlossaryMisrecognitions;
+pub use super::glossary_terms::Entity as GlossaryTerms;
+pub use super::merged_episode_speakers::Entity as MergedEpisodeSpeakers;
+pub use super::part_locks::Entity as PartLocks;
 pub use super::parts::Entity as Parts;
+pub use super::podcasts::Entity as Podcasts;
+pub use super::revision_parts::Entity as RevisionParts;
+pub use super::revisions::Entity as Revisions;
 pub use super::sentences::Entity as Sentences;
+pub use super::speaker_aliases::Entity as SpeakerAliases;
+pub use super::speaker_mappings::Entity as SpeakerMappings;
 pub use super::speakers::Entity as Speakers;
+pub use super::transcription_jobs::Entity as TranscriptionJobs;
 pub use super::users::Entity as Users;
 pub use super::words::Entity as Words;
@@ -19,6 +19,10 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::episode_speakers::Entity")]
     EpisodeSpeakers,
+    #[sea_orm(has_many = "super::speaker_aliases::Entity")]
+    SpeakerAliases,
+    #[sea_orm(has_many = "super::speaker_mappings::Entity")]
+    SpeakerMappings,
 }
 
 impl Related<super::episode_speakers::Entity> for Entity {
@@ -26,3 +30,15 @@ impl Related<super::episode_speakers::Entity> for Entity {
         Relation::EpisodeSpeakers.def()
     }
 }
+
+impl Related<super::speaker_aliases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SpeakerAliases.def()
+    }
+}
+
+impl Related<super::speaker_mappings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SpeakerMappings.def()
+    }
+}
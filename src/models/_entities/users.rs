@@ -31,6 +31,14 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::approvals::Entity")]
     Approvals,
+    #[sea_orm(has_many = "super::assignments::Entity")]
+    Assignments,
+    #[sea_orm(has_many = "super::comments::Entity")]
+    Comments,
+    #[sea_orm(has_many = "super::part_locks::Entity")]
+    PartLocks,
+    #[sea_orm(has_many = "super::revisions::Entity")]
+    Revisions,
 }
 
 impl Related<super::approvals::Entity> for Entity {
@@ -39,6 +47,30 @@ impl Related<super::approvals::Entity> for Entity {
     }
 }
 
+impl Related<super::assignments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Assignments.def()
+    }
+}
+
+impl Related<super::comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comments.def()
+    }
+}
+
+impl Related<super::part_locks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PartLocks.def()
+    }
+}
+
+impl Related<super::revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Revisions.def()
+    }
+}
+
 impl Related<super::parts::Entity> for Entity {
     fn to() -> RelationDef {
         super::approvals::Relation::Parts.def()
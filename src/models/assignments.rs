@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+pub use super::_entities::assignments::{ActiveModel, Model, Entity};
+use crate::common::part_operations::Selection;
+pub type Assignments = Entity;
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_IN_PROGRESS: &str = "in_progress";
+pub const STATUS_DONE: &str = "done";
+pub const STATUSES: [&str; 3] = [STATUS_OPEN, STATUS_IN_PROGRESS, STATUS_DONE];
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// The assigned time range of the episode, everything if it has none
+    pub fn selection(&self) -> Selection {
+        Selection {
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            from_episode_speaker_id: None,
+        }
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
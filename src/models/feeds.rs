@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+pub use super::_entities::feeds::{ActiveModel, Model, Entity};
+pub type Feeds = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Whether the poll interval (in minutes) has passed since the last poll
+    pub fn is_due(&self, now: DateTimeWithTimeZone) -> bool {
+        self.last_polled_at.is_none_or(|x| {
+            now - x >= chrono::Duration::minutes(i64::from(self.poll_interval))
+        })
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
@@ -1,9 +1,23 @@
 pub mod _entities;
 pub mod approvals;
+pub mod assignments;
+pub mod comments;
+pub mod downloads;
 pub mod episode_speakers;
 pub mod episodes;
+pub mod feeds;
+pub mod glossary_misrecognitions;
+pub mod glossary_terms;
+pub mod merged_episode_speakers;
+pub mod part_locks;
 pub mod parts;
+pub mod podcasts;
+pub mod revision_parts;
+pub mod revisions;
 pub mod sentences;
+pub mod speaker_aliases;
+pub mod speaker_mappings;
 pub mod speakers;
+pub mod transcription_jobs;
 pub mod users;
 pub mod words;
@@ -0,0 +1,163 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{Condition, TransactionTrait};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::part_locks::{ActiveModel, Model, Entity};
+use super::_entities::part_locks::Column;
+use super::_entities::users;
+use crate::common::check_auth;
+pub type PartLocks = Entity;
+
+/// A lock as shown to other contributors
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LockDisplay {
+    pub part_id: i32,
+    pub user_id: i32,
+    pub user_name: String,
+    pub expires_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Locks on the parts that did not expire yet, with their holders
+    pub async fn find_active<C: ConnectionTrait>(
+        db: &C,
+        part_ids: Vec<i32>,
+        now: DateTimeWithTimeZone,
+    ) -> Result<Vec<LockDisplay>, DbErr> {
+        let locks = Entity::find()
+            .filter(Column::PartId.is_in(part_ids))
+            .filter(Column::ExpiresAt.gt(now))
+            .find_also_related(users::Entity)
+            .all(db)
+            .await?;
+
+        Ok(locks
+            .into_iter()
+            .map(|(lock, user)| LockDisplay {
+                part_id: lock.part_id,
+                user_id: lock.user_id,
+                user_name: user.map(|x| x.name).unwrap_or_default(),
+                expires_at: lock.expires_at,
+            })
+            .collect())
+    }
+
+    /// Active locks on the parts that keep `user` from editing them. Admins
+    /// may ignore locks, so there are none for them.
+    pub async fn held_by_others<C: ConnectionTrait>(
+        db: &C,
+        user: &users::Model,
+        part_ids: Vec<i32>,
+    ) -> Result<Vec<LockDisplay>, DbErr> {
+        if check_auth::check_admin(user).is_ok() {
+            return Ok(vec![]);
+        }
+        Ok(Self::find_active(db, part_ids, chrono::Utc::now().into())
+            .await?
+            .into_iter()
+            .filter(|x| x.user_id != user.id)
+            .collect())
+    }
+
+    /// Locks the parts for `user_id` unless somebody else holds one of them.
+    /// Returns the locks of others, nothing is locked then. Expired locks and
+    /// locks of the same user are replaced.
+    pub async fn acquire(
+        db: &DatabaseConnection,
+        part_ids: Vec<i32>,
+        user_id: i32,
+        now: DateTimeWithTimeZone,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<Vec<LockDisplay>, DbErr> {
+        let txn = db.begin().await?;
+        let held_by_others: Vec<LockDisplay> = Self::find_active(&txn, part_ids.clone(), now)
+            .await?
+            .into_iter()
+            .filter(|x| x.user_id != user_id)
+            .collect();
+        if !held_by_others.is_empty() {
+            txn.rollback().await?;
+            return Ok(held_by_others);
+        }
+
+        Entity::delete_many()
+            .filter(Column::PartId.is_in(part_ids.clone()))
+            .filter(
+                Condition::any()
+                    .add(Column::ExpiresAt.lte(now))
+                    .add(Column::UserId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        // A lock taken by someone else in the meantime fails on the unique index
+        for part_id in part_ids {
+            ActiveModel {
+                part_id: Set(part_id),
+                user_id: Set(user_id),
+                expires_at: Set(expires_at),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(vec![])
+    }
+
+    /// Extends the locks `user_id` still holds. Returns how many were extended.
+    pub async fn renew(
+        db: &DatabaseConnection,
+        part_ids: Vec<i32>,
+        user_id: i32,
+        now: DateTimeWithTimeZone,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<u64, DbErr> {
+        let result = Entity::update_many()
+            .col_expr(Column::ExpiresAt, Expr::value(expires_at))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::PartId.is_in(part_ids))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::ExpiresAt.gt(now))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Removes the locks of `user_id`, or of anybody with `None`
+    pub async fn release(
+        db: &DatabaseConnection,
+        part_ids: Vec<i32>,
+        user_id: Option<i32>,
+    ) -> Result<(), DbErr> {
+        let mut query = Entity::delete_many().filter(Column::PartId.is_in(part_ids));
+        if let Some(user_id) = user_id {
+            query = query.filter(Column::UserId.eq(user_id));
+        }
+        query.exec(db).await?;
+        Ok(())
+    }
+}
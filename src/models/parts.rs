@@ -2,6 +2,9 @@ use sea_orm::entity::prelude::*;
 pub use super::_entities::parts::{ActiveModel, Model, Entity};
 pub type Parts = Entity;
 
+/// `part_type` of spoken parts, as opposed to music or ads
+pub const PART_TYPE_SPEECH: i32 = 0;
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -1,5 +1,7 @@
 use sea_orm::entity::prelude::*;
 pub use super::_entities::speakers::{ActiveModel, Model, Entity};
+use super::_entities::speakers::Column;
+use super::_entities::speaker_aliases;
 pub type Speakers = Entity;
 
 #[async_trait::async_trait]
@@ -25,4 +27,23 @@ impl Model {}
 impl ActiveModel {}
 
 // implement your custom finders, selectors oriented logic here
-impl Entity {}
+impl Entity {
+    /// The speaker with this name, or else the speaker having it as an alias
+    pub async fn find_by_name_or_alias<C: ConnectionTrait>(
+        db: &C,
+        name: &str,
+    ) -> Result<Option<Model>, DbErr> {
+        let speaker = Self::find().filter(Column::Name.eq(name)).one(db).await?;
+        if speaker.is_some() {
+            return Ok(speaker);
+        }
+        let Some(alias) = speaker_aliases::Entity::find()
+            .filter(speaker_aliases::Column::Name.eq(name))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Self::find_by_id(alias.speaker_id).one(db).await
+    }
+}
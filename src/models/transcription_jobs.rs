@@ -0,0 +1,143 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::Expr;
+use sea_orm::{QueryOrder, UpdateResult};
+pub use super::_entities::transcription_jobs::{ActiveModel, Model, Entity};
+use super::_entities::transcription_jobs::Column;
+pub type TranscriptionJobs = Entity;
+
+pub const STATUS_QUEUED: &str = "queued";
+pub const STATUS_LEASED: &str = "leased";
+pub const STATUS_DONE: &str = "done";
+pub const STATUS_FAILED: &str = "failed";
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Whether `worker_id` is allowed to work on the job right now
+    pub fn is_leased_by(&self, worker_id: i32, now: DateTimeWithTimeZone) -> bool {
+        self.status == STATUS_LEASED
+            && self.worker_id == Some(worker_id)
+            && self.lease_expires_at.is_some_and(|x| x > now)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Hands the oldest queued job to `worker_id` until `lease_expires_at`.
+    /// Several workers may ask at the same time, the status check in the
+    /// update makes sure only one of them gets a job.
+    pub async fn claim(
+        db: &DatabaseConnection,
+        worker_id: i32,
+        lease_expires_at: DateTimeWithTimeZone,
+    ) -> Result<Option<Model>, DbErr> {
+        loop {
+            let Some(job) = Entity::find()
+                .filter(Column::Status.eq(STATUS_QUEUED))
+                .order_by_asc(Column::Id)
+                .one(db)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            if let Some(job) = Self::claim_job(db, job.id, Some(worker_id), lease_expires_at).await? {
+                return Ok(Some(job));
+            }
+        }
+    }
+
+    /// Leases a specific job if it is still queued. Local transcriptions have
+    /// no `worker_id`.
+    pub async fn claim_job(
+        db: &DatabaseConnection,
+        id: i32,
+        worker_id: Option<i32>,
+        lease_expires_at: DateTimeWithTimeZone,
+    ) -> Result<Option<Model>, DbErr> {
+        let result = Entity::update_many()
+            .col_expr(Column::Status, Expr::value(STATUS_LEASED))
+            .col_expr(Column::WorkerId, Expr::value(worker_id))
+            .col_expr(Column::LeaseExpiresAt, Expr::value(lease_expires_at))
+            .col_expr(Column::Attempts, Expr::col(Column::Attempts).add(1))
+            .col_expr(Column::Progress, Expr::value(0.0))
+            .col_expr(Column::Error, Expr::value(None::<String>))
+            .col_expr(Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq(STATUS_QUEUED))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 1 {
+            Entity::find_by_id(id).one(db).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Keeps a job from being handed to someone else
+    pub async fn extend_lease(
+        db: &DatabaseConnection,
+        id: i32,
+        lease_expires_at: DateTimeWithTimeZone,
+    ) -> Result<(), DbErr> {
+        Entity::update_many()
+            .col_expr(Column::LeaseExpiresAt, Expr::value(lease_expires_at))
+            .col_expr(Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq(STATUS_LEASED))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Puts jobs back into the queue whose worker stopped sending heartbeats.
+    /// Jobs that already had `max_attempts` are marked as failed instead.
+    pub async fn requeue_expired(
+        db: &DatabaseConnection,
+        now: DateTimeWithTimeZone,
+        max_attempts: i32,
+    ) -> Result<(), DbErr> {
+        Self::release_expired(now, STATUS_FAILED)
+            .filter(Column::Attempts.gte(max_attempts))
+            .exec(db)
+            .await?;
+        let UpdateResult { rows_affected } = Self::release_expired(now, STATUS_QUEUED)
+            .exec(db)
+            .await?;
+        if rows_affected > 0 {
+            tracing::warn!(rows_affected, "requeued transcription jobs with expired lease");
+        }
+        Ok(())
+    }
+
+    fn release_expired(now: DateTimeWithTimeZone, status: &str) -> sea_orm::UpdateMany<Entity> {
+        let error = (status == STATUS_FAILED).then(|| "Lease expired".to_string());
+        Entity::update_many()
+            .col_expr(Column::Status, Expr::value(status))
+            .col_expr(Column::WorkerId, Expr::value(None::<i32>))
+            .col_expr(Column::LeaseExpiresAt, Expr::value(None::<DateTimeWithTimeZone>))
+            .col_expr(Column::Error, Expr::value(error))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Status.eq(STATUS_LEASED))
+            .filter(Column::LeaseExpiresAt.lt(now))
+    }
+}
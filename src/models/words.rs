@@ -19,7 +19,16 @@ impl ActiveModelBehavior for ActiveModel {
 }
 
 // implement your read-oriented logic here
-impl Model {}
+impl Model {
+    /// The corrected text, or the transcribed one if nobody corrected it
+    pub fn effective_text(&self) -> &str {
+        if self.overwrite.is_empty() {
+            &self.text
+        } else {
+            &self.overwrite
+        }
+    }
+}
 
 // implement your write-oriented logic here
 impl ActiveModel {}
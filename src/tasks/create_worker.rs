@@ -0,0 +1,39 @@
+use loco_rs::prelude::*;
+
+use crate::common::check_auth::ROLE_WORKER;
+use crate::models::users::{self, RegisterParams};
+
+/// Creates an account for a transcription worker machine and prints its API
+/// key. The account can't log in and only reaches the worker endpoints.
+pub struct CreateWorker;
+
+#[async_trait]
+impl Task for CreateWorker {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "create_worker".to_string(),
+            detail: "Create a transcription worker account and print its API key".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let name = vars.cli_arg("name")?;
+        let email = vars.cli_arg("email")?;
+        let user = users::Model::create_with_password(
+            &ctx.db,
+            &RegisterParams {
+                email: email.clone(),
+                // Nobody knows it, the worker uses the API key
+                password: uuid::Uuid::new_v4().to_string(),
+                name: name.clone(),
+            },
+        )
+        .await?;
+
+        let mut user = user.into_active_model();
+        user.role = Set(ROLE_WORKER);
+        let user = user.update(&ctx.db).await?;
+        println!("{}", user.api_key);
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
-
+pub mod create_worker;
+pub mod requeue_transcription_jobs;
+pub mod sync_feeds;
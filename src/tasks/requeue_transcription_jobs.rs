@@ -0,0 +1,44 @@
+use loco_rs::prelude::*;
+
+use crate::common::settings::Settings;
+use crate::models::_entities::transcription_jobs;
+use crate::models::transcription_jobs::STATUS_QUEUED;
+use crate::workers::transcriber::{TranscriptionWorker, TranscriptionWorkerArgs};
+
+/// Puts transcription jobs back into the queue whose worker stopped sending
+/// heartbeats. With a local backend the queued jobs are started right away.
+/// Meant to be run by the scheduler.
+pub struct RequeueTranscriptionJobs;
+
+#[async_trait]
+impl Task for RequeueTranscriptionJobs {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "requeue_transcription_jobs".to_string(),
+            detail: "Requeue transcription jobs with an expired lease".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
+        let settings = Settings::from_config(&ctx.config)?.transcription;
+        transcription_jobs::Entity::requeue_expired(
+            &ctx.db,
+            chrono::Utc::now().into(),
+            settings.max_attempts,
+        )
+        .await?;
+
+        if settings.local.is_none() {
+            return Ok(());
+        }
+        let queued = transcription_jobs::Entity::find()
+            .filter(transcription_jobs::Column::Status.eq(STATUS_QUEUED))
+            .all(&ctx.db)
+            .await?;
+        for job in queued {
+            TranscriptionWorker::perform_later(ctx, TranscriptionWorkerArgs { job_id: job.id })
+                .await?;
+        }
+        Ok(())
+    }
+}
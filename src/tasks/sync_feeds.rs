@@ -0,0 +1,35 @@
+use loco_rs::prelude::*;
+
+use crate::models::_entities::feeds;
+use crate::workers::feed_sync::{FeedSyncWorker, FeedSyncWorkerArgs};
+
+/// Polls all feeds whose poll interval has passed. Meant to be run by the
+/// scheduler every few minutes, `all:true` polls every feed right away.
+pub struct SyncFeeds;
+
+#[async_trait]
+impl Task for SyncFeeds {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "sync_feeds".to_string(),
+            detail: "Poll podcast feeds and sync their episodes".to_string(),
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let all = vars.cli_arg("all").is_ok_and(|x| x == "true");
+        let now = chrono::Utc::now().into();
+
+        for feed in feeds::Entity::find().all(&ctx.db).await? {
+            if !all && !feed.is_due(now) {
+                continue;
+            }
+
+            FeedSyncWorker::build(ctx)
+                .perform(FeedSyncWorkerArgs { feed_id: feed.id })
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,35 @@
 use loco_rs::prelude::*;
+use reqwest::{header, redirect, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 
+use crate::common::audio::episode_audio_path;
+use crate::common::settings::{DownloadSettings, Settings};
+use crate::models::_entities::downloads::{self, Entity};
+use crate::models::_entities::episodes;
+use crate::models::downloads::{STATUS_DONE, STATUS_FAILED, STATUS_RUNNING};
+use crate::workers::waveform::{WaveformWorker, WaveformWorkerArgs};
+
+/// Fetches the audio file of an episode, e.g. from its RSS enclosure.
+///
+/// The progress is tracked in the `downloads` table. Incomplete files are kept
+/// in the download folder so the next attempt can resume them.
 pub struct DownloadWorker {
     pub ctx: AppContext,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct DownloadWorkerArgs {
-    pub user_guid: String,
+    pub download_id: i32,
+}
+
+enum DownloadError {
+    /// Worth another attempt, e.g. a dropped connection or a 5xx response
+    Retry(String),
+    /// Will not get better by trying again
+    Fatal(String),
 }
 
 #[async_trait]
@@ -15,9 +37,208 @@ impl BackgroundWorker<DownloadWorkerArgs> for DownloadWorker {
     fn build(ctx: &AppContext) -> Self {
         Self { ctx: ctx.clone() }
     }
-    async fn perform(&self, _args: DownloadWorkerArgs) -> Result<()> {
-        // TODO: Some actual work goes here...
+
+    async fn perform(&self, args: DownloadWorkerArgs) -> Result<()> {
+        let settings = Settings::from_config(&self.ctx.config)?.downloads;
+        let mut download = Entity::find_by_id(args.download_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+
+        tokio::fs::create_dir_all(&settings.directory).await?;
+        let download_id = download.id;
+        let partial_path = settings.partial_path(download_id);
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::limited(10))
+            // A stalled server fails the attempt instead of blocking forever
+            .connect_timeout(Duration::from_millis(settings.connect_timeout_ms))
+            .read_timeout(Duration::from_millis(settings.read_timeout_ms))
+            .build()
+            .map_err(|e| Error::Message(e.to_string()))?;
+
+        let mut delay = settings.retry_delay_ms;
+        loop {
+            let attempts = download.attempts + 1;
+            let mut item = download.into_active_model();
+            item.status = Set(STATUS_RUNNING.into());
+            item.attempts = Set(attempts);
+            download = item.update(&self.ctx.db).await?;
+
+            let result = fetch(&client, &download.url, &partial_path, &settings).await;
+
+            let mut item = download.into_active_model();
+            item.bytes_downloaded = Set(file_size(&partial_path).await);
+            match result {
+                Ok(bytes_total) => {
+                    item.bytes_total = Set(Some(bytes_total));
+                    download = item.update(&self.ctx.db).await?;
+                    break;
+                }
+                Err(DownloadError::Retry(message)) if attempts < settings.max_attempts => {
+                    tracing::warn!(download_id, message, "retrying download");
+                    item.error = Set(Some(message));
+                    download = item.update(&self.ctx.db).await?;
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    delay *= 2;
+                }
+                Err(DownloadError::Retry(message) | DownloadError::Fatal(message)) => {
+                    tracing::error!(download_id, message, "download failed");
+                    // A new download starts from scratch, nobody is going to resume this one
+                    let _ = tokio::fs::remove_file(&partial_path).await;
+                    item.status = Set(STATUS_FAILED.into());
+                    item.error = Set(Some(message));
+                    item.update(&self.ctx.db).await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        // The row must not stay running, or the episode is never downloaded again
+        if let Err(err) = self.store(&download, &partial_path).await {
+            let message = err.to_string();
+            tracing::error!(download_id, message, "storing download failed");
+            let _ = tokio::fs::remove_file(&partial_path).await;
+            let mut item = download.into_active_model();
+            item.status = Set(STATUS_FAILED.into());
+            item.error = Set(Some(message));
+            item.update(&self.ctx.db).await?;
+            return Ok(());
+        }
+
+        let mut item = download.into_active_model();
+        item.status = Set(STATUS_DONE.into());
+        item.error = Set(None);
+        item.update(&self.ctx.db).await?;
 
         Ok(())
     }
 }
+
+impl DownloadWorker {
+    /// Moves the complete file into the storage and marks the episode as
+    /// having audio
+    async fn store(&self, download: &downloads::Model, partial_path: &Path) -> Result<()> {
+        let content = tokio::fs::read(partial_path).await?;
+        self.ctx
+            .storage
+            .upload(&episode_audio_path(download.episode_id), &content.into())
+            .await?;
+        tokio::fs::remove_file(partial_path).await?;
+
+        let episode = episodes::Entity::find_by_id(download.episode_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+        let mut episode = episode.into_active_model();
+        episode.has_audio_file = Set(true);
+        episode.update(&self.ctx.db).await?;
+
+        WaveformWorker::perform_later(
+            &self.ctx,
+            WaveformWorkerArgs {
+                episode_id: download.episode_id,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Downloads `url` into `partial_path`, continuing where a previous attempt
+/// stopped. Returns the size of the complete file.
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    partial_path: &Path,
+    settings: &DownloadSettings,
+) -> std::result::Result<i64, DownloadError> {
+    let existing = file_size(partial_path).await;
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", existing));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| DownloadError::Retry(e.to_string()))?;
+
+    let resume = match response.status() {
+        StatusCode::PARTIAL_CONTENT => true,
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // Whatever we have does not match the file on the server anymore
+            tokio::fs::remove_file(partial_path).await.map_err(fatal)?;
+            return Err(DownloadError::Retry(
+                "Server refused to resume the download".into(),
+            ));
+        }
+        status if status.is_success() => false,
+        status if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
+            return Err(DownloadError::Retry(format!(
+                "Server responded with {}",
+                status
+            )));
+        }
+        status => {
+            return Err(DownloadError::Fatal(format!(
+                "Server responded with {}",
+                status
+            )));
+        }
+    };
+
+    let offset = if resume { existing as u64 } else { 0 };
+    let total = response.content_length().map(|x| x + offset);
+    if total.is_some_and(|x| x > settings.max_size) {
+        return Err(too_large(settings));
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resume)
+        .truncate(!resume)
+        .open(partial_path)
+        .await
+        .map_err(fatal)?;
+
+    let mut written = offset;
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                file.flush().await.map_err(fatal)?;
+                return Err(DownloadError::Retry(e.to_string()));
+            }
+        };
+
+        written += chunk.len() as u64;
+        if written > settings.max_size {
+            return Err(too_large(settings));
+        }
+        file.write_all(&chunk).await.map_err(fatal)?;
+    }
+    file.flush().await.map_err(fatal)?;
+
+    if total.is_some_and(|x| written < x) {
+        return Err(DownloadError::Retry("Connection closed early".into()));
+    }
+
+    Ok(written as i64)
+}
+
+async fn file_size(path: &Path) -> i64 {
+    tokio::fs::metadata(path)
+        .await
+        .map_or(0, |x| x.len() as i64)
+}
+
+fn fatal(e: std::io::Error) -> DownloadError {
+    DownloadError::Fatal(e.to_string())
+}
+
+fn too_large(settings: &DownloadSettings) -> DownloadError {
+    DownloadError::Fatal(format!("File is larger than {} bytes", settings.max_size))
+}
@@ -0,0 +1,196 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::common::feed::{self, FeedItem};
+use crate::models::_entities::downloads as DownloadsNS;
+use crate::models::_entities::episodes as EpisodesNS;
+use crate::models::_entities::feeds::{self, Entity};
+use crate::models::downloads::{STATUS_FAILED, STATUS_QUEUED};
+use crate::workers::downloader::{DownloadWorker, DownloadWorkerArgs};
+
+/// How long fetching a feed may take before the poll counts as failed
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Polls a podcast feed and creates or updates its episodes
+pub struct FeedSyncWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct FeedSyncWorkerArgs {
+    pub feed_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<FeedSyncWorkerArgs> for FeedSyncWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: FeedSyncWorkerArgs) -> Result<()> {
+        let feed = Entity::find_by_id(args.feed_id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound)?;
+
+        let result = self.sync(&feed).await;
+        if let Err(e) = &result {
+            tracing::error!(feed_id = feed.id, error = e.to_string(), "feed sync failed");
+        }
+
+        let mut item = feed.into_active_model();
+        item.last_polled_at = Set(Some(chrono::Utc::now().into()));
+        item.last_error = Set(result.err().map(|e| e.to_string()));
+        item.update(&self.ctx.db).await?;
+
+        Ok(())
+    }
+}
+
+impl FeedSyncWorker {
+    async fn sync(&self, feed: &feeds::Model) -> Result<()> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| Error::Message(e.to_string()))?;
+        let content = client
+            .get(&feed.url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| Error::Message(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| Error::Message(e.to_string()))?;
+
+        let items = feed::parse(&content, feed.crop_description_at.as_deref())
+            .map_err(|e| Error::Message(e.to_string()))?;
+
+        // One broken item must not keep the others from being synced
+        let total = items.len();
+        let mut errors: Vec<String> = vec![];
+        for item in items {
+            let guid = item.guid.clone();
+            if let Err(e) = self.sync_item(feed, item).await {
+                tracing::error!(
+                    feed_id = feed.id,
+                    guid,
+                    error = e.to_string(),
+                    "item sync failed"
+                );
+                errors.push(format!("{guid}: {e}"));
+            }
+        }
+
+        match errors.first() {
+            Some(error) => Err(Error::Message(format!(
+                "{} of {} items failed, first: {}",
+                errors.len(),
+                total,
+                error
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    async fn sync_item(&self, feed: &feeds::Model, item: FeedItem) -> Result<()> {
+        let episode = self.sync_episode(feed, item).await?;
+        if feed.download_audio {
+            self.queue_download(&episode).await?;
+        }
+        Ok(())
+    }
+
+    async fn sync_episode(&self, feed: &feeds::Model, item: FeedItem) -> Result<EpisodesNS::Model> {
+        // Episodes are identified by their GUID. Episodes that were uploaded
+        // before the feed was added are matched by their filename instead.
+        // GUIDs are only unique within a feed, so only episodes of the
+        // podcast of the feed are considered.
+        let same_podcast = match feed.podcast_id {
+            Some(podcast_id) => EpisodesNS::Column::PodcastId.eq(podcast_id),
+            None => EpisodesNS::Column::PodcastId.is_null(),
+        };
+        let mut existing = EpisodesNS::Entity::find()
+            .filter(same_podcast.clone())
+            .filter(EpisodesNS::Column::ExternalId.eq(&item.guid))
+            .one(&self.ctx.db)
+            .await?;
+        if existing.is_none() {
+            existing = EpisodesNS::Entity::find()
+                .filter(same_podcast)
+                .filter(EpisodesNS::Column::ExternalId.is_null())
+                .filter(EpisodesNS::Column::Filename.eq(&item.filename))
+                .one(&self.ctx.db)
+                .await?;
+        }
+
+        let is_new = existing.is_none();
+        let mut episode = match existing {
+            Some(episode) => episode.into_active_model(),
+            None => EpisodesNS::ActiveModel {
+                filename: Set(item.filename),
+                description: Set(String::new()),
+                has_audio_file: Set(false),
+                ..Default::default()
+            },
+        };
+
+        episode.external_id = Set(Some(item.guid));
+        episode.title = Set(item.title);
+        episode.link = Set(item.link);
+        episode.published_at = Set(item.published_at);
+        episode.audio_url = Set(item.audio_url);
+        episode.podcast_id = Set(feed.podcast_id);
+        // Descriptions might have been edited by hand, only fill in blank ones
+        if episode.description.as_ref().is_empty() {
+            episode.description = Set(item.description);
+        }
+
+        let episode = if is_new {
+            episode.insert(&self.ctx.db).await?
+        } else {
+            episode.update(&self.ctx.db).await?
+        };
+
+        Ok(episode)
+    }
+
+    async fn queue_download(&self, episode: &EpisodesNS::Model) -> Result<()> {
+        let Some(url) = episode.audio_url.clone() else {
+            return Ok(());
+        };
+        if episode.has_audio_file {
+            return Ok(());
+        }
+
+        // Failed downloads are retried on the next poll, anything else is
+        // either done or still in progress.
+        let pending = DownloadsNS::Entity::find()
+            .filter(DownloadsNS::Column::EpisodeId.eq(episode.id))
+            .filter(DownloadsNS::Column::Status.ne(STATUS_FAILED))
+            .one(&self.ctx.db)
+            .await?;
+        if pending.is_some() {
+            return Ok(());
+        }
+
+        let mut item = DownloadsNS::ActiveModel {
+            ..Default::default()
+        };
+        item.episode_id = Set(episode.id);
+        item.url = Set(url);
+        item.status = Set(STATUS_QUEUED.into());
+        item.attempts = Set(0);
+        item.bytes_downloaded = Set(0);
+        let item = item.insert(&self.ctx.db).await?;
+
+        DownloadWorker::perform_later(
+            &self.ctx,
+            DownloadWorkerArgs {
+                download_id: item.id,
+            },
+        )
+        .await
+    }
+}
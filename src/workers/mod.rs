@@ -1 +1,4 @@
 pub mod downloader;
+pub mod feed_sync;
+pub mod transcriber;
+pub mod waveform;
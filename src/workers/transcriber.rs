@@ -0,0 +1,147 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::common::audio::episode_audio_path;
+use crate::common::import;
+use crate::common::settings::Settings;
+use crate::common::transcription::{
+    CommandBackend, Transcript, TranscriptionBackend, TranscriptionError,
+};
+use crate::initializers::tantivy_search;
+use crate::models::_entities::transcription_jobs::{self, Entity};
+use crate::models::transcription_jobs::{STATUS_DONE, STATUS_FAILED, STATUS_QUEUED};
+
+/// Transcribes an episode on this machine with the configured backend. The
+/// job is leased like for remote workers, so it is requeued if the server
+/// stops in the middle of the transcription.
+pub struct TranscriptionWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct TranscriptionWorkerArgs {
+    pub job_id: i32,
+}
+
+impl TranscriptionWorker {
+    /// Fetches the audio and transcribes it while renewing the lease. Every
+    /// failure is returned as a transcription error, so the job is requeued
+    /// or failed instead of staying leased.
+    async fn run(
+        &self,
+        backend: &CommandBackend,
+        job: &transcription_jobs::Model,
+        lease_seconds: i64,
+    ) -> std::result::Result<Transcript, TranscriptionError> {
+        let failed = |e: &dyn std::fmt::Display| TranscriptionError {
+            message: e.to_string(),
+            log: String::new(),
+        };
+        let directory = std::env::temp_dir().join(format!("podscribe-transcription-{}", job.id));
+        tokio::fs::create_dir_all(&directory)
+            .await
+            .map_err(|e| failed(&e))?;
+        let audio_path = directory.join("audio.mp3");
+        let content: Vec<u8> = self
+            .ctx
+            .storage
+            .download(&episode_audio_path(job.episode_id))
+            .await
+            .map_err(|e| failed(&e))?;
+        tokio::fs::write(&audio_path, content)
+            .await
+            .map_err(|e| failed(&e))?;
+
+        let lease = chrono::Duration::seconds(lease_seconds);
+        let transcription = backend.transcribe(&audio_path);
+        tokio::pin!(transcription);
+        let mut heartbeat =
+            tokio::time::interval(Duration::from_secs(lease_seconds.max(2) as u64 / 2));
+        let result = loop {
+            tokio::select! {
+                result = &mut transcription => break result,
+                _ = heartbeat.tick() => {
+                    let expires_at = (chrono::Utc::now() + lease).into();
+                    if let Err(e) = Entity::extend_lease(&self.ctx.db, job.id, expires_at).await {
+                        break Err(failed(&e));
+                    }
+                }
+            }
+        };
+        let _ = tokio::fs::remove_dir_all(&directory).await;
+        result
+    }
+}
+
+#[async_trait]
+impl BackgroundWorker<TranscriptionWorkerArgs> for TranscriptionWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: TranscriptionWorkerArgs) -> Result<()> {
+        let settings = Settings::from_config(&self.ctx.config)?.transcription;
+        let local = settings
+            .local
+            .ok_or_else(|| Error::Message("no local transcription backend configured".into()))?;
+        let backend = CommandBackend::new(local);
+        // Opened before claiming, so the job is not leased in vain
+        let tantivy = tantivy_search::container(&self.ctx)?;
+
+        let lease = chrono::Duration::seconds(settings.lease_seconds);
+        let Some(job) = Entity::claim_job(
+            &self.ctx.db,
+            args.job_id,
+            None,
+            (chrono::Utc::now() + lease).into(),
+        )
+        .await?
+        else {
+            // Already taken by someone else
+            return Ok(());
+        };
+
+        let result = self.run(&backend, &job, settings.lease_seconds).await;
+
+        let mut item = job.clone().into_active_model();
+        item.worker_id = Set(None);
+        item.lease_expires_at = Set(None);
+        match result {
+            Ok(transcript) => {
+                item.log = Set(Some(transcript.log));
+                match import::import_transcription(
+                    &self.ctx.db,
+                    &tantivy,
+                    job.episode_id,
+                    transcript.transcription,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        item.status = Set(STATUS_DONE.into());
+                        item.progress = Set(1.0);
+                    }
+                    Err(e) => {
+                        item.status = Set(STATUS_FAILED.into());
+                        item.error = Set(Some(e.to_string()));
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!(job_id = job.id, error = e.message, "transcription failed");
+                let status = if job.attempts < settings.max_attempts {
+                    STATUS_QUEUED
+                } else {
+                    STATUS_FAILED
+                };
+                item.status = Set(status.into());
+                item.error = Set(Some(e.message));
+                item.log = Set(Some(e.log));
+            }
+        }
+        let _: transcription_jobs::Model = item.update(&self.ctx.db).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::audio;
+use crate::common::waveform::{PeakCollector, Waveform, ZOOM_LEVELS};
+
+/// Decodes the audio file of an episode and stores the peak data for all zoom
+/// levels in the storage.
+pub struct WaveformWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WaveformWorkerArgs {
+    pub episode_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<WaveformWorkerArgs> for WaveformWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: WaveformWorkerArgs) -> Result<()> {
+        let content: Vec<u8> = self
+            .ctx
+            .storage
+            .download(&audio::episode_audio_path(args.episode_id))
+            .await?;
+
+        // Decoding takes a while, do not block the runtime
+        let finest = tokio::task::spawn_blocking(move || {
+            let mut collector = PeakCollector::new(ZOOM_LEVELS[0]);
+            audio::decode_mono(content, |sample_rate, samples| {
+                collector.push(sample_rate, samples);
+                std::ops::ControlFlow::Continue(())
+            })
+            .map(|_| collector.finish())
+        })
+        .await
+        .map_err(|e| Error::Message(e.to_string()))?
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+        for samples_per_pixel in ZOOM_LEVELS {
+            let waveform = finest.downsample(samples_per_pixel / ZOOM_LEVELS[0]);
+            let content = serde_json::to_vec(&waveform)?;
+            self.ctx
+                .storage
+                .upload(
+                    &Waveform::storage_path(args.episode_id, samples_per_pixel),
+                    &content.into(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_assign_and_track_work() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let first = prepare_data::create_user(&ctx, "first@example.com", 2).await;
+        let (first_key, first_value) = prepare_data::auth_header(&first.token);
+        let second = prepare_data::create_user(&ctx, "second@example.com", 2).await;
+        let (second_key, second_value) = prepare_data::auth_header(&second.token);
+
+        let res = request
+            .post("/api/assignments")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "episode_id": episode.id,
+                "user_id": first.user.id,
+                "starts_at": 1.0,
+                "ends_at": 0.0,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post("/api/assignments")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "episode_id": episode.id,
+                "user_id": first.user.id,
+                "due_at": "2020-01-01T00:00:00Z",
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let assignment: serde_json::Value = res.json();
+        assert_eq!(assignment["status"], "open");
+        let assignment_url = format!("/api/assignments/{}", assignment["id"]);
+
+        let res = request
+            .get("/api/assignments/mine")
+            .add_header(first_key.clone(), first_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let mine: serde_json::Value = res.json();
+        assert_eq!(mine.as_array().unwrap().len(), 1);
+        assert_eq!(mine[0]["overdue"], true);
+        assert_eq!(mine[0]["progress"]["parts"], 1);
+        assert_eq!(mine[0]["progress"]["untouched"], 1);
+
+        // Only the assignee and admins report progress
+        let res = request
+            .post(&format!("{}/status", assignment_url))
+            .add_header(second_key.clone(), second_value.clone())
+            .json(&serde_json::json!({ "status": "done" }))
+            .await;
+        assert_eq!(res.status_code(), 401);
+        let res = request
+            .post(&format!("{}/status", assignment_url))
+            .add_header(first_key.clone(), first_value.clone())
+            .json(&serde_json::json!({ "status": "in_progress" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .post(&format!(
+                "/api/episodes/{}/parts/{}/approve",
+                episode.id, part.id
+            ))
+            .add_header(first_key.clone(), first_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .post(&format!("{}/reassign", assignment_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "user_id": second.user.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let reassigned: serde_json::Value = res.json();
+        assert_eq!(reassigned["user_id"], second.user.id);
+        assert_eq!(reassigned["status"], "open");
+
+        let res = request
+            .get("/api/assignments/mine")
+            .add_header(first_key.clone(), first_value.clone())
+            .await;
+        let mine: serde_json::Value = res.json();
+        assert!(mine.as_array().unwrap().is_empty());
+
+        let res = request
+            .get("/api/assignments/overview")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let overview: serde_json::Value = res.json();
+        assert_eq!(overview.as_array().unwrap().len(), 1);
+        assert_eq!(overview[0]["user_id"], second.user.id);
+        assert_eq!(overview[0]["assignments"], 1);
+        assert_eq!(overview[0]["overdue"], 1);
+        assert_eq!(overview[0]["progress"]["approved"], 1);
+        assert_eq!(overview[0]["progress"]["completion"], 100.0);
+
+        // Time ranges only cover the parts spoken within them
+        for (starts_at, ends_at) in [(0.5, 0.8), (1.0, 2.0)] {
+            let res = request
+                .post("/api/assignments")
+                .add_header(auth_key.clone(), auth_value.clone())
+                .json(&serde_json::json!({
+                    "episode_id": episode.id,
+                    "user_id": first.user.id,
+                    "starts_at": starts_at,
+                    "ends_at": ends_at,
+                }))
+                .await;
+            assert_eq!(res.status_code(), 200);
+        }
+        let res = request
+            .get(&format!("/api/assignments?user_id={}", first.user.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let ranged: serde_json::Value = res.json();
+        assert_eq!(ranged.as_array().unwrap().len(), 2);
+        assert_eq!(ranged[0]["progress"]["parts"], 1);
+        assert_eq!(ranged[1]["progress"]["parts"], 0);
+    })
+    .await;
+}
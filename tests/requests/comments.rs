@@ -0,0 +1,271 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{comments, parts};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_discuss_parts_in_threads() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let contributor = prepare_data::create_user(&ctx, "contributor@example.com", 2).await;
+        let (other_key, other_value) = prepare_data::auth_header(&contributor.token);
+        let parts_url = format!("/api/episodes/{}/parts", episode.id);
+        let comments_url = format!("{}/{}/comments", parts_url, part.id);
+
+        let res = request
+            .get(&format!("{}/{}/display", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        let second_word = display["sentences"][0]["words"][1]["id"].clone();
+
+        let res = request
+            .post(&comments_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "text": "Which world?", "from_word_id": 0 }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post(&comments_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "text": "Which world?",
+                "from_word_id": second_word,
+                "to_word_id": second_word,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let thread: serde_json::Value = res.json();
+        assert_eq!(thread["resolved"], false);
+        let thread_url = format!("{}/{}", comments_url, thread["id"]);
+
+        let res = request
+            .post(&comments_url)
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "text": "Sounds fine to me", "parent_id": thread["id"] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let reply: serde_json::Value = res.json();
+
+        let res = request
+            .post(&comments_url)
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "text": "Really?", "parent_id": reply["id"] }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        // Only the author edits
+        let res = request
+            .put(&thread_url)
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "text": "Changed" }))
+            .await;
+        assert_eq!(res.status_code(), 401);
+
+        let res = request
+            .get(&format!("/api/episodes/{}/display", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["comment_counts"][part.id.to_string()], 2);
+
+        // The thread follows its words into the new part
+        let res = request
+            .post(&format!("{}/{}/split", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_id": second_word }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let split: Vec<parts::Model> = res.json();
+        let comments_url = format!("{}/{}/comments", parts_url, split[1].id);
+        let thread_url = format!("{}/{}", comments_url, thread["id"]);
+
+        let res = request
+            .get(&format!("/api/episodes/{}/comments", episode.id))
+            .add_header(other_key.clone(), other_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let open: serde_json::Value = res.json();
+        assert_eq!(open.as_array().unwrap().len(), 1);
+        assert_eq!(open[0]["comment"]["comment"]["part_id"], split[1].id);
+        assert_eq!(open[0]["comment"]["user_name"], admin.user.name);
+        assert_eq!(open[0]["replies"][0]["comment"]["id"], reply["id"]);
+
+        let res = request
+            .post(&format!("{}/{}/resolve", comments_url, reply["id"]))
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "resolved": true }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+        let res = request
+            .post(&format!("{}/resolve", thread_url))
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "resolved": true }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("/api/episodes/{}/comments", episode.id))
+            .add_header(other_key.clone(), other_value.clone())
+            .await;
+        let open: serde_json::Value = res.json();
+        assert_eq!(open.as_array().unwrap().len(), 0);
+        let res = request
+            .get(&format!(
+                "/api/episodes/{}/comments?resolved=true",
+                episode.id
+            ))
+            .add_header(other_key.clone(), other_value.clone())
+            .await;
+        let resolved: serde_json::Value = res.json();
+        assert_eq!(resolved.as_array().unwrap().len(), 1);
+
+        // Removing the thread takes the replies along
+        let res = request
+            .delete(&thread_url)
+            .add_header(other_key.clone(), other_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 401);
+        let res = request
+            .delete(&thread_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .get(&comments_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let threads: serde_json::Value = res.json();
+        assert_eq!(threads.as_array().unwrap().len(), 0);
+        let res = request
+            .get(&format!("/api/episodes/{}/display", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["comment_counts"], serde_json::json!({}));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn keeps_threads_with_their_words_when_editing() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let parts_url = format!("/api/episodes/{}/parts", episode.id);
+
+        let res = request
+            .get(&format!("{}/{}/display", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut edit: serde_json::Value = res.json();
+        let first_word = edit["sentences"][0]["words"][0]["id"].clone();
+        let second_word = edit["sentences"][0]["words"][1]["id"].clone();
+
+        let mut threads = vec![];
+        for (from_word_id, to_word_id) in [
+            (second_word.clone(), second_word.clone()),
+            (first_word.clone(), second_word.clone()),
+            (serde_json::Value::Null, serde_json::Value::Null),
+        ] {
+            let res = request
+                .post(&format!("{}/{}/comments", parts_url, part.id))
+                .add_header(auth_key.clone(), auth_value.clone())
+                .json(&serde_json::json!({
+                    "text": "Which world?",
+                    "from_word_id": from_word_id,
+                    "to_word_id": to_word_id,
+                }))
+                .await;
+            assert_eq!(res.status_code(), 200);
+            let thread: comments::Model = res.json();
+            threads.push(thread.id);
+        }
+        let find_thread = |id: i32| {
+            let db = ctx.db.clone();
+            async move {
+                comments::Entity::find_by_id(id)
+                    .one(&db)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+
+        // "world." moves into a new part, its thread goes along
+        let mut moved = edit["sentences"][0].clone();
+        moved["sentence"]["id"] = 0.into();
+        moved["words"] = serde_json::json!([edit["sentences"][0]["words"][1]]);
+        moved["move_sentence"] = "downnew".into();
+        edit["sentences"][0]["words"] = serde_json::json!([edit["sentences"][0]["words"][0]]);
+        edit["sentences"]
+            .as_array_mut()
+            .unwrap()
+            .push(moved.clone());
+        let res = request
+            .post(&format!("{}/{}/update", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let thread = find_thread(threads[0]).await;
+        assert_ne!(thread.part_id, part.id);
+        let new_part_id = thread.part_id;
+        let thread = find_thread(threads[1]).await;
+        assert_eq!(thread.part_id, part.id);
+        assert_eq!(thread.to_word_id, None);
+        assert_eq!(find_thread(threads[2]).await.part_id, part.id);
+
+        // Threads about deleted words are about the part
+        let res = request
+            .get(&format!("{}/{}/display", parts_url, new_part_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut other_edit: serde_json::Value = res.json();
+        other_edit["sentences"][0]["words"][0]["id"] = 0.into();
+        other_edit["sentences"][0]["words"][0]["text"] = "earth.".into();
+        let res = request
+            .post(&format!("{}/{}/update", parts_url, new_part_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&other_edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let thread = find_thread(threads[0]).await;
+        assert_eq!(thread.part_id, new_part_id);
+        assert_eq!((thread.from_word_id, thread.to_word_id), (None, None));
+
+        // Moving all sentences away keeps the threads of the part
+        let res = request
+            .get(&format!("{}/{}/display", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut edit: serde_json::Value = res.json();
+        edit["sentences"][0]["move_sentence"] = "down".into();
+        let res = request
+            .post(&format!("{}/{}/update", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert!(parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+        for id in threads {
+            assert_eq!(find_thread(id).await.part_id, new_part_id);
+        }
+    })
+    .await;
+}
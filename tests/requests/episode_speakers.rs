@@ -1,7 +1,11 @@
-use podscribe::app::App;
 use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{episode_speakers, parts, revisions, speakers};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
 use serial_test::serial;
 
+use super::prepare_data;
+
 #[tokio::test]
 #[serial]
 async fn can_get_episode_speakers() {
@@ -14,3 +18,128 @@ async fn can_get_episode_speakers() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn can_merge_episode_speakers() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+
+        let speaker = speakers::ActiveModel {
+            name: Set("Guest".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let guest = episode_speakers::ActiveModel {
+            episode_id: Set(episode.id),
+            speaker_id: Set(speaker.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        // Diarization gave the second word to another speaker
+        let res = request
+            .get(&format!(
+                "/api/episodes/{}/parts/{}/display",
+                episode.id, part.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        let res = request
+            .post(&format!(
+                "/api/episodes/{}/parts/{}/split",
+                episode.id, part.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "word_id": display["sentences"][0]["words"][1]["id"],
+                "episode_speaker_id": guest.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let split: Vec<parts::Model> = res.json();
+
+        let merge_url = format!(
+            "/api/episodes/{}/speakers/{}/merge",
+            episode.id, part.episode_speaker_id
+        );
+        let res = request
+            .post(&merge_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "episode_speaker_id": part.episode_speaker_id }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post(&merge_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "episode_speaker_id": guest.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        for item in &split {
+            let item = parts::Entity::find_by_id(item.id)
+                .one(&ctx.db)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(item.episode_speaker_id, part.episode_speaker_id);
+        }
+        assert!(episode_speakers::Entity::find_by_id(guest.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(revisions::Entity::find()
+            .filter(revisions::Column::Action.eq("merge_speakers"))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_some());
+
+        // The split can still be undone although its speaker is gone
+        let revision = revisions::Entity::find()
+            .filter(revisions::Column::Action.eq("split"))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let restore_url = format!(
+            "/api/episodes/{}/parts/{}/revisions/{}/restore",
+            episode.id, part.id, revision.id
+        );
+        let res = request
+            .post(&restore_url)
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert!(parts::Entity::find_by_id(split[1].id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Redoing it gives the removed speaker's part to the one it was merged into
+        let res = request
+            .post(&format!("{restore_url}?after=true"))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let redone = parts::Entity::find_by_id(split[1].id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redone.episode_speaker_id, part.episode_speaker_id);
+    })
+    .await;
+}
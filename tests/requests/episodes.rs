@@ -1,7 +1,13 @@
+use axum::extract::FromRequestParts;
+use axum::http::Request;
 use podscribe::app::App;
+use podscribe::common::check_auth;
 use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, IntoActiveModel};
 use serial_test::serial;
 
+use super::prepare_data;
+
 #[tokio::test]
 #[serial]
 async fn can_get_episodes() {
@@ -14,3 +20,68 @@ async fn can_get_episodes() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn can_cut_clip() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let mut episode = prepare_data::create_episode(&ctx).await.into_active_model();
+        episode.filename = Set("Über \"it\"\r\nX-Evil: 1".into());
+        let episode = episode.update(&ctx.db).await.unwrap();
+
+        let res = request
+            .post(&format!("/api/episodes/{}/audio", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .bytes(prepare_data::sine_wav().into())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!(
+                "/api/episodes/{}/clip?start=0.25&end=0.5&padding=0.1&fade=0.05",
+                episode.id
+            ))
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.header("content-type"), "audio/wav");
+        assert_eq!(
+            res.header("content-disposition"),
+            "attachment; filename=\"_ber _it___X-Evil_ 1-0.wav\"; \
+             filename*=UTF-8''%C3%9Cber%20%22it%22%0D%0AX-Evil%3A%201-0.wav"
+        );
+        // 0.45 seconds at 8 kHz, 16 bits each, plus the header
+        assert_eq!(res.as_bytes().len(), 44 + 2 * 3600);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn accepts_event_tokens_from_query_and_cookie() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let episode = prepare_data::create_episode(&ctx).await;
+        let events_url = format!("/api/episodes/{}/events", episode.id);
+
+        let res = request.get(&events_url).await;
+        assert_eq!(res.status_code(), 401);
+        let res = request.get(&format!("{events_url}?token=invalid")).await;
+        assert_eq!(res.status_code(), 401);
+
+        // The stream does not end, so the extractor is checked on its own
+        for request in [
+            Request::get(format!("{events_url}?token={}", user.token)),
+            Request::get(&events_url).header("cookie", format!("token={}", user.token)),
+        ] {
+            let (mut parts, ()) = request.body(()).unwrap().into_parts();
+            let auth = check_auth::EventStreamAuth::from_request_parts(&mut parts, &ctx)
+                .await
+                .unwrap();
+            assert_eq!(auth.user.id, user.user.id);
+        }
+    })
+    .await;
+}
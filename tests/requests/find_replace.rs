@@ -0,0 +1,289 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{parts, words};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_find_and_replace_words() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let (_, other_part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let contributor = prepare_data::create_user(&ctx, "contributor@example.com", 2).await;
+        let (other_key, other_value) = prepare_data::auth_header(&contributor.token);
+
+        let res = request
+            .post("/api/find_replace")
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({ "find": "world", "replace": "World" }))
+            .await;
+        assert_eq!(res.status_code(), 401);
+
+        let res = request
+            .post("/api/find_replace")
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({
+                "find": "world",
+                "replace": " ",
+                "episode_id": episode.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        // Punctuation is kept, nothing is stored yet
+        let res = request
+            .post("/api/find_replace")
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({
+                "find": "WORLD",
+                "replace": "World",
+                "episode_id": episode.id,
+                "preview": true,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["preview"], true);
+        let matches = result["matches"].as_array().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["part_id"], part.id);
+        assert_eq!(matches[0]["before"], "Hello world.");
+        assert_eq!(matches[0]["after"], "Hello World.");
+        let unchanged = parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.text, "Hello world.");
+
+        // Phrases are merged into fewer words
+        let res = request
+            .post("/api/find_replace")
+            .add_header(other_key.clone(), other_value.clone())
+            .json(&serde_json::json!({
+                "find": "hello world",
+                "replace": "Helloworld",
+                "episode_id": episode.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"][0]["after"], "Helloworld.");
+        let replaced = parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced.text, "Helloworld.");
+        assert_eq!(replaced.ends_at, 1.0);
+        let word_ids: Vec<i64> = result["matches"][0]["replacements"][0]["word_ids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_i64().unwrap())
+            .collect();
+        let merged = words::Entity::find_by_id(word_ids[0] as i32)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(merged.overwrite, "Helloworld.");
+        assert_eq!(merged.ends_at, 1.0);
+        assert!(words::Entity::find_by_id(word_ids[1] as i32)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+
+        let res = request
+            .get(&format!(
+                "/api/episodes/{}/parts/{}/revisions",
+                episode.id, part.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let revisions: serde_json::Value = res.json();
+        assert_eq!(revisions.as_array().unwrap().len(), 1);
+        assert_eq!(revisions[0]["revision"]["action"], "replace");
+        assert_eq!(revisions[0]["user_name"], contributor.user.name);
+
+        // Everywhere, but only in the picked sentences
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "Hello",
+                "replace": "Hi",
+                "case_sensitive": true,
+                "preview": true,
+            }))
+            .await;
+        let result: serde_json::Value = res.json();
+        let matches = result["matches"].as_array().unwrap();
+        assert!(matches.iter().all(|x| x["part_id"] != part.id));
+        let sentence_id = matches
+            .iter()
+            .find(|x| x["part_id"] == other_part.id)
+            .unwrap()["sentence_id"]
+            .clone();
+
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "Hello",
+                "replace": "Hi",
+                "case_sensitive": true,
+                "sentence_ids": [sentence_id],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"].as_array().unwrap().len(), 1);
+        let replaced = parts::Entity::find_by_id(other_part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced.text, "Hi world.");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn finds_words_with_non_ascii_letters() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let episode = prepare_data::create_episode(&ctx).await;
+        let res = request
+            .post(&format!("/api/episodes/{}", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "transcription": [{
+                    "start": 0.0,
+                    "end": 1.0,
+                    "speaker": "SPEAKER_00",
+                    "text": "Über snake_case.",
+                    "sentences": [{
+                        "text": "Über snake_case.",
+                        "start": 0.0,
+                        "end": 1.0,
+                        "words_per_second": 2.0,
+                        "words": [
+                            { "text": "Über", "start": 0.0, "end": 0.5, "probability": 0.9 },
+                            { "text": "snake_case.", "start": 0.5, "end": 1.0, "probability": 0.8 }
+                        ]
+                    }]
+                }]
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // SQLite does not lowercase "Ü"
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "über",
+                "replace": "Over",
+                "episode_id": episode.id,
+                "preview": true,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"].as_array().unwrap().len(), 1);
+        assert_eq!(result["matches"][0]["after"], "Over snake_case.");
+
+        // "_" is not a wildcard
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "snake_case",
+                "replace": "camelCase",
+                "episode_id": episode.id,
+                "preview": true,
+            }))
+            .await;
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"][0]["after"], "Über camelCase.");
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "snake_ase",
+                "replace": "camelCase",
+                "episode_id": episode.id,
+                "preview": true,
+            }))
+            .await;
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"].as_array().unwrap().len(), 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn replaces_in_more_parts_than_fit_into_one_query() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&admin.token);
+        let episode = prepare_data::create_episode(&ctx).await;
+        let transcription: Vec<serde_json::Value> = (0..600)
+            .map(|i| {
+                let start = f64::from(i);
+                serde_json::json!({
+                    "start": start,
+                    "end": start + 1.0,
+                    "speaker": "SPEAKER_00",
+                    "text": "Helo there.",
+                    "sentences": [{
+                        "text": "Helo there.",
+                        "start": start,
+                        "end": start + 1.0,
+                        "words_per_second": 2.0,
+                        "words": [
+                            { "text": "Helo", "start": start, "end": start + 0.5, "probability": 0.9 },
+                            { "text": "there.", "start": start + 0.5, "end": start + 1.0, "probability": 0.8 }
+                        ]
+                    }]
+                })
+            })
+            .collect();
+        let res = request
+            .post(&format!("/api/episodes/{}", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "transcription": transcription }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .post("/api/find_replace")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "find": "helo",
+                "replace": "Hello",
+                "episode_id": episode.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["matches"].as_array().unwrap().len(), 600);
+
+        let parts = parts::Entity::find().all(&ctx.db).await.unwrap();
+        assert_eq!(parts.len(), 600);
+        assert!(parts.iter().all(|x| x.text == "Hello there."));
+    })
+    .await;
+}
@@ -0,0 +1,141 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_suggest_glossary_terms() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "term": "Hallo", "misrecognitions": ["..."] }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "term": "Hallo", "misrecognitions": ["hullo"] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let hallo: serde_json::Value = res.json();
+        assert_eq!(hallo["misrecognitions"], serde_json::json!(["hullo"]));
+
+        let res = request
+            .put(&format!("/api/glossary/{}", hallo["term"]["id"]))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "term": "Hallo",
+                "note": "The host greets in German",
+                "misrecognitions": ["hello", "hullo", "hello"],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let hallo: serde_json::Value = res.json();
+        assert_eq!(
+            hallo["misrecognitions"],
+            serde_json::json!(["hello", "hullo"])
+        );
+
+        // The term itself is suggested when the case is off
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "term": "WORLD" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        // Terms of other podcasts do not apply
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "term": "Welt",
+                "podcast_id": 999_999,
+                "misrecognitions": ["world"],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let display: serde_json::Value = res.json();
+        let suggestions = display["suggestions"].as_array().unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0]["term"], "Hallo");
+        assert_eq!(
+            suggestions[0]["replacement"]["texts"],
+            serde_json::json!(["Hallo"])
+        );
+        assert_eq!(suggestions[1]["term"], "WORLD");
+        assert_eq!(
+            suggestions[1]["replacement"]["texts"],
+            serde_json::json!(["WORLD."])
+        );
+
+        let hello_id = suggestions[0]["replacement"]["word_ids"][0].clone();
+        let world_id = suggestions[1]["replacement"]["word_ids"][0].clone();
+        let res = request
+            .post(&format!("{}/replace_words", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "word_ids": [world_id, world_id],
+                "texts": ["WORLD."],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post(&format!("{}/replace_words", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&suggestions[1]["replacement"])
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let parts: serde_json::Value = res.json();
+        assert_eq!(parts[0]["text"], "Hello WORLD.");
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["suggestions"].as_array().unwrap().len(), 1);
+
+        let res = request
+            .get("/api/glossary/prompt")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.text(), "Hallo, WORLD");
+        let res = request
+            .get("/api/glossary/prompt?podcast_id=999999")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.text(), "Hallo, WORLD, Welt");
+
+        // Words are replaced in the order they are spoken
+        let res = request
+            .post(&format!("{}/replace_words", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "word_ids": [world_id, hello_id],
+                "texts": ["Hallo", "WORLD."],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let parts: serde_json::Value = res.json();
+        assert_eq!(parts[0]["text"], "Hallo WORLD.");
+    })
+    .await;
+}
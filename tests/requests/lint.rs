@@ -0,0 +1,129 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::common::hunspell;
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_lint_parts() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request.get(&format!("{}/lint", part_url)).await;
+        assert_eq!(res.status_code(), 401);
+
+        let res = request
+            .get(&format!("{}/lint", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.json::<serde_json::Value>(), serde_json::json!({}));
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        let first = display["sentences"][0]["words"][0]["id"].clone();
+        let second = display["sentences"][0]["words"][1]["id"].clone();
+
+        for (id, text) in [(&first, "hello"), (&second, "hello")] {
+            let res = request
+                .post(&format!("{}/replace_words", part_url))
+                .add_header(auth_key.clone(), auth_value.clone())
+                .json(&serde_json::json!({ "word_ids": [id], "texts": [text] }))
+                .await;
+            assert_eq!(res.status_code(), 200);
+        }
+        let res = request
+            .get(&format!("{}/lint", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let issues: serde_json::Value = res.json();
+        let kinds = |id: &serde_json::Value| -> Vec<String> {
+            issues[id.to_string()]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["kind"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(kinds(&first), ["lowercase_start"]);
+        assert_eq!(kinds(&second), ["doubled_word", "missing_punctuation"]);
+
+        let res = request
+            .post(&format!("{}/replace_words", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_ids": [first], "texts": ["Hello"] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post(&format!("{}/replace_words", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_ids": [second], "texts": ["Podscribe!"] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .get(&format!("{}/lint", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let issues: serde_json::Value = res.json();
+        assert_eq!(issues.as_object().unwrap().len(), 1);
+        assert_eq!(issues[second.to_string()][0]["kind"], "misspelling");
+
+        // Glossary terms are spelled right
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "term": "Podscribe" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .get(&format!("{}/lint", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.json::<serde_json::Value>(), serde_json::json!({}));
+    })
+    .await;
+}
+
+#[test]
+fn reads_dictionaries_in_their_encoding() {
+    let directory = std::env::temp_dir().join("podscribe-dictionary-test");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("de_DE");
+    std::fs::write(
+        path.with_extension("aff"),
+        "SET ISO8859-1\n\nSFX E Y 1\nSFX E   0     n          e\n",
+    )
+    .unwrap();
+    // "Grüße" in Latin-1
+    std::fs::write(path.with_extension("dic"), b"1\nGr\xfc\xdfe/E\n").unwrap();
+
+    let dictionary = hunspell::read(&path).unwrap();
+    assert!(dictionary.check("Grüße"));
+    assert!(dictionary.check("Grüßen"));
+    assert!(!dictionary.check("grüße"));
+    assert!(!dictionary.check("Grusse"));
+
+    std::fs::write(path.with_extension("aff"), "SET UNKNOWN-1\n").unwrap();
+    assert!(hunspell::read(&path).is_err());
+}
+
+#[test]
+fn checks_with_the_bundled_dictionary() {
+    let dictionary = hunspell::load(None).unwrap();
+    assert!(dictionary.check("Hello"));
+    assert!(dictionary.check("HELLO"));
+    assert!(dictionary.check("transcriptions"));
+    // Proper nouns keep their capital letter
+    assert!(dictionary.check("Paris"));
+    assert!(!dictionary.check("paris"));
+    assert!(!dictionary.check("Podscribe"));
+}
@@ -1,10 +1,20 @@
 mod auth;
-mod prepare_data;
+pub mod prepare_data;
 
+pub mod assignments;
+pub mod comments;
 pub mod episode_speakers;
 pub mod episodes;
+pub mod find_replace;
 pub mod frontend;
+pub mod glossary;
+pub mod lint;
 pub mod parts;
+pub mod podcasts;
+pub mod review_queue;
+pub mod revisions;
 pub mod sentences;
+pub mod speaker_mappings;
 pub mod speakers;
+pub mod transcription_jobs;
 pub mod words;
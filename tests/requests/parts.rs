@@ -1,7 +1,17 @@
 use podscribe::app::App;
+use podscribe::common::events::{self, EventKind};
+use podscribe::common::review::{self, PartStatus};
+use podscribe::common::settings::ApprovalSettings;
+use podscribe::models::_entities::{episode_speakers, parts, speakers, words};
+use podscribe::models::part_locks;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+};
 use loco_rs::testing::prelude::*;
 use serial_test::serial;
 
+use super::prepare_data;
+
 #[tokio::test]
 #[serial]
 async fn can_get_parts() {
@@ -14,3 +24,699 @@ async fn can_get_parts() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn rejects_update_of_outdated_part() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let loaded: serde_json::Value = res.json();
+
+        let mut first = loaded.clone();
+        first["sentences"][0]["words"][1]["overwrite"] = "there.".into();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&first)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // Based on the state before the first edit
+        let mut second = loaded.clone();
+        second["sentences"][0]["words"][0]["overwrite"] = "Hi".into();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&second)
+            .await;
+        assert_eq!(res.status_code(), 409);
+        let current: serde_json::Value = res.json();
+        assert_eq!(current["sentences"][0]["words"][0]["overwrite"], "");
+        assert_eq!(current["sentences"][0]["words"][1]["overwrite"], "there.");
+        assert_ne!(current["part"]["updated_at"], loaded["part"]["updated_at"]);
+
+        second["part"]["updated_at"] = current["part"]["updated_at"].clone();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&second)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // Without the state the edit is based on it cannot be checked
+        let mut unchecked = second.clone();
+        unchecked["part"]
+            .as_object_mut()
+            .unwrap()
+            .remove("updated_at");
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&unchecked)
+            .await;
+        assert_eq!(res.status_code(), 422);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_lock_parts() {
+    request::<App, _, _>(|request, ctx| async move {
+        let admin = prepare_data::init_admin_login(&request, &ctx).await;
+        let (admin_key, admin_value) = prepare_data::auth_header(&admin.token);
+        let contributor = prepare_data::create_user(&ctx, "contributor@loco.com", 2).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&contributor.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &admin.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .post(&format!("{}/lock", part_url))
+            .add_header(admin_key.clone(), admin_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let locks: Vec<serde_json::Value> = res.json();
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0]["user_id"], admin.user.id);
+
+        let res = request
+            .post(&format!("{}/lock", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 409);
+
+        // The lock of the admin is neither replaced nor removed
+        let now = chrono::Utc::now();
+        let held_by_others = part_locks::Entity::acquire(
+            &ctx.db,
+            vec![part.id],
+            contributor.user.id,
+            now.into(),
+            (now + chrono::Duration::seconds(60)).into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(held_by_others.len(), 1);
+        assert_eq!(held_by_others[0].user_id, admin.user.id);
+
+        let res = request
+            .get(&format!("/api/episodes/{}/display", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["locks"][0]["part_id"], part.id);
+        assert_eq!(display["locks"][0]["user_name"], admin.user.name);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut edit: serde_json::Value = res.json();
+        edit["sentences"][0]["words"][1]["overwrite"] = "there.".into();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 423);
+
+        // Contributors only release their own locks
+        let res = request
+            .delete(&format!("{}/lock", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .put(&format!("{}/lock", part_url))
+            .add_header(admin_key.clone(), admin_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .delete(&format!("{}/lock", part_url))
+            .add_header(admin_key.clone(), admin_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .put(&format!("{}/lock", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 409);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn publishes_episode_events() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let mut receiver = events::subscribe();
+        let res = request
+            .post(&format!("{}/approve", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post(&format!("{}/lock", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.episode_id, episode.id);
+        assert_eq!(
+            event.kind,
+            EventKind::ApprovalChanged {
+                part_id: part.id,
+                approvals: 1
+            }
+        );
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.kind.name(), "lock_acquired");
+        assert!(receiver.try_recv().is_err());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_insert_and_delete_words() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut edit: serde_json::Value = res.json();
+        // Without timing, squeezed in between its neighbours
+        edit["sentences"][0]["words"]
+            .as_array_mut()
+            .unwrap()
+            .insert(
+                1,
+                serde_json::json!({ "id": -1, "text": "big", "overwrite": "", "hidden": false }),
+            );
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut edit: serde_json::Value = res.json();
+        assert_eq!(edit["part"]["text"], "Hello big world.");
+        let words = edit["sentences"][0]["words"].as_array().unwrap();
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[1]["text"], "big");
+        assert_eq!(words[1]["starts_at"], 0.5);
+        assert_eq!(words[1]["ends_at"], 0.5);
+
+        // Overlapping "Hello"
+        let mut overlapping = edit.clone();
+        overlapping["sentences"][0]["words"]
+            .as_array_mut()
+            .unwrap()
+            .insert(
+                1,
+                serde_json::json!({
+                    "id": -1,
+                    "text": "very",
+                    "overwrite": "",
+                    "hidden": false,
+                    "starts_at": 0.2,
+                    "ends_at": 0.5,
+                }),
+            );
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&overlapping)
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        // Reaching past the end of the sentence
+        let mut outside = edit.clone();
+        outside["sentences"][0]["words"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({
+                "id": -1,
+                "text": "again",
+                "overwrite": "",
+                "hidden": false,
+                "starts_at": 1.0,
+                "ends_at": 1.5,
+            }));
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&outside)
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        edit["sentences"][0]["words"].as_array_mut().unwrap().remove(0);
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&edit)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["part"]["text"], "big world.");
+        assert_eq!(display["part"]["starts_at"], 0.5);
+        assert_eq!(display["sentences"][0]["words"].as_array().unwrap().len(), 2);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_split_merge_and_reassign_parts() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let parts_url = format!("/api/episodes/{}/parts", episode.id);
+
+        let res = request
+            .get(&format!("{}/{}/display", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        let first_word = display["sentences"][0]["words"][0]["id"].clone();
+        let second_word = display["sentences"][0]["words"][1]["id"].clone();
+
+        let res = request
+            .post(&format!("{}/{}/split", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_id": first_word }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+
+        let res = request
+            .post(&format!("{}/{}/split", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_id": second_word }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let parts: Vec<parts::Model> = res.json();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].id, part.id);
+        assert_eq!(parts[0].text, "Hello");
+        assert_eq!(parts[0].ends_at, 0.5);
+        assert_eq!(parts[1].text, "world.");
+        assert_eq!(parts[1].starts_at, 0.5);
+
+        let res = request
+            .post(&format!("{}/{}/merge", parts_url, parts[1].id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "part_id": part.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let merged: Vec<parts::Model> = res.json();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].id, part.id);
+        assert_eq!(merged[0].text, "Hello world.");
+        assert_eq!(merged[0].ends_at, 1.0);
+
+        let speaker = speakers::ActiveModel {
+            name: Set("Guest".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let guest = episode_speakers::ActiveModel {
+            episode_id: Set(episode.id),
+            speaker_id: Set(speaker.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let res = request
+            .post(&format!("{}/reassign", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "starts_at": 0.4,
+                "ends_at": 2.0,
+                "episode_speaker_id": guest.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        let parts: Vec<parts::Model> = serde_json::from_value(result["after"].clone()).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].text, "Hello");
+        assert_eq!(parts[0].episode_speaker_id, part.episode_speaker_id);
+        assert_eq!(parts[1].text, "world.");
+        assert_eq!(parts[1].episode_speaker_id, guest.id);
+
+        let res = request
+            .get(&format!("{}/{}/revisions", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let revisions: Vec<serde_json::Value> = res.json();
+        assert_eq!(revisions[0]["revision"]["action"], "reassign");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_preview_speaker_reassignment() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let parts_url = format!("/api/episodes/{}/parts", episode.id);
+
+        let speaker = speakers::ActiveModel {
+            name: Set("Guest".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let guest = episode_speakers::ActiveModel {
+            episode_id: Set(episode.id),
+            speaker_id: Set(speaker.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        let res = request
+            .post(&format!("{}/reassign", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "starts_at": 0.4,
+                "episode_speaker_id": guest.id,
+                "preview": true,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["preview"], true);
+        assert_eq!(result["before"].as_array().unwrap().len(), 1);
+        assert_eq!(result["after"][0]["text"], "Hello");
+        assert_eq!(result["after"][1]["text"], "world.");
+        assert_eq!(result["after"][1]["episode_speaker_id"], guest.id);
+
+        // Nothing changed
+        let count = parts::Entity::find()
+            .filter(parts::Column::EpisodeId.eq(episode.id))
+            .count(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Everything the speaker of the imported part said
+        let res = request
+            .post(&format!("{}/reassign", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "from_episode_speaker_id": part.episode_speaker_id,
+                "episode_speaker_id": guest.id,
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["preview"], false);
+        assert_eq!(result["after"].as_array().unwrap().len(), 1);
+        assert_eq!(result["after"][0]["text"], "Hello world.");
+        assert_eq!(result["after"][0]["episode_speaker_id"], guest.id);
+
+        let res = request
+            .post(&format!("{}/reassign", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "episode_speaker_id": guest.id }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_retime_episodes() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let parts_url = format!("/api/episodes/{}/parts", episode.id);
+
+        let res = request
+            .post(&format!("{}/retime", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "scale": 2.0, "offset": 1.0, "preview": true }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["parts"][0]["starts_at"], 1.0);
+        assert_eq!(result["parts"][0]["ends_at"], 3.0);
+        let unchanged = parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.ends_at, 1.0);
+
+        for params in [
+            serde_json::json!({ "offset": -1.0 }),
+            serde_json::json!({ "scale": 0.0 }),
+            serde_json::json!({ "sync_points": [
+                { "before": 1.0, "after": 2.0 },
+                { "before": 1.0, "after": 3.0 },
+            ] }),
+        ] {
+            let res = request
+                .post(&format!("{}/retime", parts_url))
+                .add_header(auth_key.clone(), auth_value.clone())
+                .json(&params)
+                .await;
+            assert_eq!(res.status_code(), 400);
+        }
+
+        // Only the parts after the new intro move
+        let second_word = words::Entity::find()
+            .filter(words::Column::Text.eq("world."))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        let res = request
+            .post(&format!("{}/{}/split", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "word_id": second_word.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post(&format!("{}/retime", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "offset": 0.5, "from": 0.5 }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["parts"].as_array().unwrap().len(), 1);
+        assert_eq!(result["parts"][0]["starts_at"], 1.0);
+        assert_eq!(result["parts"][0]["ends_at"], 1.5);
+
+        // Cutting the intro again may not move parts into the ones before
+        let res = request
+            .post(&format!("{}/retime", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "offset": -0.75, "from": 1.0 }))
+            .await;
+        assert_eq!(res.status_code(), 400);
+        let res = request
+            .post(&format!("{}/retime", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "offset": -0.5, "from": 1.0 }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["parts"][0]["starts_at"], 0.5);
+        assert_eq!(result["parts"][0]["ends_at"], 1.0);
+
+        let res = request
+            .post(&format!("{}/retime", parts_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "sync_points": [
+                { "before": 0.5, "after": 1.5 },
+                { "before": 1.5, "after": 3.5 },
+            ] }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["retiming"]["scale"], 2.0);
+        assert_eq!(result["retiming"]["offset"], 0.5);
+        assert_eq!(result["parts"][0]["starts_at"], 0.5);
+        assert_eq!(result["parts"][1]["ends_at"], 2.5);
+        let times: Vec<(f64, f64)> = words::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.starts_at, x.ends_at))
+            .collect();
+        assert_eq!(times, [(0.5, 1.5), (1.5, 2.5)]);
+
+        let res = request
+            .get(&format!("{}/{}/revisions", parts_url, part.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let revisions: Vec<serde_json::Value> = res.json();
+        assert_eq!(revisions[0]["revision"]["action"], "retime");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_approve_and_withdraw_approvals() {
+    request::<App, _, _>(|request, ctx| async move {
+        let editor = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&editor.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &editor.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut display: serde_json::Value = res.json();
+        assert_eq!(display["status"], "untouched");
+
+        display["sentences"][0]["words"][1]["overwrite"] = "there.".into();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&display)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // By default the approval of the editor is enough
+        let res = request
+            .get(&format!("/api/episodes/{}/progress", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let progress: serde_json::Value = res.json();
+        assert_eq!(progress["approved"], 1);
+        assert_eq!(progress["completion"], 100.0);
+
+        let res = request
+            .delete(&format!("{}/approve", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["approvals"], 0);
+        assert_eq!(result["status"], "edited");
+
+        let contributor = prepare_data::create_user(&ctx, "contributor@example.com", 2).await;
+        let (contributor_key, contributor_value) = prepare_data::auth_header(&contributor.token);
+        let res = request
+            .post(&format!("{}/approve", part_url))
+            .add_header(contributor_key, contributor_value)
+            .await;
+        let result: serde_json::Value = res.json();
+        assert_eq!(result["approvals"], 1);
+        assert_eq!(result["status"], "approved");
+
+        let res = request
+            .post(&format!("{}/approve", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // Two approvals, one of them by a reviewer, and the editor does not count
+        let strict = ApprovalSettings {
+            required: 2,
+            count_editor: false,
+            reviewer_required: true,
+            reviewer_role: 3,
+        };
+        let statuses = review::statuses(&ctx.db, &strict, &[part.id]).await.unwrap();
+        assert_eq!(statuses[&part.id], PartStatus::InReview);
+
+        let reviewer = prepare_data::create_user(&ctx, "reviewer@example.com", 3).await;
+        let (reviewer_key, reviewer_value) = prepare_data::auth_header(&reviewer.token);
+        let res = request
+            .post(&format!("{}/approve", part_url))
+            .add_header(reviewer_key, reviewer_value)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let statuses = review::statuses(&ctx.db, &strict, &[part.id]).await.unwrap();
+        assert_eq!(statuses[&part.id], PartStatus::Approved);
+
+        // Only parts of the episode, and only existing ones
+        let other_episode = prepare_data::create_episode(&ctx).await;
+        let wrong_urls = [
+            format!(
+                "/api/episodes/{}/parts/{}/approve",
+                other_episode.id, part.id
+            ),
+            format!(
+                "/api/episodes/{}/parts/{}/approve",
+                episode.id,
+                part.id + 1000
+            ),
+        ];
+        for url in &wrong_urls {
+            let res = request
+                .post(url)
+                .add_header(auth_key.clone(), auth_value.clone())
+                .await;
+            assert_eq!(res.status_code(), 404);
+            let res = request
+                .delete(url)
+                .add_header(auth_key.clone(), auth_value.clone())
+                .await;
+            assert_eq!(res.status_code(), 404);
+        }
+    })
+    .await;
+}
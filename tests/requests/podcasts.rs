@@ -0,0 +1,179 @@
+use loco_rs::testing::prelude::*;
+use podscribe::{
+    app::App,
+    models::{_entities::glossary_terms, episode_speakers, podcasts, speakers},
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter,
+};
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_scope_episodes_and_speakers_by_podcast() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let res = request
+            .post("/api/podcasts")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "title": "Podcast",
+                "artwork_url": "https://example.com/cover.jpg",
+                "language": "de",
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let podcast: podcasts::Model = res.json();
+
+        let episode = prepare_data::create_episode(&ctx).await;
+        let mut item = episode.into_active_model();
+        item.podcast_id = Set(Some(podcast.id));
+        let episode = item.update(&ctx.db).await.unwrap();
+        prepare_data::create_episode(&ctx).await;
+
+        let mut speaker_ids = vec![];
+        for name in ["Guest", "Host"] {
+            let speaker = speakers::ActiveModel {
+                name: Set(name.into()),
+                ..Default::default()
+            }
+            .insert(&ctx.db)
+            .await
+            .unwrap();
+            speaker_ids.push(speaker.id);
+        }
+        episode_speakers::ActiveModel {
+            episode_id: Set(episode.id),
+            speaker_id: Set(speaker_ids[1]),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        let res = request
+            .get(&format!("/api/podcasts/{}/episodes", podcast.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let episodes: Vec<serde_json::Value> = res.json();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0]["id"], episode.id);
+
+        let res = request
+            .get(&format!("/api/speakers?podcast_id={}", podcast.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let listed: Vec<speakers::Model> = res.json();
+        assert_eq!(
+            listed.iter().map(|x| x.id).collect::<Vec<_>>(),
+            vec![speaker_ids[1]]
+        );
+
+        let res = request
+            .post("/api/glossary")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "term": "Podcastwort",
+                "podcast_id": podcast.id,
+                "misrecognitions": ["podcast word"],
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .delete(&format!("/api/podcasts/{}", podcast.id))
+            .add_header(auth_key, auth_value)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // Terms of the podcast go with it
+        let remaining = glossary_terms::Entity::find()
+            .filter(glossary_terms::Column::PodcastId.eq(podcast.id))
+            .count(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_search_episodes_of_a_podcast() {
+    request::<App, _, _>(|request, ctx| async move {
+        prepare_data::reset_search_index(&ctx).await;
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let res = request
+            .post("/api/podcasts")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({
+                "title": "Podcast",
+                "artwork_url": "https://example.com/cover.jpg",
+                "language": "en",
+            }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let podcast: podcasts::Model = res.json();
+
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let mut item = episode.into_active_model();
+        item.podcast_id = Set(Some(podcast.id));
+        item.update(&ctx.db).await.unwrap();
+        let (other_episode, other_part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+
+        let res = request
+            .get("/api/episodes/search?query=hello")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        let mut part_ids: Vec<i64> = result["parts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["id"].as_i64().unwrap())
+            .collect();
+        part_ids.sort_unstable();
+        assert_eq!(part_ids, [i64::from(part.id), i64::from(other_part.id)]);
+
+        // Parts of other podcasts are left out
+        let res = request
+            .get(&format!(
+                "/api/episodes/search?query=hello&podcast_id={}",
+                podcast.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let result: serde_json::Value = res.json();
+        let parts = result["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0]["id"], part.id);
+        let episodes = result["episodes"].as_array().unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_ne!(episodes[0]["id"], other_episode.id);
+
+        // A podcast without episodes has no results
+        let res = request
+            .get(&format!(
+                "/api/episodes/search?query=hello&podcast_id={}",
+                podcast.id + 1
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let result: serde_json::Value = res.json();
+        assert!(result["parts"].as_array().unwrap().is_empty());
+    })
+    .await;
+}
@@ -1,6 +1,11 @@
 use axum::http::{HeaderName, HeaderValue};
-use loco_rs::{app::AppContext, TestServer};
-use podscribe::{models::users, views::auth::LoginResponse};
+use loco_rs::{app::AppContext, prelude::*, TestServer};
+use podscribe::{
+    common::audio,
+    initializers::tantivy_search,
+    models::{_entities::parts, episodes, users},
+    views::auth::LoginResponse,
+};
 
 const USER_EMAIL: &str = "test@loco.com";
 const USER_PASSWORD: &str = "1234";
@@ -55,3 +60,110 @@ pub fn auth_header(token: &str) -> (HeaderName, HeaderValue) {
 
     (HeaderName::from_static("authorization"), auth_header_value)
 }
+
+pub async fn init_admin_login(request: &TestServer, ctx: &AppContext) -> LoggedInUser {
+    let logged_in = init_user_login(request, ctx).await;
+
+    let mut user = logged_in.user.into_active_model();
+    user.role = Set(3);
+    let user = user.update(&ctx.db).await.unwrap();
+
+    LoggedInUser {
+        user,
+        token: logged_in.token,
+    }
+}
+
+/// A second user next to the one from `init_user_login`
+pub async fn create_user(ctx: &AppContext, email: &str, role: i32) -> LoggedInUser {
+    let user = users::Model::create_with_password(
+        &ctx.db,
+        &users::RegisterParams {
+            email: email.into(),
+            password: USER_PASSWORD.into(),
+            name: email.into(),
+        },
+    )
+    .await
+    .unwrap();
+    let mut user = user.into_active_model();
+    user.role = Set(role);
+    let user = user.update(&ctx.db).await.unwrap();
+
+    let jwt = ctx.config.get_jwt_config().unwrap();
+    let token = user.generate_jwt(&jwt.secret, &jwt.expiration).unwrap();
+    LoggedInUser { user, token }
+}
+
+/// The search index outlives the test database, this drops the parts of
+/// earlier tests from it
+pub async fn reset_search_index(ctx: &AppContext) {
+    tantivy_search::container(ctx)
+        .unwrap()
+        .rebuild(&ctx.db)
+        .await
+        .unwrap();
+}
+
+pub async fn create_episode(ctx: &AppContext) -> episodes::Model {
+    episodes::ActiveModel {
+        title: Set("Episode".into()),
+        link: Set("https://example.com/episode".into()),
+        description: Set("".into()),
+        filename: Set("episode".into()),
+        has_audio_file: Set(false),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+/// Creates an episode with a single part "Hello world." through the import
+pub async fn create_transcribed_episode(
+    request: &TestServer,
+    ctx: &AppContext,
+    token: &str,
+) -> (episodes::Model, parts::Model) {
+    let episode = create_episode(ctx).await;
+    let (auth_key, auth_value) = auth_header(token);
+    let res = request
+        .post(&format!("/api/episodes/{}", episode.id))
+        .add_header(auth_key, auth_value)
+        .json(&serde_json::json!({
+            "transcription": [{
+                "start": 0.0,
+                "end": 1.0,
+                "speaker": "SPEAKER_00",
+                "text": "Hello world.",
+                "sentences": [{
+                    "text": "Hello world.",
+                    "start": 0.0,
+                    "end": 1.0,
+                    "words_per_second": 2.0,
+                    "words": [
+                        { "text": "Hello", "start": 0.0, "end": 0.5, "probability": 0.9 },
+                        { "text": "world.", "start": 0.5, "end": 1.0, "probability": 0.8 }
+                    ]
+                }]
+            }]
+        }))
+        .await;
+    assert_eq!(res.status_code(), 200);
+
+    let part = parts::Entity::find()
+        .filter(parts::Column::EpisodeId.eq(episode.id))
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    (episode, part)
+}
+
+/// One second of a sine wave at 8 kHz
+pub fn sine_wav() -> Vec<u8> {
+    let samples: Vec<f32> = (0..8000u16)
+        .map(|i| 0.5 * (f32::from(i) / 8000.0 * 440.0 * std::f32::consts::TAU).sin())
+        .collect();
+    audio::encode_wav(8000, &samples)
+}
@@ -0,0 +1,74 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{sentences, words};
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn ranks_parts_by_low_probability_words() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (_, part) = prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let (other_episode, other_part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+
+        // Nothing of the other part can be trusted
+        let sentence = sentences::Entity::find()
+            .filter(sentences::Column::PartId.eq(other_part.id))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        words::Entity::update_many()
+            .col_expr(words::Column::Probability, Expr::value(0.1))
+            .filter(words::Column::SentenceId.eq(sentence.id))
+            .exec(&ctx.db)
+            .await
+            .unwrap();
+
+        let res = request
+            .get("/api/review_queue?threshold=0.85")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let queue: serde_json::Value = res.json();
+        let queue = queue.as_array().unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0]["part"]["id"], other_part.id);
+        assert_eq!(queue[0]["share"], 1.0);
+        assert_eq!(queue[1]["part"]["id"], part.id);
+        assert_eq!(queue[1]["low_probability_words"], 1);
+        assert_eq!(queue[1]["share"], 0.5);
+
+        // Approved parts are done
+        let res = request
+            .post(&format!(
+                "/api/episodes/{}/parts/{}/approve",
+                other_episode.id, other_part.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .get("/api/review_queue?threshold=0.85")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let queue: serde_json::Value = res.json();
+        assert_eq!(queue.as_array().unwrap().len(), 1);
+
+        let res = request
+            .get(&format!(
+                "/api/review_queue?episode_id={}",
+                other_episode.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let queue: serde_json::Value = res.json();
+        assert!(queue.as_array().unwrap().is_empty());
+    })
+    .await;
+}
@@ -0,0 +1,175 @@
+use loco_rs::testing::prelude::*;
+use podscribe::{
+    app::App,
+    models::_entities::{parts, words},
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serial_test::serial;
+
+use super::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_list_and_restore_revisions() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        // The editor sends back what it loaded from the display endpoint
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut display: serde_json::Value = res.json();
+        display["sentences"][0]["words"][1]["overwrite"] = "there.".into();
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&display)
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("{}/revisions", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let revisions: Vec<serde_json::Value> = res.json();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0]["revision"]["action"], "edit");
+        assert_eq!(revisions[0]["user_name"], user.user.name);
+        let revision_id = revisions[0]["revision"]["id"].as_i64().unwrap();
+
+        let res = request
+            .get(&format!("{}/revisions/{}", part_url, revision_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let revision: serde_json::Value = res.json();
+        let word_diffs = revision["parts"][0]["words"].as_array().unwrap();
+        assert_eq!(word_diffs.len(), 1);
+        assert_eq!(word_diffs[0]["before"]["overwrite"], "");
+        assert_eq!(word_diffs[0]["after"]["overwrite"], "there.");
+
+        // Not while somebody else holds the lock
+        let contributor = prepare_data::create_user(&ctx, "contributor@example.com", 2).await;
+        let (other_key, other_value) = prepare_data::auth_header(&contributor.token);
+        let res = request
+            .post(&format!("{}/lock", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let res = request
+            .post(&format!("{}/revisions/{}/restore", part_url, revision_id))
+            .add_header(other_key.clone(), other_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 423);
+
+        let res = request
+            .post(&format!("{}/revisions/{}/restore", part_url, revision_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(display["sentences"][0]["words"][1]["overwrite"], "");
+        let part = parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(part.text, "Hello world.");
+
+        let res = request
+            .get(&format!("{}/revisions", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let revisions: Vec<serde_json::Value> = res.json();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0]["revision"]["action"], "restore");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn restoring_removes_inserted_words() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let part_url = format!("/api/episodes/{}/parts/{}", episode.id, part.id);
+
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let mut display: serde_json::Value = res.json();
+        display["sentences"][0]["words"]
+            .as_array_mut()
+            .unwrap()
+            .insert(
+                1,
+                serde_json::json!({
+                    "id": 0,
+                    "text": "wide",
+                    "overwrite": "",
+                    "hidden": false,
+                }),
+            );
+        let res = request
+            .post(&format!("{}/update", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&display)
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let inserted = words::Entity::find()
+            .filter(words::Column::Text.eq("wide"))
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let res = request
+            .get(&format!("{}/revisions", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let revisions: Vec<serde_json::Value> = res.json();
+        let revision_id = revisions[0]["revision"]["id"].as_i64().unwrap();
+        let res = request
+            .post(&format!("{}/revisions/{}/restore", part_url, revision_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        assert!(words::Entity::find_by_id(inserted.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+        let res = request
+            .get(&format!("{}/display", part_url))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        let display: serde_json::Value = res.json();
+        assert_eq!(
+            display["sentences"][0]["words"].as_array().unwrap().len(),
+            2
+        );
+        let part = parts::Entity::find_by_id(part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(part.text, "Hello world.");
+    })
+    .await;
+}
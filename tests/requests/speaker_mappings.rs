@@ -0,0 +1,108 @@
+use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{episode_speakers, episodes, parts, speakers};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter,
+};
+use serial_test::serial;
+
+use super::prepare_data;
+
+fn transcription(speakers: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "transcription": [{
+            "start": 0.0,
+            "end": 1.0,
+            "speaker": "SPEAKER_00",
+            "text": "Hello world.",
+            "sentences": [{
+                "text": "Hello world.",
+                "start": 0.0,
+                "end": 1.0,
+                "words_per_second": 2.0,
+                "words": [
+                    { "text": "Hello", "start": 0.0, "end": 0.5, "probability": 0.9 },
+                    { "text": "world.", "start": 0.5, "end": 1.0, "probability": 0.8 }
+                ]
+            }]
+        }],
+        "speakers": speakers,
+    })
+}
+
+#[tokio::test]
+#[serial]
+async fn imports_speakers_by_alias_and_mapping() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+
+        let res = request
+            .post("/api/podcasts")
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "title": "Show" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let podcast: serde_json::Value = res.json();
+
+        let host = speakers::ActiveModel {
+            name: Set("Host A".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let res = request
+            .post(&format!("/api/speakers/{}/aliases", host.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "name": "Anna" }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let mut episode_ids = vec![];
+        for speakers in [
+            serde_json::json!({ "SPEAKER_00": "Anna" }),
+            serde_json::json!({}),
+        ] {
+            let mut episode = prepare_data::create_episode(&ctx).await.into_active_model();
+            episode.podcast_id = Set(podcast["id"].as_i64().map(|x| x as i32));
+            let episode: episodes::Model = episode.update(&ctx.db).await.unwrap();
+            let res = request
+                .post(&format!("/api/episodes/{}", episode.id))
+                .add_header(auth_key.clone(), auth_value.clone())
+                .json(&transcription(speakers))
+                .await;
+            assert_eq!(res.status_code(), 200);
+            episode_ids.push(episode.id);
+        }
+
+        // The second import remembered the mapping of the first one
+        for episode_id in episode_ids {
+            let part = parts::Entity::find()
+                .filter(parts::Column::EpisodeId.eq(episode_id))
+                .one(&ctx.db)
+                .await
+                .unwrap()
+                .unwrap();
+            let episode_speaker = episode_speakers::Entity::find_by_id(part.episode_speaker_id)
+                .one(&ctx.db)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(episode_speaker.speaker_id, host.id);
+        }
+        assert_eq!(speakers::Entity::find().count(&ctx.db).await.unwrap(), 1);
+
+        let res = request
+            .get(&format!("/api/podcasts/{}/speaker_mappings", podcast["id"]))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let mappings: serde_json::Value = res.json();
+        assert_eq!(mappings.as_array().unwrap().len(), 1);
+        assert_eq!(mappings[0]["label"], "SPEAKER_00");
+        assert_eq!(mappings[0]["speaker_id"], host.id);
+    })
+    .await;
+}
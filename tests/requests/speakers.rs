@@ -1,7 +1,11 @@
-use podscribe::app::App;
 use loco_rs::testing::prelude::*;
+use podscribe::app::App;
+use podscribe::models::_entities::{episode_speakers, parts, speakers};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
 use serial_test::serial;
 
+use super::prepare_data;
+
 #[tokio::test]
 #[serial]
 async fn can_get_speakers() {
@@ -14,3 +18,151 @@ async fn can_get_speakers() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn can_merge_speakers() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (first_episode, first_part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let (second_episode, second_part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let duplicate = episode_speakers::Entity::find_by_id(first_part.episode_speaker_id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap()
+            .speaker_id;
+
+        // Jane already was assigned to the first episode, but not the second
+        let jane = speakers::ActiveModel {
+            name: Set("Jane".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let jane_in_first = episode_speakers::ActiveModel {
+            episode_id: Set(first_episode.id),
+            speaker_id: Set(jane.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        let res = request
+            .post(&format!("/api/speakers/{}/merge", jane.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "speaker_id": duplicate }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        assert!(speakers::Entity::find_by_id(duplicate)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .is_none());
+        let first_part = parts::Entity::find_by_id(first_part.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first_part.episode_speaker_id, jane_in_first.id);
+        let first_speakers = episode_speakers::Entity::find()
+            .filter(episode_speakers::Column::EpisodeId.eq(first_episode.id))
+            .all(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(first_speakers.len(), 1);
+
+        // Nothing to merge in the second episode, its speaker is just renamed
+        let second_speaker = episode_speakers::Entity::find_by_id(second_part.episode_speaker_id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second_speaker.episode_id, second_episode.id);
+        assert_eq!(second_speaker.speaker_id, jane.id);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_get_speaker_stats() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let (first_episode, part) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let (second_episode, _) =
+            prepare_data::create_transcribed_episode(&request, &ctx, &user.token).await;
+        let host = episode_speakers::Entity::find_by_id(part.episode_speaker_id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // A guest says the second word of the first episode
+        let guest = speakers::ActiveModel {
+            name: Set("Guest".into()),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let guest = episode_speakers::ActiveModel {
+            episode_id: Set(first_episode.id),
+            speaker_id: Set(guest.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        let res = request
+            .post(&format!(
+                "/api/episodes/{}/parts/reassign",
+                first_episode.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .json(&serde_json::json!({ "starts_at": 0.5, "episode_speaker_id": guest.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        let res = request
+            .get(&format!("/api/speakers/{}/stats", host.speaker_id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let stats: serde_json::Value = res.json();
+        assert_eq!(stats["talk_time"], 1.5);
+        assert_eq!(stats["word_count"], 3);
+        assert!(stats["words_per_second"].is_number());
+        assert_eq!(stats["episode_count"], 2);
+        assert_eq!(stats["first_appearance"]["id"], first_episode.id);
+        assert_eq!(stats["last_appearance"]["id"], second_episode.id);
+        let episodes = stats["episodes"].as_array().unwrap();
+        assert_eq!(episodes.len(), 2);
+        let first = episodes
+            .iter()
+            .find(|x| x["episode_id"] == first_episode.id)
+            .unwrap();
+        assert_eq!(first["share"], 0.5);
+
+        let res = request
+            .get(&format!(
+                "/api/episodes/{}/speakers/stats",
+                first_episode.id
+            ))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let stats: serde_json::Value = res.json();
+        assert_eq!(stats["talk_time"], 1.0);
+        assert_eq!(stats["speakers"].as_array().unwrap().len(), 2);
+    })
+    .await;
+}
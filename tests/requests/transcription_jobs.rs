@@ -0,0 +1,175 @@
+use loco_rs::testing::prelude::*;
+use podscribe::{
+    app::App,
+    common::check_auth::ROLE_WORKER,
+    models::{_entities::parts, transcription_jobs},
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter,
+};
+use serial_test::serial;
+
+use super::prepare_data;
+
+fn transcription() -> serde_json::Value {
+    serde_json::json!({
+        "transcription": [{
+            "start": 0.0,
+            "end": 1.0,
+            "speaker": "SPEAKER_00",
+            "text": "Hello world.",
+            "sentences": [{
+                "text": "Hello world.",
+                "start": 0.0,
+                "end": 1.0,
+                "words_per_second": 2.0,
+                "words": [
+                    { "text": "Hello", "start": 0.0, "end": 0.5, "probability": 0.9 },
+                    { "text": "world.", "start": 0.5, "end": 1.0, "probability": 0.8 }
+                ]
+            }]
+        }]
+    })
+}
+
+#[tokio::test]
+#[serial]
+async fn can_lease_and_complete_job() {
+    request::<App, _, _>(|request, ctx| async move {
+        let user = prepare_data::init_admin_login(&request, &ctx).await;
+        let (auth_key, auth_value) = prepare_data::auth_header(&user.token);
+        let worker = prepare_data::create_user(&ctx, "worker@example.com", ROLE_WORKER).await;
+        let (worker_key, worker_value) = prepare_data::auth_header(&worker.user.api_key);
+
+        let episode = prepare_data::create_episode(&ctx).await;
+        request
+            .post(&format!("/api/episodes/{}/audio", episode.id))
+            .add_header(auth_key.clone(), auth_value.clone())
+            .bytes(prepare_data::sine_wav().into())
+            .await;
+
+        let res = request
+            .post("/api/transcription_jobs")
+            .add_header(auth_key, auth_value)
+            .json(&serde_json::json!({ "episode_id": episode.id }))
+            .await;
+        assert_eq!(res.status_code(), 200);
+
+        // Only worker accounts take jobs, not even admins
+        let (admin_key, admin_value) = prepare_data::auth_header(&user.user.api_key);
+        let res = request
+            .post("/api/transcription_jobs/claim")
+            .add_header(admin_key, admin_value)
+            .await;
+        assert_eq!(res.status_code(), 401);
+
+        let res = request
+            .post("/api/transcription_jobs/claim")
+            .add_header(worker_key.clone(), worker_value.clone())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let job: transcription_jobs::Model = res.json();
+        assert_eq!(job.status, transcription_jobs::STATUS_LEASED);
+        assert_eq!(job.attempts, 1);
+
+        // Nothing left for a second worker
+        let res = request
+            .post("/api/transcription_jobs/claim")
+            .add_header(worker_key.clone(), worker_value.clone())
+            .await;
+        assert_eq!(res.text(), "null");
+
+        let res = request
+            .get(&format!("/api/transcription_jobs/{}/audio", job.id))
+            .add_header(worker_key.clone(), worker_value.clone())
+            .await;
+        assert_eq!(res.as_bytes().len(), prepare_data::sine_wav().len());
+
+        let res = request
+            .post(&format!("/api/transcription_jobs/{}/heartbeat", job.id))
+            .add_header(worker_key.clone(), worker_value.clone())
+            .json(&serde_json::json!({ "progress": 0.5 }))
+            .await;
+        let heartbeat: transcription_jobs::Model = res.json();
+        assert!((heartbeat.progress - 0.5).abs() < f64::EPSILON);
+
+        let res = request
+            .post(&format!("/api/transcription_jobs/{}/complete", job.id))
+            .add_header(worker_key.clone(), worker_value.clone())
+            .json(&transcription())
+            .await;
+        assert_eq!(res.status_code(), 200);
+        let job: transcription_jobs::Model = res.json();
+        assert_eq!(job.status, transcription_jobs::STATUS_DONE);
+
+        let parts = parts::Entity::find()
+            .filter(parts::Column::EpisodeId.eq(episode.id))
+            .all(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(parts.len(), 1);
+
+        // The lease ended with the result
+        let res = request
+            .post(&format!("/api/transcription_jobs/{}/heartbeat", job.id))
+            .add_header(worker_key, worker_value)
+            .json(&serde_json::json!({}))
+            .await;
+        assert_eq!(res.status_code(), 409);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn requeues_expired_leases() {
+    request::<App, _, _>(|request, ctx| async move {
+        let worker = prepare_data::create_user(&ctx, "worker@example.com", ROLE_WORKER).await;
+        let (worker_key, worker_value) = prepare_data::auth_header(&worker.user.api_key);
+        let episode = prepare_data::create_episode(&ctx).await;
+
+        let job = transcription_jobs::ActiveModel {
+            episode_id: Set(episode.id),
+            status: Set(transcription_jobs::STATUS_LEASED.into()),
+            attempts: Set(1),
+            progress: Set(0.3),
+            worker_id: Set(Some(0)),
+            lease_expires_at: Set(Some(
+                (chrono::Utc::now() - chrono::Duration::minutes(1)).into(),
+            )),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        let res = request
+            .post("/api/transcription_jobs/claim")
+            .add_header(worker_key, worker_value)
+            .await;
+        let claimed: transcription_jobs::Model = res.json();
+        assert_eq!(claimed.id, job.id);
+        assert_eq!(claimed.worker_id, Some(worker.user.id));
+        assert_eq!(claimed.attempts, 2);
+
+        // Out of attempts, the job is given up
+        let mut item = claimed.into_active_model();
+        item.attempts = Set(3);
+        item.lease_expires_at = Set(Some(
+            (chrono::Utc::now() - chrono::Duration::minutes(1)).into(),
+        ));
+        item.update(&ctx.db).await.unwrap();
+        transcription_jobs::Entity::requeue_expired(&ctx.db, chrono::Utc::now().into(), 3)
+            .await
+            .unwrap();
+
+        let job = transcription_jobs::Entity::find_by_id(job.id)
+            .one(&ctx.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, transcription_jobs::STATUS_FAILED);
+        assert_eq!(job.worker_id, None);
+    })
+    .await;
+}
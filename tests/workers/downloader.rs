@@ -0,0 +1,240 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, prelude::*};
+use podscribe::{
+    app::App,
+    common::{audio::episode_audio_path, settings::Settings},
+    models::{downloads, episodes},
+    workers::downloader::{DownloadWorker, DownloadWorkerArgs},
+};
+use serial_test::serial;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use crate::requests::prepare_data;
+
+/// Stand-in for a podcast host: redirects to the actual file, fails the first
+/// `failures` requests and supports resuming via range requests.
+struct FileServer {
+    content: Vec<u8>,
+    failures: AtomicUsize,
+    ranges: Mutex<Vec<String>>,
+}
+
+async fn serve_file(State(server): State<Arc<FileServer>>, headers: HeaderMap) -> Response {
+    if server.failures.load(Ordering::SeqCst) > 0 {
+        server.failures.fetch_sub(1, Ordering::SeqCst);
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let Some(range) = headers.get(header::RANGE) else {
+        return server.content.clone().into_response();
+    };
+
+    let range = range.to_str().unwrap().to_string();
+    server.ranges.lock().unwrap().push(range.clone());
+    let start: usize = range
+        .trim_start_matches("bytes=")
+        .trim_end_matches('-')
+        .parse()
+        .unwrap();
+    (
+        StatusCode::PARTIAL_CONTENT,
+        [(
+            header::CONTENT_RANGE,
+            format!(
+                "bytes {}-{}/{}",
+                start,
+                server.content.len() - 1,
+                server.content.len()
+            ),
+        )],
+        server.content[start..].to_vec(),
+    )
+        .into_response()
+}
+
+async fn start_server(server: Arc<FileServer>) -> String {
+    let app = Router::new()
+        .route(
+            "/feed/episode.mp3",
+            get(|| async { Redirect::temporary("/files/episode.wav") }),
+        )
+        .route("/files/episode.wav", get(serve_file))
+        .route(
+            "/files/stalled.wav",
+            get(|| async {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+                StatusCode::OK
+            }),
+        )
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{}/feed/episode.mp3", address)
+}
+
+fn configure(ctx: &AppContext, max_size: u64) -> (AppContext, Settings) {
+    let directory = std::env::temp_dir().join(format!("podscribe-test-{}", uuid::Uuid::new_v4()));
+    let settings = serde_json::json!({
+        "downloads": {
+            "max_size": max_size,
+            "max_attempts": 3,
+            "retry_delay_ms": 1,
+            "read_timeout_ms": 200,
+            "directory": directory,
+        }
+    });
+
+    let mut ctx = ctx.clone();
+    ctx.config.settings = Some(settings);
+    let settings = Settings::from_config(&ctx.config).unwrap();
+    (ctx, settings)
+}
+
+async fn create_download(ctx: &AppContext, url: String) -> downloads::Model {
+    let episode = prepare_data::create_episode(ctx).await;
+    downloads::ActiveModel {
+        episode_id: Set(episode.id),
+        url: Set(url),
+        status: Set(downloads::STATUS_QUEUED.into()),
+        attempts: Set(0),
+        bytes_downloaded: Set(0),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_download_and_resume() {
+    let boot = boot_test::<App>().await.unwrap();
+    let (ctx, settings) = configure(&boot.app_context, 1024 * 1024);
+
+    let content = prepare_data::sine_wav();
+    let server = Arc::new(FileServer {
+        content: content.clone(),
+        failures: AtomicUsize::new(1),
+        ranges: Mutex::new(vec![]),
+    });
+    let download = create_download(&ctx, start_server(server.clone()).await).await;
+
+    // Pretend a previous run got interrupted after 1000 bytes
+    std::fs::create_dir_all(&settings.downloads.directory).unwrap();
+    std::fs::write(
+        settings.downloads.partial_path(download.id),
+        &content[..1000],
+    )
+    .unwrap();
+
+    DownloadWorker::build(&ctx)
+        .perform(DownloadWorkerArgs {
+            download_id: download.id,
+        })
+        .await
+        .unwrap();
+
+    let download = downloads::Entity::find_by_id(download.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(download.status, downloads::STATUS_DONE);
+    assert_eq!(download.attempts, 2);
+    assert_eq!(download.bytes_total, Some(content.len() as i64));
+    assert_eq!(*server.ranges.lock().unwrap(), vec!["bytes=1000-"]);
+
+    let episode = episodes::Entity::find_by_id(download.episode_id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(episode.has_audio_file);
+
+    let stored: Vec<u8> = ctx
+        .storage
+        .download(&episode_audio_path(episode.id))
+        .await
+        .unwrap();
+    assert_eq!(stored, content);
+    assert!(!settings.downloads.partial_path(download.id).exists());
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_large_files() {
+    let boot = boot_test::<App>().await.unwrap();
+    let (ctx, _) = configure(&boot.app_context, 100);
+
+    let server = Arc::new(FileServer {
+        content: prepare_data::sine_wav(),
+        failures: AtomicUsize::new(0),
+        ranges: Mutex::new(vec![]),
+    });
+    let download = create_download(&ctx, start_server(server).await).await;
+
+    DownloadWorker::build(&ctx)
+        .perform(DownloadWorkerArgs {
+            download_id: download.id,
+        })
+        .await
+        .unwrap();
+
+    let download = downloads::Entity::find_by_id(download.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(download.status, downloads::STATUS_FAILED);
+    assert_eq!(download.attempts, 1);
+
+    let episode = episodes::Entity::find_by_id(download.episode_id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!episode.has_audio_file);
+}
+
+#[tokio::test]
+#[serial]
+async fn gives_up_on_stalled_servers() {
+    let boot = boot_test::<App>().await.unwrap();
+    let (ctx, _) = configure(&boot.app_context, 1024 * 1024);
+
+    let server = Arc::new(FileServer {
+        content: prepare_data::sine_wav(),
+        failures: AtomicUsize::new(0),
+        ranges: Mutex::new(vec![]),
+    });
+    let url = start_server(server)
+        .await
+        .replace("/feed/episode.mp3", "/files/stalled.wav");
+    let download = create_download(&ctx, url).await;
+
+    DownloadWorker::build(&ctx)
+        .perform(DownloadWorkerArgs {
+            download_id: download.id,
+        })
+        .await
+        .unwrap();
+
+    let download = downloads::Entity::find_by_id(download.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(download.status, downloads::STATUS_FAILED);
+    assert_eq!(download.attempts, 3);
+}
@@ -0,0 +1,159 @@
+use axum::{routing::get, Router};
+use loco_rs::{bgworker::BackgroundWorker, prelude::*};
+use podscribe::{
+    app::App,
+    models::{_entities::episodes::Column, episodes, feeds, podcasts},
+    workers::feed_sync::{FeedSyncWorker, FeedSyncWorkerArgs},
+};
+use sea_orm::QueryOrder;
+use serial_test::serial;
+
+const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Podcast</title>
+    <link>https://example.com</link>
+    <description>A podcast</description>
+    <item>
+      <guid>episode-1</guid>
+      <title>First episode</title>
+      <link>https://example.com/1</link>
+      <description><![CDATA[<p>We talk about <b>things</b>.</p><p>Support us on example.com</p>]]></description>
+      <pubDate>Mon, 06 Jan 2025 10:00:00 +0000</pubDate>
+      <enclosure url="https://example.com/files/episode-1.mp3?source=rss" length="1000" type="audio/mpeg"/>
+    </item>
+    <item>
+      <guid>episode-2</guid>
+      <title>Second episode</title>
+      <link>https://example.com/2</link>
+      <description>Second</description>
+      <pubDate>Mon, 13 Jan 2025 10:00:00 +0000</pubDate>
+      <enclosure url="https://example.com/files/episode-2.mp3" length="1000" type="audio/mpeg"/>
+    </item>
+  </channel>
+</rss>"#;
+
+async fn start_server() -> String {
+    let app = Router::new().route("/feed.xml", get(|| async { FEED }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{}/feed.xml", address)
+}
+
+#[tokio::test]
+#[serial]
+async fn can_sync_feed() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    // Uploaded by hand before the feed was added, matched by its filename
+    let existing = episodes::ActiveModel {
+        title: Set("Old title".into()),
+        link: Set(String::new()),
+        description: Set("Written by hand".into()),
+        filename: Set("episode-2".into()),
+        has_audio_file: Set(true),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    let feed = feeds::ActiveModel {
+        url: Set(start_server().await),
+        poll_interval: Set(60),
+        crop_description_at: Set(Some("Support us".into())),
+        download_audio: Set(false),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    // Syncing twice must not create duplicates
+    for _ in 0..2 {
+        FeedSyncWorker::build(ctx)
+            .perform(FeedSyncWorkerArgs { feed_id: feed.id })
+            .await
+            .unwrap();
+    }
+
+    let episodes = episodes::Entity::find()
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(episodes.len(), 2);
+
+    assert_eq!(episodes[0].id, existing.id);
+    assert_eq!(episodes[0].external_id.as_deref(), Some("episode-2"));
+    assert_eq!(episodes[0].title, "Second episode");
+    assert_eq!(episodes[0].description, "Written by hand");
+
+    assert_eq!(episodes[1].external_id.as_deref(), Some("episode-1"));
+    assert_eq!(episodes[1].filename, "episode-1");
+    assert_eq!(episodes[1].description, "We talk about things.");
+    assert_eq!(
+        episodes[1].audio_url.as_deref(),
+        Some("https://example.com/files/episode-1.mp3?source=rss")
+    );
+    assert!(episodes[1].published_at.is_some());
+
+    let feed = feeds::Entity::find_by_id(feed.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(feed.last_polled_at.is_some());
+    assert_eq!(feed.last_error, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn keeps_episodes_of_feeds_with_the_same_guids_apart() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    let podcast = podcasts::ActiveModel {
+        title: Set("Podcast".into()),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+    let url = start_server().await;
+    let mut feed_ids = vec![];
+    for podcast_id in [None, Some(podcast.id)] {
+        let feed = feeds::ActiveModel {
+            url: Set(url.clone()),
+            poll_interval: Set(60),
+            download_audio: Set(false),
+            podcast_id: Set(podcast_id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+        feed_ids.push(feed.id);
+    }
+
+    for feed_id in feed_ids {
+        FeedSyncWorker::build(ctx)
+            .perform(FeedSyncWorkerArgs { feed_id })
+            .await
+            .unwrap();
+    }
+
+    let episodes = episodes::Entity::find()
+        .order_by_asc(Column::Id)
+        .all(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(episodes.len(), 4);
+    assert_eq!(
+        episodes.iter().map(|x| x.podcast_id).collect::<Vec<_>>(),
+        vec![None, None, Some(podcast.id), Some(podcast.id)]
+    );
+}
@@ -1 +1,4 @@
-
+mod downloader;
+mod feed_sync;
+mod transcriber;
+mod waveform;
@@ -0,0 +1,162 @@
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, prelude::*};
+use podscribe::{
+    app::App,
+    common::audio::episode_audio_path,
+    models::{_entities::parts, transcription_jobs},
+    workers::transcriber::{TranscriptionWorker, TranscriptionWorkerArgs},
+};
+use serial_test::serial;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::requests::prepare_data;
+
+/// Full JSON output of whisper.cpp, shortened to two segments
+const WHISPER_OUTPUT: &str = r#"{
+  "transcription": [
+    {
+      "offsets": { "from": 0, "to": 1200 },
+      "text": " Hello world.",
+      "tokens": [
+        { "text": "[_BEG_]", "offsets": { "from": 0, "to": 0 }, "p": 0.9 },
+        { "text": " Hello", "offsets": { "from": 0, "to": 500 }, "p": 0.9 },
+        { "text": " wor", "offsets": { "from": 500, "to": 800 }, "p": 0.8 },
+        { "text": "ld.", "offsets": { "from": 800, "to": 1200 }, "p": 0.6 }
+      ]
+    },
+    {
+      "offsets": { "from": 5000, "to": 6000 },
+      "text": " Bye.",
+      "tokens": [
+        { "text": " Bye.", "offsets": { "from": 5000, "to": 6000 }, "p": 0.7 }
+      ]
+    }
+  ]
+}"#;
+
+/// Writes a shell script standing in for whisper.cpp
+fn fake_command(script: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("podscribe-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("whisper-cli");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn configure(ctx: &AppContext, command: PathBuf) -> AppContext {
+    let mut ctx = ctx.clone();
+    ctx.config.settings = Some(serde_json::json!({
+        "transcription": {
+            "max_attempts": 1,
+            "local": {
+                "command": command,
+                "model": "ggml-large-v3.bin",
+                "language": "en",
+                "args": ["{input}"],
+            }
+        }
+    }));
+    ctx
+}
+
+async fn create_job(ctx: &AppContext) -> transcription_jobs::Model {
+    let episode = prepare_data::create_episode(ctx).await;
+    ctx.storage
+        .upload(
+            &episode_audio_path(episode.id),
+            &prepare_data::sine_wav().into(),
+        )
+        .await
+        .unwrap();
+
+    transcription_jobs::ActiveModel {
+        episode_id: Set(episode.id),
+        status: Set(transcription_jobs::STATUS_QUEUED.into()),
+        attempts: Set(0),
+        progress: Set(0.0),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+async fn run(ctx: &AppContext, job: &transcription_jobs::Model) -> transcription_jobs::Model {
+    TranscriptionWorker::build(ctx)
+        .perform(TranscriptionWorkerArgs { job_id: job.id })
+        .await
+        .unwrap();
+
+    transcription_jobs::Entity::find_by_id(job.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_transcribe_locally() {
+    let boot = boot_test::<App>().await.unwrap();
+    let command = fake_command(&format!(
+        "echo \"transcribing $1\" >&2\ncat <<'EOF'\n{}\nEOF",
+        WHISPER_OUTPUT
+    ));
+    let ctx = configure(&boot.app_context, command);
+    let job = create_job(&ctx).await;
+
+    let job = run(&ctx, &job).await;
+    assert_eq!(job.status, transcription_jobs::STATUS_DONE);
+    assert_eq!(job.attempts, 1);
+    assert!(job.log.unwrap().starts_with("transcribing "));
+
+    // The pause between the segments starts a new part
+    let parts = parts::Entity::find()
+        .filter(parts::Column::EpisodeId.eq(job.episode_id))
+        .all(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(
+        parts.iter().map(|x| x.text.as_str()).collect::<Vec<_>>(),
+        vec!["Hello world.", "Bye."]
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn keeps_log_of_failed_transcription() {
+    let boot = boot_test::<App>().await.unwrap();
+    let command = fake_command("echo 'model not found' >&2\nexit 3");
+    let ctx = configure(&boot.app_context, command);
+    let job = create_job(&ctx).await;
+
+    let job = run(&ctx, &job).await;
+    assert_eq!(job.status, transcription_jobs::STATUS_FAILED);
+    assert_eq!(job.log.as_deref(), Some("model not found\n"));
+    assert!(job.error.is_some());
+}
+
+#[tokio::test]
+#[serial]
+async fn releases_job_when_audio_is_missing() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = configure(&boot.app_context, fake_command("exit 0"));
+    let episode = prepare_data::create_episode(&ctx).await;
+    let job = transcription_jobs::ActiveModel {
+        episode_id: Set(episode.id),
+        status: Set(transcription_jobs::STATUS_QUEUED.into()),
+        attempts: Set(0),
+        progress: Set(0.0),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap();
+
+    // Not left running until the lease expires
+    let job = run(&ctx, &job).await;
+    assert_eq!(job.status, transcription_jobs::STATUS_FAILED);
+    assert!(job.lease_expires_at.is_none());
+    assert!(job.error.is_some());
+}
@@ -0,0 +1,44 @@
+use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
+use podscribe::{
+    app::App,
+    common::{audio::episode_audio_path, waveform::Waveform},
+    workers::waveform::{WaveformWorker, WaveformWorkerArgs},
+};
+use serial_test::serial;
+
+use crate::requests::prepare_data;
+
+#[tokio::test]
+#[serial]
+async fn can_compute_waveform() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    ctx.storage
+        .upload(&episode_audio_path(1), &prepare_data::sine_wav().into())
+        .await
+        .unwrap();
+
+    assert!(
+        WaveformWorker::perform_later(ctx, WaveformWorkerArgs { episode_id: 1 })
+            .await
+            .is_ok()
+    );
+
+    let content: Vec<u8> = ctx
+        .storage
+        .download(&Waveform::storage_path(1, 512))
+        .await
+        .unwrap();
+    let waveform: Waveform = serde_json::from_slice(&content).unwrap();
+    assert_eq!(waveform.sample_rate, 8000);
+    assert_eq!(waveform.samples_per_pixel, 512);
+    assert_eq!(waveform.length, 16);
+    assert_eq!(waveform.data.len(), 32);
+    assert!(waveform.data[0] < -50 && waveform.data[1] > 50);
+
+    // 0.25s to 0.5s are pixels 3 to 8 at 512 samples per pixel
+    let slice = waveform.slice(Some(0.25), Some(0.5));
+    assert_eq!(slice.length, 5);
+    assert_eq!(slice.data, waveform.data[6..16]);
+}